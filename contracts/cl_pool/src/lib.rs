@@ -0,0 +1,1409 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// An experimental pool of concentrated liquidity, after Uniswap V3: each position provides
+/// liquidity between two ticks only, earning the fees of swaps while the price is in its
+/// range. Positions are PSP34 tokens held by their owner.
+///
+/// It emits `Pair`'s `Sync`, `Mint`, `Burn` and `Swap` events, so indexers follow it like the
+/// other pools. Unlike them, tokens are pulled with `PSP22::transfer_from` rather than sent
+/// beforehand, as the amounts a position or swap takes are only known once computed.
+#[ink::contract]
+pub mod cl_pool {
+    use ink::prelude::{string::String, vec::Vec};
+    use ink::storage::Mapping;
+    use primitive_types::U256;
+    use uniswap_math::concentrated::{
+        self as math, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK,
+    };
+    use uniswap_math::MathError;
+
+    #[ink(event)]
+    pub struct Sync {
+        reserve_0: u128,
+        reserve_1: u128,
+        /// The emitting pool, so `Sync` can be filtered by topic across pools.
+        #[ink(topic)]
+        pair: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        owner: Option<AccountId>,
+        amount_0: Balance,
+        amount_1: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// Liquidity of the position minted to `to`.
+        liquidity: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        sender: Option<AccountId>,
+        amount_0: Balance,
+        amount_1: Balance,
+        /// Owner of the position, who the amounts are owed to until collected.
+        #[ink(topic)]
+        to: AccountId,
+        /// Liquidity taken out of the position.
+        liquidity: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Swap {
+        #[ink(topic)]
+        sender: Option<AccountId>,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// Reserves after the swap.
+        reserve_0: Balance,
+        reserve_1: Balance,
+    }
+
+    /// PSP34 transfer of a position; `from` is `None` when it is minted.
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    /// PSP34 approval of `operator` for the position `id`, or for all of `owner`'s when
+    /// `None`.
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        #[ink(topic)]
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    #[ink(event)]
+    pub struct Initialize {
+        sqrt_price: u128,
+        tick: i32,
+    }
+
+    #[ink(event)]
+    pub struct Collect {
+        #[ink(topic)]
+        id: u128,
+        #[ink(topic)]
+        to: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+    }
+
+    /// A PSP34 token id, encoded like openbrush's. Positions are `U128`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Id {
+        U8(u8),
+        U16(u16),
+        U32(u32),
+        U64(u64),
+        U128(u128),
+        Bytes(Vec<u8>),
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP34Error {
+        Custom(String),
+        SelfApprove,
+        NotApproved,
+        TokenExists,
+        TokenNotExists,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// The PSP34 messages of the position tokens, with openbrush's selectors.
+    #[ink::trait_definition]
+    #[allow(clippy::upper_case_acronyms)]
+    pub trait PSP34 {
+        #[ink(message)]
+        fn collection_id(&self) -> Id;
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32;
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            operator: AccountId,
+            id: Option<Id>,
+            approved: bool,
+        ) -> Result<(), PSP34Error>;
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error>;
+
+        #[ink(message)]
+        fn total_supply(&self) -> Balance;
+    }
+
+    /// Liquidity starting or ending at a tick. Fee growth is Q128.128, as `U256` words.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct TickInfo {
+        /// Liquidity of all positions bounded by the tick.
+        pub liquidity_gross: u128,
+        /// Liquidity added when the price crosses the tick upwards.
+        pub liquidity_net: i128,
+        /// Fee growth on the other side of the tick from the current price.
+        pub fee_growth_outside_0: [u64; 4],
+        pub fee_growth_outside_1: [u64; 4],
+    }
+
+    /// Liquidity a position provides between two ticks, and the fees it is owed.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Position {
+        pub tick_lower: i32,
+        pub tick_upper: i32,
+        pub liquidity: u128,
+        /// Fee growth inside the range when the position was last updated.
+        pub fee_growth_inside_0_last: [u64; 4],
+        pub fee_growth_inside_1_last: [u64; 4],
+        /// Fees earned and liquidity burned, waiting for `collect`.
+        pub tokens_owed_0: Balance,
+        pub tokens_owed_1: Balance,
+    }
+
+    /// Outcome of a swap before it is applied.
+    struct SwapState {
+        amount_remaining: Balance,
+        amount_out: Balance,
+        sqrt_price: u128,
+        tick: i32,
+        liquidity: u128,
+        /// Fee growth of the token swapped in.
+        fee_growth_global: U256,
+        /// Initialized ticks crossed, with that fee growth when they were.
+        crossed: Vec<(i32, U256)>,
+    }
+
+    /// No protocol fee is taken, and there is no price oracle.
+    #[ink(storage)]
+    pub struct ClPool {
+        factory: AccountId,
+        token_0: AccountId,
+        token_1: AccountId,
+        /// Swap fee in millionths of the amount in.
+        fee: u32,
+        tick_spacing: i32,
+        /// Square root of the price of token 0 in token 1, in Q64.64; 0 until
+        /// `initialize_price`.
+        sqrt_price: u128,
+        /// The greatest tick at or below `sqrt_price`.
+        tick: i32,
+        /// Liquidity of the positions in range.
+        liquidity: u128,
+        /// Fees earned per unit of liquidity over the pool's life, in Q128.128.
+        fee_growth_global_0: [u64; 4],
+        fee_growth_global_1: [u64; 4],
+        /// Tokens held for positions, owed amounts included.
+        reserve_0: Balance,
+        reserve_1: Balance,
+        /// Block timestamp, in milliseconds, of the last reserves update.
+        block_timestamp_last: u128,
+        /// Id of the next position; ids start at 1.
+        next_position_id: u128,
+        ticks: Mapping<i32, TickInfo>,
+        /// Initialized ticks, a bit per multiple of `tick_spacing`, by word.
+        tick_bitmap: Mapping<i32, u128>,
+        positions: Mapping<u128, Position>,
+        owners: Mapping<u128, AccountId>,
+        balances: Mapping<AccountId, u32>,
+        token_approvals: Mapping<u128, AccountId>,
+        operator_approvals: Mapping<(AccountId, AccountId), ()>,
+    }
+
+    impl ClPool {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId, token_0: AccountId, token_1: AccountId, fee: u32) -> Self {
+            let tick_spacing = math::tick_spacing(fee).expect("UniswapV3: INVALID_FEE");
+            Self {
+                factory,
+                token_0,
+                token_1,
+                fee,
+                tick_spacing,
+                sqrt_price: 0,
+                tick: 0,
+                liquidity: 0,
+                fee_growth_global_0: [0; 4],
+                fee_growth_global_1: [0; 4],
+                reserve_0: 0,
+                reserve_1: 0,
+                block_timestamp_last: 0,
+                next_position_id: 1,
+                ticks: Mapping::default(),
+                tick_bitmap: Mapping::default(),
+                positions: Mapping::default(),
+                owners: Mapping::default(),
+                balances: Mapping::default(),
+                token_approvals: Mapping::default(),
+                operator_approvals: Mapping::default(),
+            }
+        }
+
+        /// Constructor used by `UniswapCore::create_cl_pool`, which sets the tokens and fee
+        /// right after through `initialize`.
+        #[ink(constructor)]
+        pub fn create() -> Self {
+            let zero = AccountId::from([0x0; 32]);
+            Self::new(Self::env().caller(), zero, zero, 3_000)
+        }
+
+        #[ink(message)]
+        pub fn initialize(&mut self, token_0: AccountId, token_1: AccountId, fee: u32) {
+            assert!(self.env().caller() == self.factory, "UniswapV3: FORBIDDEN");
+            assert!(
+                self.token_0 == AccountId::from([0x0; 32]),
+                "UniswapV3: ALREADY_INITIALIZED"
+            );
+            self.tick_spacing = math::tick_spacing(fee).expect("UniswapV3: INVALID_FEE");
+            self.fee = fee;
+            self.token_0 = token_0;
+            self.token_1 = token_1;
+        }
+
+        /// Sets the starting price, which anyone may do once before liquidity is added.
+        #[ink(message)]
+        pub fn initialize_price(&mut self, sqrt_price: u128) {
+            assert!(self.sqrt_price == 0, "UniswapV3: ALREADY_INITIALIZED");
+            let tick = math::tick_at_sqrt_price(sqrt_price).expect("UniswapV3: INVALID_PRICE");
+            self.sqrt_price = sqrt_price;
+            self.tick = tick;
+            self.env().emit_event(Initialize { sqrt_price, tick });
+        }
+
+        /// Swaps this pool's code for `code_hash`, keeping its storage. Only the factory
+        /// can upgrade a pool, see `UniswapCore::upgrade_pair`.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) {
+            assert!(self.env().caller() == self.factory, "UniswapV3: FORBIDDEN");
+            self.env()
+                .set_code_hash(&code_hash)
+                .expect("UniswapV3: SET_CODE_FAILED");
+        }
+
+        #[ink(message)]
+        pub fn get_reserves(&self) -> (u128, u128, u128) {
+            (self.reserve_0, self.reserve_1, self.block_timestamp_last)
+        }
+
+        /// The pool itself: liquidity is held as PSP34 positions rather than a PSP22 token.
+        #[ink(message)]
+        pub fn lp_token(&self) -> AccountId {
+            self.env().account_id()
+        }
+
+        /// Liquidity of the positions in range, in place of an LP token supply.
+        #[ink(message)]
+        pub fn get_total_supply(&self) -> Balance {
+            self.liquidity
+        }
+
+        #[ink(message)]
+        pub fn token_0(&self) -> AccountId {
+            self.token_0
+        }
+
+        #[ink(message)]
+        pub fn token_1(&self) -> AccountId {
+            self.token_1
+        }
+
+        /// Swap fee in millionths of the amount in, e.g. 3000 for 0.3%.
+        #[ink(message)]
+        pub fn fee(&self) -> u32 {
+            self.fee
+        }
+
+        #[ink(message)]
+        pub fn stable(&self) -> bool {
+            false
+        }
+
+        #[ink(message)]
+        pub fn weight_0(&self) -> u32 {
+            50
+        }
+
+        /// Tells concentrated liquidity pools apart from pairs, which lack this message.
+        #[ink(message)]
+        pub fn concentrated(&self) -> bool {
+            true
+        }
+
+        /// Ticks positions may start and end at are multiples of this.
+        #[ink(message)]
+        pub fn tick_spacing(&self) -> i32 {
+            self.tick_spacing
+        }
+
+        /// Square root of the price in Q64.64, and its tick.
+        #[ink(message)]
+        pub fn slot_0(&self) -> (u128, i32) {
+            (self.sqrt_price, self.tick)
+        }
+
+        /// Liquidity of the positions in range.
+        #[ink(message)]
+        pub fn liquidity(&self) -> u128 {
+            self.liquidity
+        }
+
+        #[ink(message)]
+        pub fn position(&self, id: u128) -> Option<Position> {
+            self.positions.get(id)
+        }
+
+        #[ink(message)]
+        pub fn tick_info(&self, tick: i32) -> Option<TickInfo> {
+            self.ticks.get(tick)
+        }
+
+        /// Output of swapping `amount_in` of `token_in`, as far as the liquidity goes.
+        #[ink(message)]
+        pub fn get_amount_out(
+            &self,
+            amount_in: Balance,
+            token_in: AccountId,
+        ) -> Result<Balance, MathError> {
+            let zero_for_one = if token_in == self.token_0 {
+                true
+            } else if token_in == self.token_1 {
+                false
+            } else {
+                return Err(MathError::InvalidToken);
+            };
+            if self.sqrt_price == 0 {
+                return Err(MathError::InsufficientLiquidity);
+            }
+            if amount_in == 0 {
+                return Err(MathError::InsufficientInputAmount);
+            }
+            let limit = if zero_for_one {
+                MIN_SQRT_PRICE
+            } else {
+                MAX_SQRT_PRICE
+            };
+            Ok(self
+                .compute_swap(zero_for_one, amount_in, limit)?
+                .amount_out)
+        }
+
+        /// Opens a position of as much liquidity between `tick_lower` and `tick_upper` as
+        /// the desired amounts allow, minted to `to`. The amounts are pulled from the caller,
+        /// who must have approved the pool, rounded up; this may be a unit more than desired.
+        /// Returns the position's id, its liquidity and the amounts taken.
+        #[ink(message)]
+        pub fn mint(
+            &mut self,
+            tick_lower: i32,
+            tick_upper: i32,
+            amount_0_desired: Balance,
+            amount_1_desired: Balance,
+            to: AccountId,
+        ) -> (u128, u128, Balance, Balance) {
+            assert!(!self.is_paused(), "UniswapV3: PAUSED");
+            assert!(self.sqrt_price != 0, "UniswapV3: NOT_INITIALIZED");
+            assert!(
+                tick_lower < tick_upper
+                    && tick_lower >= MIN_TICK
+                    && tick_upper <= MAX_TICK
+                    && tick_lower % self.tick_spacing == 0
+                    && tick_upper % self.tick_spacing == 0,
+                "UniswapV3: INVALID_TICKS"
+            );
+            let liquidity = math::liquidity_for_amounts(
+                self.sqrt_price,
+                Self::sqrt_price_at(tick_lower),
+                Self::sqrt_price_at(tick_upper),
+                amount_0_desired,
+                amount_1_desired,
+            )
+            .expect("UniswapV3: OVERFLOW");
+            assert!(liquidity > 0, "UniswapV3: INSUFFICIENT_LIQUIDITY_MINTED");
+            let delta = i128::try_from(liquidity).expect("UniswapV3: OVERFLOW");
+
+            let mut position = Position {
+                tick_lower,
+                tick_upper,
+                ..Default::default()
+            };
+            let (amount_0, amount_1) = self.modify_position(&mut position, delta);
+            let id = self.next_position_id;
+            self.next_position_id += 1;
+            self.positions.insert(id, &position);
+            self.mint_position(to, id);
+
+            let caller = self.env().caller();
+            if amount_0 > 0 {
+                self.safe_transfer_from(self.token_0, caller, amount_0);
+            }
+            if amount_1 > 0 {
+                self.safe_transfer_from(self.token_1, caller, amount_1);
+            }
+            self.reserve_0 = self
+                .reserve_0
+                .checked_add(amount_0)
+                .expect("UniswapV3: OVERFLOW");
+            self.reserve_1 = self
+                .reserve_1
+                .checked_add(amount_1)
+                .expect("UniswapV3: OVERFLOW");
+
+            self.update();
+            self.env().emit_event(Mint {
+                owner: Some(caller),
+                amount_0,
+                amount_1,
+                to,
+                liquidity,
+            });
+            (id, liquidity, amount_0, amount_1)
+        }
+
+        /// Takes `liquidity` out of position `id`, owing its worth and the fees earned to
+        /// the position until `collect`. Burning nothing only settles the fees. The caller
+        /// must own the position or be approved for it.
+        #[ink(message)]
+        pub fn burn(&mut self, id: u128, liquidity: u128) -> (Balance, Balance) {
+            let caller = self.env().caller();
+            let owner = self.owners.get(id).expect("UniswapV3: NO_POSITION");
+            assert!(
+                self.is_approved_or_owner(caller, owner, id),
+                "UniswapV3: FORBIDDEN"
+            );
+            let mut position = self.positions.get(id).expect("UniswapV3: NO_POSITION");
+            assert!(
+                liquidity <= position.liquidity,
+                "UniswapV3: INSUFFICIENT_LIQUIDITY_BURNED"
+            );
+
+            let (amount_0, amount_1) = if position.liquidity > 0 {
+                let delta = i128::try_from(liquidity).expect("UniswapV3: OVERFLOW");
+                self.modify_position(&mut position, -delta)
+            } else {
+                (0, 0)
+            };
+            position.tokens_owed_0 = position.tokens_owed_0.saturating_add(amount_0);
+            position.tokens_owed_1 = position.tokens_owed_1.saturating_add(amount_1);
+            self.positions.insert(id, &position);
+
+            self.env().emit_event(Burn {
+                sender: Some(caller),
+                amount_0,
+                amount_1,
+                to: owner,
+                liquidity,
+            });
+            (amount_0, amount_1)
+        }
+
+        /// Pays what position `id` is owed to `to`. The caller must own the position or be
+        /// approved for it.
+        #[ink(message)]
+        pub fn collect(&mut self, id: u128, to: AccountId) -> (Balance, Balance) {
+            let owner = self.owners.get(id).expect("UniswapV3: NO_POSITION");
+            assert!(
+                self.is_approved_or_owner(self.env().caller(), owner, id),
+                "UniswapV3: FORBIDDEN"
+            );
+            let mut position = self.positions.get(id).expect("UniswapV3: NO_POSITION");
+            let amount_0 = core::mem::take(&mut position.tokens_owed_0);
+            let amount_1 = core::mem::take(&mut position.tokens_owed_1);
+            self.positions.insert(id, &position);
+
+            self.reserve_0 = self
+                .reserve_0
+                .checked_sub(amount_0)
+                .expect("UniswapV3: INSUFFICIENT_LIQUIDITY");
+            self.reserve_1 = self
+                .reserve_1
+                .checked_sub(amount_1)
+                .expect("UniswapV3: INSUFFICIENT_LIQUIDITY");
+            if amount_0 > 0 {
+                self.safe_transfer(self.token_0, to, amount_0);
+            }
+            if amount_1 > 0 {
+                self.safe_transfer(self.token_1, to, amount_1);
+            }
+
+            self.update();
+            self.env().emit_event(Collect {
+                id,
+                to,
+                amount_0,
+                amount_1,
+            });
+            (amount_0, amount_1)
+        }
+
+        /// Swaps `amount_in` of token 0 for token 1 when `zero_for_one`, else the other way,
+        /// until the amount runs out or the price reaches `sqrt_price_limit`. What is used of
+        /// the amount is pulled from the caller, who must have approved the pool. Returns
+        /// the amount sent to `to`.
+        #[ink(message)]
+        pub fn swap(
+            &mut self,
+            zero_for_one: bool,
+            amount_in: Balance,
+            sqrt_price_limit: u128,
+            to: AccountId,
+        ) -> Balance {
+            assert!(!self.is_paused(), "UniswapV3: PAUSED");
+            assert!(self.sqrt_price != 0, "UniswapV3: NOT_INITIALIZED");
+            assert!(amount_in > 0, "UniswapV3: INSUFFICIENT_INPUT_AMOUNT");
+            let valid_limit = if zero_for_one {
+                sqrt_price_limit < self.sqrt_price && sqrt_price_limit >= MIN_SQRT_PRICE
+            } else {
+                sqrt_price_limit > self.sqrt_price && sqrt_price_limit <= MAX_SQRT_PRICE
+            };
+            assert!(valid_limit, "UniswapV3: SQRT_PRICE_LIMIT");
+            assert!(
+                to != self.token_0 && to != self.token_1,
+                "UniswapV3: INVALID_TO_ADDRESS"
+            );
+
+            let state = self
+                .compute_swap(zero_for_one, amount_in, sqrt_price_limit)
+                .expect("UniswapV3: SWAP_FAILED");
+            assert!(
+                state.amount_out > 0,
+                "UniswapV3: INSUFFICIENT_OUTPUT_AMOUNT"
+            );
+            let amount_in = amount_in - state.amount_remaining;
+            let amount_out = state.amount_out;
+
+            let (global_0, global_1) = if zero_for_one {
+                (state.fee_growth_global, U256(self.fee_growth_global_1))
+            } else {
+                (U256(self.fee_growth_global_0), state.fee_growth_global)
+            };
+            for (tick, fee_growth_global) in state.crossed {
+                let (global_0, global_1) = if zero_for_one {
+                    (fee_growth_global, global_1)
+                } else {
+                    (global_0, fee_growth_global)
+                };
+                self.cross_tick(tick, global_0, global_1);
+            }
+            self.fee_growth_global_0 = global_0.0;
+            self.fee_growth_global_1 = global_1.0;
+            self.sqrt_price = state.sqrt_price;
+            self.tick = state.tick;
+            self.liquidity = state.liquidity;
+
+            let (token_in, token_out) = if zero_for_one {
+                (self.token_0, self.token_1)
+            } else {
+                (self.token_1, self.token_0)
+            };
+            self.safe_transfer_from(token_in, self.env().caller(), amount_in);
+            self.safe_transfer(token_out, to, amount_out);
+
+            let (amount_0_in, amount_1_in, amount_0_out, amount_1_out) = if zero_for_one {
+                (amount_in, 0, 0, amount_out)
+            } else {
+                (0, amount_in, amount_out, 0)
+            };
+            self.reserve_0 = (self.reserve_0 + amount_0_in)
+                .checked_sub(amount_0_out)
+                .expect("UniswapV3: INSUFFICIENT_LIQUIDITY");
+            self.reserve_1 = (self.reserve_1 + amount_1_in)
+                .checked_sub(amount_1_out)
+                .expect("UniswapV3: INSUFFICIENT_LIQUIDITY");
+
+            self.update();
+            self.env().emit_event(Swap {
+                sender: Some(self.env().caller()),
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+                to,
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
+            });
+            amount_out
+        }
+
+        /// Asks the factory whether its guardian has halted trading on this pool.
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            ext::is_paused(self.factory, self.env().account_id())
+        }
+
+        fn sqrt_price_at(tick: i32) -> u128 {
+            math::sqrt_price_at_tick(tick).expect("UniswapV3: INVALID_TICKS")
+        }
+
+        /// Runs a swap against the current state without writing it, stepping from one
+        /// initialized tick to the next.
+        fn compute_swap(
+            &self,
+            zero_for_one: bool,
+            amount_in: Balance,
+            sqrt_price_limit: u128,
+        ) -> Result<SwapState, MathError> {
+            let mut state = SwapState {
+                amount_remaining: amount_in,
+                amount_out: 0,
+                sqrt_price: self.sqrt_price,
+                tick: self.tick,
+                liquidity: self.liquidity,
+                fee_growth_global: U256(if zero_for_one {
+                    self.fee_growth_global_0
+                } else {
+                    self.fee_growth_global_1
+                }),
+                crossed: Vec::new(),
+            };
+            while state.amount_remaining > 0 && state.sqrt_price != sqrt_price_limit {
+                let (next_tick, initialized) = math::next_initialized_tick(
+                    |word| self.tick_bitmap.get(word).unwrap_or(0),
+                    state.tick,
+                    self.tick_spacing,
+                    zero_for_one,
+                );
+                let next_tick = next_tick.clamp(MIN_TICK, MAX_TICK);
+                let sqrt_price_next = math::sqrt_price_at_tick(next_tick)?;
+                let target = if zero_for_one {
+                    sqrt_price_next.max(sqrt_price_limit)
+                } else {
+                    sqrt_price_next.min(sqrt_price_limit)
+                };
+
+                let step = math::compute_swap_step(
+                    state.sqrt_price,
+                    target,
+                    state.liquidity,
+                    state.amount_remaining,
+                    self.fee,
+                )?;
+                state.amount_remaining -= step.amount_in + step.fee_amount;
+                state.amount_out = state
+                    .amount_out
+                    .checked_add(step.amount_out)
+                    .ok_or(MathError::Overflow)?;
+                state.fee_growth_global = state
+                    .fee_growth_global
+                    .overflowing_add(math::fee_growth(step.fee_amount, state.liquidity))
+                    .0;
+
+                if step.sqrt_price_next == sqrt_price_next {
+                    if initialized {
+                        let net = self
+                            .ticks
+                            .get(next_tick)
+                            .map_or(0, |tick| tick.liquidity_net);
+                        let net = if zero_for_one { -net } else { net };
+                        state.liquidity = math::add_delta(state.liquidity, net)?;
+                        state.crossed.push((next_tick, state.fee_growth_global));
+                    }
+                    state.tick = if zero_for_one {
+                        next_tick - 1
+                    } else {
+                        next_tick
+                    };
+                } else if step.sqrt_price_next != state.sqrt_price {
+                    state.tick = math::tick_at_sqrt_price(step.sqrt_price_next)?;
+                }
+                state.sqrt_price = step.sqrt_price_next;
+            }
+            Ok(state)
+        }
+
+        /// Flips the fee growth outside `tick` to the other side as the price crosses it.
+        fn cross_tick(&mut self, tick: i32, global_0: U256, global_1: U256) {
+            let mut info = self.ticks.get(tick).expect("UniswapV3: NO_TICK");
+            info.fee_growth_outside_0 = global_0
+                .overflowing_sub(U256(info.fee_growth_outside_0))
+                .0
+                 .0;
+            info.fee_growth_outside_1 = global_1
+                .overflowing_sub(U256(info.fee_growth_outside_1))
+                .0
+                 .0;
+            self.ticks.insert(tick, &info);
+        }
+
+        /// Adds `delta` liquidity to `position`, settling the fees it earned so far. Returns
+        /// the amounts its liquidity changes by, rounded up when adding.
+        fn modify_position(&mut self, position: &mut Position, delta: i128) -> (Balance, Balance) {
+            let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+            let flipped_lower = self.update_tick(tick_lower, delta, false);
+            let flipped_upper = self.update_tick(tick_upper, delta, true);
+
+            let (inside_0, inside_1) = self.fee_growth_inside(tick_lower, tick_upper);
+            let owed_0 = math::fees_owed(
+                inside_0,
+                U256(position.fee_growth_inside_0_last),
+                position.liquidity,
+            );
+            let owed_1 = math::fees_owed(
+                inside_1,
+                U256(position.fee_growth_inside_1_last),
+                position.liquidity,
+            );
+            position.tokens_owed_0 = position.tokens_owed_0.saturating_add(owed_0);
+            position.tokens_owed_1 = position.tokens_owed_1.saturating_add(owed_1);
+            position.fee_growth_inside_0_last = inside_0.0;
+            position.fee_growth_inside_1_last = inside_1.0;
+            position.liquidity =
+                math::add_delta(position.liquidity, delta).expect("UniswapV3: LIQUIDITY");
+
+            // Ticks no position bounds any more are cleared once their fees are settled.
+            if flipped_lower && delta < 0 {
+                self.ticks.remove(tick_lower);
+            }
+            if flipped_upper && delta < 0 {
+                self.ticks.remove(tick_upper);
+            }
+
+            let (sqrt_lower, sqrt_upper) = (
+                Self::sqrt_price_at(tick_lower),
+                Self::sqrt_price_at(tick_upper),
+            );
+            let (liquidity, round_up) = (delta.unsigned_abs(), delta > 0);
+            let amounts = if self.tick < tick_lower {
+                math::amount_0_delta(sqrt_lower, sqrt_upper, liquidity, round_up).map(|a| (a, 0))
+            } else if self.tick < tick_upper {
+                self.liquidity =
+                    math::add_delta(self.liquidity, delta).expect("UniswapV3: LIQUIDITY");
+                math::amount_0_delta(self.sqrt_price, sqrt_upper, liquidity, round_up).and_then(
+                    |amount_0| {
+                        math::amount_1_delta(sqrt_lower, self.sqrt_price, liquidity, round_up)
+                            .map(|amount_1| (amount_0, amount_1))
+                    },
+                )
+            } else {
+                math::amount_1_delta(sqrt_lower, sqrt_upper, liquidity, round_up).map(|a| (0, a))
+            };
+            amounts.expect("UniswapV3: OVERFLOW")
+        }
+
+        /// Adds `delta` to the liquidity bounded by `tick`, as the upper bound when `upper`.
+        /// Returns whether the tick went from unused to used or back.
+        fn update_tick(&mut self, tick: i32, delta: i128, upper: bool) -> bool {
+            let mut info = self.ticks.get(tick).unwrap_or_default();
+            let gross_before = info.liquidity_gross;
+            info.liquidity_gross =
+                math::add_delta(gross_before, delta).expect("UniswapV3: LIQUIDITY");
+            // Growth before a tick is first used is taken to have happened below it.
+            if gross_before == 0 && tick <= self.tick {
+                info.fee_growth_outside_0 = self.fee_growth_global_0;
+                info.fee_growth_outside_1 = self.fee_growth_global_1;
+            }
+            info.liquidity_net = if upper {
+                info.liquidity_net.checked_sub(delta)
+            } else {
+                info.liquidity_net.checked_add(delta)
+            }
+            .expect("UniswapV3: LIQUIDITY");
+            self.ticks.insert(tick, &info);
+
+            let flipped = (info.liquidity_gross == 0) != (gross_before == 0);
+            if flipped {
+                let (word, bit) = math::bitmap_position(tick, self.tick_spacing);
+                let bits = self.tick_bitmap.get(word).unwrap_or(0) ^ (1 << bit);
+                if bits == 0 {
+                    self.tick_bitmap.remove(word);
+                } else {
+                    self.tick_bitmap.insert(word, &bits);
+                }
+            }
+            flipped
+        }
+
+        fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+            let lower = self.ticks.get(tick_lower).unwrap_or_default();
+            let upper = self.ticks.get(tick_upper).unwrap_or_default();
+            let inside = |global: [u64; 4], lower: [u64; 4], upper: [u64; 4]| {
+                math::fee_growth_inside(
+                    tick_lower,
+                    tick_upper,
+                    self.tick,
+                    U256(global),
+                    U256(lower),
+                    U256(upper),
+                )
+            };
+            (
+                inside(
+                    self.fee_growth_global_0,
+                    lower.fee_growth_outside_0,
+                    upper.fee_growth_outside_0,
+                ),
+                inside(
+                    self.fee_growth_global_1,
+                    lower.fee_growth_outside_1,
+                    upper.fee_growth_outside_1,
+                ),
+            )
+        }
+
+        fn update(&mut self) {
+            self.block_timestamp_last = self.env().block_timestamp() as u128;
+            self.env().emit_event(Sync {
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
+                pair: self.env().account_id(),
+            });
+        }
+
+        fn mint_position(&mut self, to: AccountId, id: u128) {
+            self.owners.insert(id, &to);
+            self.balances
+                .insert(to, &(self.balances.get(to).unwrap_or(0) + 1));
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                id: Id::U128(id),
+            });
+        }
+
+        fn is_approved_or_owner(&self, account: AccountId, owner: AccountId, id: u128) -> bool {
+            account == owner
+                || self.token_approvals.get(id) == Some(account)
+                || self.operator_approvals.contains((owner, account))
+        }
+
+        /// Sends `value` of `token` held by the pool to `to`.
+        fn safe_transfer(&self, token: AccountId, to: AccountId, value: Balance) {
+            assert!(
+                ext::transfer(token, to, value),
+                "UniswapV3: TRANSFER_FAILED"
+            );
+        }
+
+        /// Pulls `value` of `token` from `from`, who must have approved the pool.
+        fn safe_transfer_from(&self, token: AccountId, from: AccountId, value: Balance) {
+            assert!(
+                ext::transfer_from(token, from, self.env().account_id(), value),
+                "UniswapV3: TRANSFER_FROM_FAILED"
+            );
+        }
+    }
+
+    /// The position an `Id` stands for, if any.
+    fn position_id(id: &Id) -> Option<u128> {
+        match id {
+            Id::U128(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    impl PSP34 for ClPool {
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            Id::Bytes(AsRef::<[u8]>::as_ref(&self.env().account_id()).to_vec())
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.balances.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.owners.get(position_id(&id)?)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+            self.operator_approvals.contains((owner, operator))
+                || id
+                    .as_ref()
+                    .and_then(position_id)
+                    .is_some_and(|id| self.token_approvals.get(id) == Some(operator))
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            operator: AccountId,
+            id: Option<Id>,
+            approved: bool,
+        ) -> Result<(), PSP34Error> {
+            let caller = self.env().caller();
+            if operator == caller {
+                return Err(PSP34Error::SelfApprove);
+            }
+            let owner = match &id {
+                Some(id) => {
+                    let position = position_id(id).ok_or(PSP34Error::TokenNotExists)?;
+                    let owner = self
+                        .owners
+                        .get(position)
+                        .ok_or(PSP34Error::TokenNotExists)?;
+                    if caller != owner && !self.operator_approvals.contains((owner, caller)) {
+                        return Err(PSP34Error::NotApproved);
+                    }
+                    if approved {
+                        self.token_approvals.insert(position, &operator);
+                    } else {
+                        self.token_approvals.remove(position);
+                    }
+                    owner
+                }
+                None => {
+                    if approved {
+                        self.operator_approvals.insert((caller, operator), &());
+                    } else {
+                        self.operator_approvals.remove((caller, operator));
+                    }
+                    caller
+                }
+            };
+            self.env().emit_event(Approval {
+                owner,
+                operator,
+                id,
+                approved,
+            });
+            Ok(())
+        }
+
+        /// `data` is ignored, and no check is made that a contract `to` accepts positions.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error> {
+            let _ = data;
+            let position = position_id(&id).ok_or(PSP34Error::TokenNotExists)?;
+            let owner = self
+                .owners
+                .get(position)
+                .ok_or(PSP34Error::TokenNotExists)?;
+            if !self.is_approved_or_owner(self.env().caller(), owner, position) {
+                return Err(PSP34Error::NotApproved);
+            }
+            self.token_approvals.remove(position);
+            self.balances
+                .insert(owner, &(self.balances.get(owner).unwrap_or(0) - 1));
+            self.balances
+                .insert(to, &(self.balances.get(to).unwrap_or(0) + 1));
+            self.owners.insert(position, &to);
+            self.env().emit_event(Transfer {
+                from: Some(owner),
+                to: Some(to),
+                id,
+            });
+            Ok(())
+        }
+
+        /// Positions ever minted: burning a position's liquidity keeps its token.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.next_position_id - 1
+        }
+    }
+
+    #[cfg(not(test))]
+    use uniswap_support::ext;
+    #[cfg(test)]
+    use uniswap_support::mock as ext;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use uniswap_support::{layout, mock};
+
+        /// Packed fields of the deployed storage, in order.
+        const PACKED_FIELDS: &[&str] = &[
+            "factory",
+            "token_0",
+            "token_1",
+            "fee",
+            "tick_spacing",
+            "sqrt_price",
+            "tick",
+            "liquidity",
+            "fee_growth_global_0",
+            "fee_growth_global_1",
+            "reserve_0",
+            "reserve_1",
+            "block_timestamp_last",
+            "next_position_id",
+        ];
+
+        #[test]
+        fn storage_layout_is_upgrade_compatible() {
+            layout::assert_upgrade_compatible::<ClPool>(PACKED_FIELDS, &[]);
+        }
+
+        const FACTORY: [u8; 32] = [0x10; 32];
+        const POOL: [u8; 32] = [0x11; 32];
+        const TOKEN_0: [u8; 32] = [0x13; 32];
+        const TOKEN_1: [u8; 32] = [0x14; 32];
+
+        const E12: Balance = 1_000_000_000_000;
+
+        /// Square root of a price of one.
+        const ONE: u128 = 1 << 64;
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        /// A 0.3% pool of `TOKEN_0`/`TOKEN_1` at a price of one, called by Alice, who holds
+        /// a million of both.
+        fn setup() -> ClPool {
+            let alice = accounts().alice;
+            mock::mint(TOKEN_0.into(), alice, 1_000_000 * E12);
+            mock::mint(TOKEN_1.into(), alice, 1_000_000 * E12);
+            test::set_callee::<DefaultEnvironment>(POOL.into());
+            test::set_caller::<DefaultEnvironment>(alice);
+            let mut pool = ClPool::new(FACTORY.into(), TOKEN_0.into(), TOKEN_1.into(), 3_000);
+            pool.initialize_price(ONE);
+            pool
+        }
+
+        /// Positions overlapping around the price, and one entirely above it.
+        const RANGES: [(i32, i32); 4] = [(-600, 600), (-120, 120), (-1_200, -60), (180, 960)];
+
+        /// Mints a position over each of `RANGES` for up to a thousand of both tokens, and
+        /// returns their ranges with their liquidity.
+        fn add_positions(pool: &mut ClPool) -> Vec<(i32, i32, u128)> {
+            RANGES
+                .iter()
+                .map(|&(lower, upper)| {
+                    let (_, liquidity, _, _) =
+                        pool.mint(lower, upper, 1_000 * E12, 1_000 * E12, accounts().alice);
+                    (lower, upper, liquidity)
+                })
+                .collect()
+        }
+
+        fn float_sqrt_price(tick: i32) -> f64 {
+            1.0001f64.powf(tick as f64 / 2.0)
+        }
+
+        /// Uniswap V3's exact input swap in floating point, with liquidity ranges and the
+        /// price as real numbers. Returns the amount out and the final square root price.
+        fn reference_swap(
+            positions: &[(i32, i32, u128)],
+            sqrt_price: f64,
+            amount_in: f64,
+            fee: f64,
+            zero_for_one: bool,
+        ) -> (f64, f64) {
+            let mut bounds: Vec<f64> = positions
+                .iter()
+                .flat_map(|&(lower, upper, _)| [float_sqrt_price(lower), float_sqrt_price(upper)])
+                .collect();
+            bounds.sort_by(f64::total_cmp);
+            let (mut price, mut remaining, mut amount_out) = (sqrt_price, amount_in, 0.0);
+            while remaining > 0.0 {
+                // Liquidity of the ranges the price moves through next.
+                let liquidity: f64 = positions
+                    .iter()
+                    .filter(|&&(lower, upper, _)| {
+                        let (lower, upper) = (float_sqrt_price(lower), float_sqrt_price(upper));
+                        if zero_for_one {
+                            lower < price && price <= upper
+                        } else {
+                            lower <= price && price < upper
+                        }
+                    })
+                    .map(|&(_, _, liquidity)| liquidity as f64)
+                    .sum();
+                let next = if zero_for_one {
+                    bounds.iter().rev().find(|&&bound| bound < price)
+                } else {
+                    bounds.iter().find(|&&bound| bound > price)
+                };
+                let Some(&next) = next else { break };
+                let available = remaining * (1.0 - fee);
+                let needed = if zero_for_one {
+                    liquidity * (1.0 / next - 1.0 / price)
+                } else {
+                    liquidity * (next - price)
+                };
+                let new_price = if available >= needed {
+                    remaining -= needed / (1.0 - fee);
+                    next
+                } else {
+                    remaining = 0.0;
+                    if zero_for_one {
+                        liquidity * price / (liquidity + available * price)
+                    } else {
+                        price + available / liquidity
+                    }
+                };
+                amount_out += if zero_for_one {
+                    liquidity * (price - new_price)
+                } else {
+                    liquidity * (1.0 / price - 1.0 / new_price)
+                };
+                price = new_price;
+            }
+            (amount_out, price)
+        }
+
+        fn assert_close(actual: u128, expected: f64) {
+            assert!(
+                (actual as f64 - expected).abs() <= expected * 1e-9,
+                "{actual} vs {expected}"
+            );
+        }
+
+        #[ink::test]
+        fn positions_take_their_share_of_each_token() {
+            let mut pool = setup();
+            let alice = accounts().alice;
+
+            let (id, liquidity, amount_0, amount_1) =
+                pool.mint(-600, 600, 1_000 * E12, 1_000 * E12, alice);
+
+            // At a price of one, an even range takes as much of either token.
+            let (lower, upper) = (float_sqrt_price(-600), float_sqrt_price(600));
+            assert_close(amount_0, liquidity as f64 * (1.0 - 1.0 / upper));
+            assert_close(amount_1, liquidity as f64 * (1.0 - lower));
+            assert!(amount_0.max(amount_1) <= 1_000 * E12 + 1);
+            assert_eq!(pool.liquidity(), liquidity);
+            assert_eq!(pool.get_reserves().0, amount_0);
+            assert_eq!(mock::balance(TOKEN_1, POOL.into()), amount_1);
+
+            assert_eq!(pool.owner_of(Id::U128(id)), Some(alice));
+            assert_eq!(PSP34::balance_of(&pool, alice), 1);
+
+            // Above the price, a range only takes token 0.
+            let (_, _, amount_0, amount_1) = pool.mint(180, 960, 1_000 * E12, 1_000 * E12, alice);
+            assert_eq!((amount_0 > 0, amount_1), (true, 0));
+            assert_eq!(pool.liquidity(), liquidity);
+        }
+
+        /// Swaps on a fresh pool with `add_positions` and checks the amount out and final
+        /// price against `reference_swap`.
+        fn assert_swap_matches_reference(zero_for_one: bool, amount_in: Balance) {
+            let mut pool = setup();
+            let positions = add_positions(&mut pool);
+            let (token_in, limit) = if zero_for_one {
+                (TOKEN_0, MIN_SQRT_PRICE)
+            } else {
+                (TOKEN_1, MAX_SQRT_PRICE)
+            };
+
+            let quote = pool.get_amount_out(amount_in, token_in.into()).unwrap();
+            let amount_out = pool.swap(zero_for_one, amount_in, limit, accounts().bob);
+            assert_eq!(quote, amount_out);
+
+            let (expected_out, expected_price) =
+                reference_swap(&positions, 1.0, amount_in as f64, 0.003, zero_for_one);
+            assert_close(amount_out, expected_out);
+            let (sqrt_price, tick) = pool.slot_0();
+            assert!((sqrt_price as f64 / ONE as f64 - expected_price).abs() <= 1e-9);
+            assert_eq!(tick, math::tick_at_sqrt_price(sqrt_price).unwrap());
+        }
+
+        #[ink::test]
+        fn quotes_only_the_pool_tokens() {
+            let mut pool = setup();
+            add_positions(&mut pool);
+
+            assert_eq!(
+                pool.get_amount_out(10 * E12, POOL.into()),
+                Err(MathError::InvalidToken)
+            );
+        }
+
+        #[ink::test]
+        fn swaps_within_a_range_match_the_reference() {
+            assert_swap_matches_reference(true, 10 * E12);
+        }
+
+        #[ink::test]
+        fn swaps_down_across_ticks_match_the_reference() {
+            assert_swap_matches_reference(true, 1_500 * E12);
+        }
+
+        #[ink::test]
+        fn swaps_up_across_ticks_match_the_reference() {
+            assert_swap_matches_reference(false, 1_500 * E12);
+        }
+
+        #[ink::test]
+        fn crossing_ticks_changes_the_liquidity_in_range() {
+            let mut pool = setup();
+            let positions = add_positions(&mut pool);
+            let liquidity =
+                |ranges: &[usize]| -> u128 { ranges.iter().map(|&index| positions[index].2).sum() };
+            assert_eq!(pool.liquidity(), liquidity(&[0, 1]));
+
+            // Down past -60 into the third range, then past -120 out of the second.
+            pool.swap(true, 1_500 * E12, MIN_SQRT_PRICE, accounts().bob);
+            assert!(pool.slot_0().1 < -120);
+            assert_eq!(pool.liquidity(), liquidity(&[0, 2]));
+
+            // Back up past 180 into the fourth range, leaving the third.
+            pool.swap(false, 3_000 * E12, MAX_SQRT_PRICE, accounts().bob);
+            assert!(pool.slot_0().1 >= 180);
+            assert_eq!(pool.liquidity(), liquidity(&[0, 3]));
+        }
+
+        #[ink::test]
+        fn swaps_stop_at_the_price_limit() {
+            let mut pool = setup();
+            add_positions(&mut pool);
+            let limit = math::sqrt_price_at_tick(-30).unwrap();
+            let before = mock::balance(TOKEN_0, accounts().alice);
+
+            pool.swap(true, 1_000 * E12, limit, accounts().bob);
+
+            assert_eq!(pool.slot_0(), (limit, -30));
+            let used = before - mock::balance(TOKEN_0, accounts().alice);
+            assert!(used > 0 && used < 1_000 * E12);
+        }
+
+        #[ink::test]
+        fn fees_go_to_the_positions_in_range() {
+            let mut pool = setup();
+            let positions = add_positions(&mut pool);
+            let alice = accounts().alice;
+
+            // Small enough to stay within the first two ranges.
+            let amount_in = E12;
+            pool.swap(true, amount_in, MIN_SQRT_PRICE, accounts().bob);
+
+            let in_range = positions[0].2 + positions[1].2;
+            for (index, &(_, _, liquidity)) in positions.iter().enumerate() {
+                let id = index as u128 + 1;
+                pool.burn(id, 0);
+                let (fees_0, fees_1) = pool.collect(id, alice);
+                let expected = if index < 2 {
+                    3 * amount_in / 1_000 * liquidity / in_range
+                } else {
+                    0
+                };
+                assert!(fees_0.abs_diff(expected) <= 1, "{fees_0} vs {expected}");
+                assert_eq!(fees_1, 0);
+            }
+        }
+
+        #[ink::test]
+        fn burn_and_collect_return_the_tokens() {
+            let mut pool = setup();
+            let alice = accounts().alice;
+            let bob = accounts().bob;
+            let (id, liquidity, amount_0, amount_1) =
+                pool.mint(-600, 600, 1_000 * E12, 1_000 * E12, alice);
+
+            let (burned_0, burned_1) = pool.burn(id, liquidity);
+            assert!(amount_0 - burned_0 <= 1 && amount_1 - burned_1 <= 1);
+            assert_eq!(pool.liquidity(), 0);
+            assert_eq!(pool.tick_info(-600), None);
+            assert_eq!(pool.position(id).unwrap().tokens_owed_0, burned_0);
+
+            assert_eq!(pool.collect(id, bob), (burned_0, burned_1));
+            assert_eq!(mock::balance(TOKEN_0, bob), burned_0);
+            assert_eq!(pool.get_reserves().0, amount_0 - burned_0);
+            assert_eq!(pool.collect(id, bob), (0, 0));
+        }
+
+        #[ink::test]
+        fn positions_move_with_their_token() {
+            let mut pool = setup();
+            let accounts = accounts();
+            let (id, liquidity, _, _) =
+                pool.mint(-600, 600, 1_000 * E12, 1_000 * E12, accounts.alice);
+
+            assert_eq!(
+                pool.approve(accounts.alice, Some(Id::U128(id)), true),
+                Err(PSP34Error::SelfApprove)
+            );
+            assert_eq!(
+                pool.approve(accounts.charlie, Some(Id::U128(id)), true),
+                Ok(())
+            );
+            assert!(pool.allowance(accounts.alice, accounts.charlie, Some(Id::U128(id))));
+
+            assert_eq!(
+                PSP34::transfer(&mut pool, accounts.bob, Id::U128(id), Vec::new()),
+                Ok(())
+            );
+            assert_eq!(pool.owner_of(Id::U128(id)), Some(accounts.bob));
+            assert_eq!(PSP34::balance_of(&pool, accounts.alice), 0);
+            // The transfer clears the approval.
+            assert!(!pool.allowance(accounts.bob, accounts.charlie, Some(Id::U128(id))));
+            assert_eq!(
+                PSP34::transfer(&mut pool, accounts.alice, Id::U128(id), Vec::new()),
+                Err(PSP34Error::NotApproved)
+            );
+
+            test::set_caller::<DefaultEnvironment>(accounts.bob);
+            assert!(pool.burn(id, liquidity / 2).0 > 0);
+            assert_eq!(PSP34::total_supply(&pool), 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV3: FORBIDDEN")]
+        fn only_the_owner_burns_a_position() {
+            let mut pool = setup();
+            let (id, liquidity, _, _) =
+                pool.mint(-600, 600, 1_000 * E12, 1_000 * E12, accounts().alice);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            pool.burn(id, liquidity);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV3: INVALID_TICKS")]
+        fn ranges_must_fit_the_tick_spacing() {
+            let mut pool = setup();
+            pool.mint(-600, 610, 1_000 * E12, 1_000 * E12, accounts().alice);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV3: PAUSED")]
+        fn swap_fails_while_paused() {
+            let mut pool = setup();
+            add_positions(&mut pool);
+            mock::set_paused(true);
+
+            pool.swap(true, E12, MIN_SQRT_PRICE, accounts().bob);
+        }
+
+        #[ink::test]
+        fn initialize_sets_the_tokens_and_fee() {
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            let mut pool = ClPool::create();
+
+            pool.initialize(TOKEN_0.into(), TOKEN_1.into(), 500);
+
+            assert_eq!((pool.fee(), pool.tick_spacing()), (500, 10));
+            assert_eq!(pool.token_1(), AccountId::from(TOKEN_1));
+            assert!(pool.concentrated() && !pool.stable());
+            assert_eq!(pool.slot_0(), (0, 0));
+        }
+    }
+}
@@ -1,9 +1,67 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 #[ink::contract]
-mod core {
-    use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+pub mod core {
+    use ink::env::{
+        call::{build_call, build_create, ExecutionInput, FromAccountId, Selector},
+        ContractEnv, DefaultEnvironment,
+    };
+    use ink::prelude::{format, string::String, vec::Vec};
+    use ink::storage::{Lazy, Mapping};
+    use uniswap_math::address::{
+        cl_pool_address, cl_pool_salt, pair_address, pair_salt, stable_pair_address,
+        stable_pair_salt, weighted_pair_address, weighted_pair_salt, PAIR_CONSTRUCTOR,
+    };
+    use uniswap_math::weighted::{self, WEIGHT_DENOMINATOR};
+    use uniswap_math::{concentrated, dynamic_fee};
+
+    /// Decimals of the LP token deployed alongside every pair.
+    const LP_TOKEN_DECIMALS: u8 = 18;
+
+    /// Longest token symbol carried into LP token metadata.
+    const MAX_SYMBOL_LEN: usize = 11;
+
+    /// Handle to a contract instantiated by the factory, of which only the address is kept.
+    struct Deployed(AccountId);
+
+    impl ContractEnv for Deployed {
+        type Env = DefaultEnvironment;
+    }
+
+    impl FromAccountId<DefaultEnvironment> for Deployed {
+        fn from_account_id(account_id: AccountId) -> Self {
+            Self(account_id)
+        }
+    }
+
+    /// The contracts the factory creates pairs from.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PairKind {
+        /// A `Pair`.
+        Classic,
+        /// A `StablePair`.
+        Stable,
+        /// A `WeightedPair` in which token 0 has this weight, in percent.
+        Weighted(u32),
+        /// A `ClPool` charging this fee, in millionths.
+        Concentrated(u32),
+    }
+
+    /// The kinds of pair whose liquidity is held in an LP token.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LpKind {
+        Classic,
+        Stable,
+        /// A `WeightedPair` in which token 0 has this weight.
+        Weighted(u32),
+    }
+
+    /// Tokens of a weighted pair and the weight of the first.
+    type WeightedPairKey = (AccountId, AccountId, u32);
+
+    /// Tokens of a concentrated liquidity pool and its fee.
+    type ClPoolKey = (AccountId, AccountId, u32);
 
     // (address indexed token0, address indexed token1, address pair, uint);
     #[ink(event)]
@@ -14,6 +72,146 @@ mod core {
         token1: Option<AccountId>,
         pair: Option<AccountId>,
         pair_index: u32,
+        /// The kind of `pair`, with its weight or fee tier.
+        kind: PairKind,
+    }
+
+    #[ink(event)]
+    pub struct FeeToChanged {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeeToSetterChanged {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PairCodeHashChanged {
+        previous: Hash,
+        new: Hash,
+        /// The kind of pair whose code changed. The code serves every weight and fee tier,
+        /// so `Weighted` and `Concentrated` carry 0.
+        kind: PairKind,
+    }
+
+    /// Emitted when trading is halted on `pair`, or on every pair when `None`.
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        pair: Option<AccountId>,
+    }
+
+    /// Emitted when trading resumes on `pair`, or on every pair when `None`.
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        pair: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct DynamicFeeChanged {
+        #[ink(topic)]
+        pair: AccountId,
+        /// `(min_fee, max_fee)` in millionths, or `None` once the fee is fixed again.
+        bounds: Option<(u32, u32)>,
+    }
+
+    /// Who may call `create_pair`. Tokens on the denylist can never be paired.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum CreationMode {
+        /// Anyone, for any tokens.
+        #[default]
+        Open,
+        /// Anyone, but only for tokens on the allowlist.
+        Allowlisted,
+        /// Only the fee-to setter and the accounts it made pair creators.
+        Permissioned,
+    }
+
+    #[ink(event)]
+    pub struct CreationModeChanged {
+        previous: CreationMode,
+        new: CreationMode,
+    }
+
+    #[ink(event)]
+    pub struct TokenAllowlisted {
+        #[ink(topic)]
+        token: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct TokenDenylisted {
+        #[ink(topic)]
+        token: AccountId,
+        denied: bool,
+    }
+
+    #[ink(event)]
+    pub struct PairCreatorChanged {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct GuardianChanged {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    /// Emitted when `current` proposes `pending` as the next fee-to setter.
+    #[ink(event)]
+    pub struct FeeToSetterProposed {
+        #[ink(topic)]
+        current: AccountId,
+        #[ink(topic)]
+        pending: AccountId,
+    }
+
+    /// Emitted when the fee-to setter withdraws its proposal of `pending`, or `None` when
+    /// there was none.
+    #[ink(event)]
+    pub struct FeeToSetterProposalCancelled {
+        #[ink(topic)]
+        pending: Option<AccountId>,
+    }
+
+    /// A pair's state as `pools` reports it.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct PoolState {
+        pub token_0: AccountId,
+        pub token_1: AccountId,
+        pub lp_token: AccountId,
+        pub reserve_0: Balance,
+        pub reserve_1: Balance,
+        /// Block timestamp, in milliseconds, of the last reserves update.
+        pub block_timestamp_last: u128,
+        /// LP tokens in circulation.
+        pub total_supply: Balance,
+        /// Swap fee in millionths, e.g. 3000 for 0.3%.
+        pub fee: u32,
+        pub stable: bool,
+        /// Share of the pool's value in token 0, in percent.
+        pub weight_0: u32,
+        /// Whether the pool is a `ClPool`, of which `lp_token` is the pool itself and
+        /// `total_supply` the liquidity in range.
+        pub concentrated: bool,
     }
 
     /// Defines the storage of your contract.
@@ -26,17 +224,65 @@ mod core {
         fee_to_setter: AccountId,
         get_pairs: Mapping<(AccountId, AccountId), AccountId>,
         all_pairs: Vec<AccountId>,
+        /// Account allowed to pause and unpause pairs
+        guardian: AccountId,
+        all_paused: bool,
+        paused_pairs: Mapping<AccountId, ()>,
+        /// Setter proposed by `propose_fee_to_setter`, waiting for it to accept
+        pending_fee_to_setter: Option<AccountId>,
+        /// Code new pairs are instantiated from
+        pair_code_hash: Hash,
+        /// Code of the `lp_token` deployed for every pair, which only that pair can mint
+        /// and burn
+        lp_token_code_hash: Hash,
+        /// `Open` until set
+        creation_mode: Lazy<CreationMode>,
+        allowed_tokens: Mapping<AccountId, ()>,
+        denied_tokens: Mapping<AccountId, ()>,
+        pair_creators: Mapping<AccountId, ()>,
+        /// Code stable pairs are instantiated from, unset until stable pairs are enabled
+        stable_pair_code_hash: Lazy<Hash>,
+        /// Stable pairs by their tokens, both ways round like `get_pairs`
+        stable_pairs: Mapping<(AccountId, AccountId), AccountId>,
+        /// Code weighted pairs are instantiated from, unset until weighted pairs are enabled
+        weighted_pair_code_hash: Lazy<Hash>,
+        /// Weighted pairs by their tokens and the weight of the first, both ways round
+        weighted_pairs: Mapping<WeightedPairKey, AccountId>,
+        /// Code concentrated liquidity pools are instantiated from, unset until they are enabled
+        cl_pool_code_hash: Lazy<Hash>,
+        /// Concentrated liquidity pools by their tokens and fee, both ways round
+        cl_pools: Mapping<ClPoolKey, AccountId>,
     }
 
     impl UniswapCore {
         /// Constructor that initializes the `bool` value to the given `init_value`.
         #[ink(constructor)]
-        pub fn new(fee_to_setter: AccountId) -> Self {
+        pub fn new(
+            fee_to_setter: AccountId,
+            pair_code_hash: Hash,
+            lp_token_code_hash: Hash,
+        ) -> Self {
             Self {
                 fee_to_setter,
                 fee_to: AccountId::from([0x0; 32]),
                 get_pairs: Mapping::new(),
                 all_pairs: Vec::new(),
+                guardian: fee_to_setter,
+                all_paused: false,
+                paused_pairs: Mapping::new(),
+                pending_fee_to_setter: None,
+                pair_code_hash,
+                lp_token_code_hash,
+                creation_mode: Lazy::new(),
+                allowed_tokens: Mapping::new(),
+                denied_tokens: Mapping::new(),
+                pair_creators: Mapping::new(),
+                stable_pair_code_hash: Lazy::new(),
+                stable_pairs: Mapping::new(),
+                weighted_pair_code_hash: Lazy::new(),
+                weighted_pairs: Mapping::new(),
+                cl_pool_code_hash: Lazy::new(),
+                cl_pools: Mapping::new(),
             }
         }
 
@@ -45,7 +291,7 @@ mod core {
         /// Constructors can delegate to other constructors.
         #[ink(constructor)]
         pub fn default() -> Self {
-            Self::new(AccountId::from([0x0; 32]))
+            Self::new(AccountId::from([0x0; 32]), Hash::default(), Hash::default())
         }
         // function createPair(address tokenA, address tokenB) external returns (address pair) {
         //     require(getPair[token0][token1] == address(0), 'UniswapV2: PAIR_EXISTS'); // single check is sufficient
@@ -62,39 +308,1342 @@ mod core {
         // }
 
         #[ink(message)]
-        pub fn create_pair(&mut self, token_a: AccountId, token_b: AccountId) -> Result<(), ()> {
-            assert!(token_a != token_b, "Uniswap: IDENTICAL_ADDRESSES");
-            assert!(token_a != Self::zero_address(), "Uniswap: ZERO_ADDRESS");
-            match self.get_pairs.get((token_a, token_b)) {
-                Some(_) => panic!("Uniswap: PAIR_EXISTS"),
-                None => {}
-            }
+        pub fn create_pair(&mut self, token_a: AccountId, token_b: AccountId) -> AccountId {
+            self.create(token_a, token_b, PairKind::Classic)
+        }
+
+        /// Creates the `StablePair` of two tokens, which can exist alongside their classic
+        /// pair. Both tokens need `PSP22Metadata` decimals, at most 18.
+        #[ink(message)]
+        pub fn create_stable_pair(&mut self, token_a: AccountId, token_b: AccountId) -> AccountId {
+            self.create(token_a, token_b, PairKind::Stable)
+        }
+
+        /// Creates the `WeightedPair` of two tokens in which `token_a` makes up `weight_a`
+        /// percent of the value, from 1 to 99. Pairs of the same tokens with other weights,
+        /// and their classic and stable pairs, can exist alongside.
+        #[ink(message)]
+        pub fn create_weighted_pair(
+            &mut self,
+            token_a: AccountId,
+            token_b: AccountId,
+            weight_a: u32,
+        ) -> AccountId {
+            self.create(token_a, token_b, PairKind::Weighted(weight_a))
+        }
+
+        /// Creates the `ClPool` of two tokens for a fee tier: 500, 3000 or 10000 millionths.
+        /// Pools of other tiers, and the pairs of the same tokens, can exist alongside. The
+        /// pool takes liquidity once anyone sets its price with `initialize_price`.
+        #[ink(message)]
+        pub fn create_cl_pool(
+            &mut self,
+            token_a: AccountId,
+            token_b: AccountId,
+            fee: u32,
+        ) -> AccountId {
+            self.create(token_a, token_b, PairKind::Concentrated(fee))
+        }
+
+        #[ink(message)]
+        pub fn get_pair(&self, token_a: AccountId, token_b: AccountId) -> Option<AccountId> {
+            self.get_pairs.get((token_a, token_b))
+        }
+
+        /// Address `create_pair` deploys the `token_a`/`token_b` pair at with the current
+        /// `pair_code_hash`, whether or not it exists yet. Routers can derive the same
+        /// address off-chain with `uniswap_math::address::pair_address`.
+        #[ink(message)]
+        pub fn compute_pair_address(&self, token_a: AccountId, token_b: AccountId) -> AccountId {
+            pair_address(
+                &self.env().account_id(),
+                &self.pair_code_hash,
+                token_a,
+                token_b,
+            )
+            .expect("Uniswap: IDENTICAL_ADDRESSES")
+        }
+
+        #[ink(message)]
+        pub fn get_stable_pair(&self, token_a: AccountId, token_b: AccountId) -> Option<AccountId> {
+            self.stable_pairs.get((token_a, token_b))
+        }
+
+        /// Like `compute_pair_address`, for the stable pair. Panics while stable pairs are
+        /// not enabled.
+        #[ink(message)]
+        pub fn compute_stable_pair_address(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+        ) -> AccountId {
+            stable_pair_address(
+                &self.env().account_id(),
+                &self
+                    .stable_pair_code_hash
+                    .get()
+                    .expect("Uniswap: NO_STABLE_PAIR_CODE"),
+                token_a,
+                token_b,
+            )
+            .expect("Uniswap: IDENTICAL_ADDRESSES")
+        }
 
-            Ok(())
+        /// The weighted pair in which `token_a` weighs `weight_a`, the way it was created or
+        /// the other way round: `(a, b, 80)` finds the pair created as `(b, a, 20)`.
+        #[ink(message)]
+        pub fn get_weighted_pair(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+            weight_a: u32,
+        ) -> Option<AccountId> {
+            self.weighted_pairs.get((token_a, token_b, weight_a))
         }
 
+        /// Like `compute_pair_address`, for the weighted pair. Panics while weighted pairs
+        /// are not enabled.
+        #[ink(message)]
+        pub fn compute_weighted_pair_address(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+            weight_a: u32,
+        ) -> AccountId {
+            weighted_pair_address(
+                &self.env().account_id(),
+                &self
+                    .weighted_pair_code_hash
+                    .get()
+                    .expect("Uniswap: NO_WEIGHTED_PAIR_CODE"),
+                token_a,
+                token_b,
+                weight_a,
+            )
+            .expect("Uniswap: INVALID_PAIR")
+        }
+
+        /// The concentrated liquidity pool of two tokens for a fee tier, in either order.
+        #[ink(message)]
+        pub fn get_cl_pool(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+            fee: u32,
+        ) -> Option<AccountId> {
+            self.cl_pools.get((token_a, token_b, fee))
+        }
+
+        /// Like `compute_pair_address`, for the concentrated liquidity pool. Panics while
+        /// they are not enabled.
+        #[ink(message)]
+        pub fn compute_cl_pool_address(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+            fee: u32,
+        ) -> AccountId {
+            cl_pool_address(
+                &self.env().account_id(),
+                &self
+                    .cl_pool_code_hash
+                    .get()
+                    .expect("Uniswap: NO_CL_POOL_CODE"),
+                token_a,
+                token_b,
+                fee,
+            )
+            .expect("Uniswap: INVALID_PAIR")
+        }
+
+        /// Pairs of every kind, in creation order.
         #[ink(message)]
         pub fn all_pairs_length(&self) -> u32 {
             self.all_pairs.len() as u32
         }
 
+        /// Up to `count` pairs from `all_pairs` onwards of `start`, with their state, so a
+        /// pool list loads in one dry-run. The state is `None` for a pair that could not be
+        /// read. Pairs on code older than `stable`, `weight_0` or `concentrated` read as
+        /// classic ones until `upgrade_pair` updates them.
+        #[ink(message)]
+        pub fn pools(&self, start: u32, count: u32) -> Vec<(AccountId, Option<PoolState>)> {
+            self.page(start, count)
+                .iter()
+                .map(|&pair| (pair, Self::pool_state(pair)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn get_fee_to(&self) -> AccountId {
+            self.fee_to
+        }
+
+        #[ink(message)]
+        pub fn get_fee_to_setter(&self) -> AccountId {
+            self.fee_to_setter
+        }
+
+        #[ink(message)]
+        pub fn pending_fee_to_setter(&self) -> Option<AccountId> {
+            self.pending_fee_to_setter
+        }
+
         #[ink(message)]
         pub fn set_fee_to(&mut self, fee_to: AccountId) {
             let caller = self.env().caller();
             assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
 
+            let previous = self.fee_to;
             self.fee_to = fee_to;
+            self.env().emit_event(FeeToChanged {
+                previous,
+                new: fee_to,
+            });
+        }
+
+        /// First step of handing over fee administration. Nothing changes until the
+        /// proposed account calls `accept_fee_to_setter`, so a wrong address can still be
+        /// replaced or cancelled.
+        #[ink(message)]
+        pub fn propose_fee_to_setter(&mut self, fee_to_setter: AccountId) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+            self.pending_fee_to_setter = Some(fee_to_setter);
+            self.env().emit_event(FeeToSetterProposed {
+                current: caller,
+                pending: fee_to_setter,
+            });
+        }
+
+        #[ink(message)]
+        pub fn cancel_fee_to_setter_proposal(&mut self) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+            let pending = self.pending_fee_to_setter.take();
+            self.env()
+                .emit_event(FeeToSetterProposalCancelled { pending });
+        }
+
+        #[ink(message)]
+        pub fn accept_fee_to_setter(&mut self) {
+            let caller = self.env().caller();
+            assert!(
+                self.pending_fee_to_setter == Some(caller),
+                "Uniswap: Forbidden Caller"
+            );
+
+            let previous = self.fee_to_setter;
+            self.fee_to_setter = caller;
+            self.pending_fee_to_setter = None;
+            self.env().emit_event(FeeToSetterChanged {
+                previous,
+                new: caller,
+            });
+        }
+
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: AccountId) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+            let previous = self.guardian;
+            self.guardian = guardian;
+            self.env().emit_event(GuardianChanged {
+                previous,
+                new: guardian,
+            });
+        }
+
+        #[ink(message)]
+        pub fn guardian(&self) -> AccountId {
+            self.guardian
+        }
+
+        /// Halts `swap` and `mint` on `pair`. `burn` stays open so LPs can exit.
+        #[ink(message)]
+        pub fn pause(&mut self, pair: AccountId) {
+            self.ensure_guardian();
+            self.paused_pairs.insert(pair, &());
+            self.env().emit_event(Paused { pair: Some(pair) });
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self, pair: AccountId) {
+            self.ensure_guardian();
+            self.paused_pairs.remove(pair);
+            self.env().emit_event(Unpaused { pair: Some(pair) });
+        }
+
+        /// Emergency shutdown of every pair, regardless of their own pause state.
+        #[ink(message)]
+        pub fn pause_all(&mut self) {
+            self.ensure_guardian();
+            self.all_paused = true;
+            self.env().emit_event(Paused { pair: None });
+        }
+
+        #[ink(message)]
+        pub fn unpause_all(&mut self) {
+            self.ensure_guardian();
+            self.all_paused = false;
+            self.env().emit_event(Unpaused { pair: None });
+        }
+
+        /// Queried by pairs before every `swap` and `mint`.
+        #[ink(message)]
+        pub fn is_paused(&self, pair: AccountId) -> bool {
+            self.all_paused || self.paused_pairs.contains(pair)
+        }
+
+        #[ink(message)]
+        pub fn creation_mode(&self) -> CreationMode {
+            self.creation_mode.get().unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn set_creation_mode(&mut self, mode: CreationMode) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            let previous = self.creation_mode();
+            self.creation_mode.set(&mode);
+            self.env().emit_event(CreationModeChanged {
+                previous,
+                new: mode,
+            });
+        }
+
+        /// Whether `token` may be paired while the mode is `Allowlisted`.
+        #[ink(message)]
+        pub fn is_token_allowed(&self, token: AccountId) -> bool {
+            self.allowed_tokens.contains(token)
+        }
+
+        #[ink(message)]
+        pub fn set_token_allowed(&mut self, token: AccountId, allowed: bool) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            if allowed {
+                self.allowed_tokens.insert(token, &());
+            } else {
+                self.allowed_tokens.remove(token);
+            }
+            self.env().emit_event(TokenAllowlisted { token, allowed });
+        }
+
+        /// Whether `token` is barred from new pairs, whatever the mode. Existing pairs of
+        /// the token are left alone; `pause` them to stop trading.
+        #[ink(message)]
+        pub fn is_token_denied(&self, token: AccountId) -> bool {
+            self.denied_tokens.contains(token)
+        }
+
+        #[ink(message)]
+        pub fn set_token_denied(&mut self, token: AccountId, denied: bool) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            if denied {
+                self.denied_tokens.insert(token, &());
+            } else {
+                self.denied_tokens.remove(token);
+            }
+            self.env().emit_event(TokenDenylisted { token, denied });
+        }
+
+        /// Whether `account` may create pairs while the mode is `Permissioned`. The fee-to
+        /// setter always may.
+        #[ink(message)]
+        pub fn is_pair_creator(&self, account: AccountId) -> bool {
+            account == self.fee_to_setter || self.pair_creators.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn set_pair_creator(&mut self, account: AccountId, allowed: bool) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            if allowed {
+                self.pair_creators.insert(account, &());
+            } else {
+                self.pair_creators.remove(account);
+            }
+            self.env()
+                .emit_event(PairCreatorChanged { account, allowed });
         }
 
         #[ink(message)]
-        pub fn set_fee_to_setter(&mut self, fee_to_setter: AccountId) {
+        pub fn pair_code_hash(&self) -> Hash {
+            self.pair_code_hash
+        }
+
+        /// Code used for pairs created from now on. Existing pairs keep theirs until
+        /// upgraded one by one with `upgrade_pair`.
+        #[ink(message)]
+        pub fn set_pair_code_hash(&mut self, pair_code_hash: Hash) {
             let caller = self.env().caller();
             assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
-            self.fee_to_setter = fee_to_setter;
+
+            let previous = self.pair_code_hash;
+            self.pair_code_hash = pair_code_hash;
+            self.env().emit_event(PairCodeHashChanged {
+                previous,
+                new: pair_code_hash,
+                kind: PairKind::Classic,
+            });
+        }
+
+        #[ink(message)]
+        pub fn stable_pair_code_hash(&self) -> Option<Hash> {
+            self.stable_pair_code_hash.get()
+        }
+
+        /// Code used for stable pairs created from now on. Setting it first enables
+        /// `create_stable_pair`.
+        #[ink(message)]
+        pub fn set_stable_pair_code_hash(&mut self, stable_pair_code_hash: Hash) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            let previous = self.stable_pair_code_hash().unwrap_or_default();
+            self.stable_pair_code_hash.set(&stable_pair_code_hash);
+            self.env().emit_event(PairCodeHashChanged {
+                previous,
+                new: stable_pair_code_hash,
+                kind: PairKind::Stable,
+            });
+        }
+
+        #[ink(message)]
+        pub fn weighted_pair_code_hash(&self) -> Option<Hash> {
+            self.weighted_pair_code_hash.get()
+        }
+
+        /// Code used for weighted pairs created from now on. Setting it first enables
+        /// `create_weighted_pair`.
+        #[ink(message)]
+        pub fn set_weighted_pair_code_hash(&mut self, weighted_pair_code_hash: Hash) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            let previous = self.weighted_pair_code_hash().unwrap_or_default();
+            self.weighted_pair_code_hash.set(&weighted_pair_code_hash);
+            self.env().emit_event(PairCodeHashChanged {
+                previous,
+                new: weighted_pair_code_hash,
+                kind: PairKind::Weighted(0),
+            });
+        }
+
+        #[ink(message)]
+        pub fn cl_pool_code_hash(&self) -> Option<Hash> {
+            self.cl_pool_code_hash.get()
+        }
+
+        /// Code used for concentrated liquidity pools created from now on. Setting it first
+        /// enables `create_cl_pool`.
+        #[ink(message)]
+        pub fn set_cl_pool_code_hash(&mut self, cl_pool_code_hash: Hash) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            let previous = self.cl_pool_code_hash().unwrap_or_default();
+            self.cl_pool_code_hash.set(&cl_pool_code_hash);
+            self.env().emit_event(PairCodeHashChanged {
+                previous,
+                new: cl_pool_code_hash,
+                kind: PairKind::Concentrated(0),
+            });
+        }
+
+        /// Makes the fee of the classic `pair` follow the volatility of its price oracle
+        /// within `bounds`, `(min_fee, max_fee)` in millionths, or fixes it again with `None`.
+        #[ink(message)]
+        pub fn set_dynamic_fee(&mut self, pair: AccountId, bounds: Option<(u32, u32)>) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+            if let Some((min_fee, max_fee)) = bounds {
+                assert!(
+                    dynamic_fee::is_valid_bounds(min_fee, max_fee),
+                    "Uniswap: INVALID_FEE"
+                );
+            }
+
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("set_dynamic_fee")))
+                        .push_arg(&bounds),
+                )
+                .returns::<()>()
+                .invoke();
+            self.env().emit_event(DynamicFeeChanged { pair, bounds });
+        }
+
+        /// Opt-in upgrade of an existing pair to the current `pair_code_hash`, or the code
+        /// hash of its kind for a stable or weighted pair, or a concentrated liquidity pool.
+        #[ink(message)]
+        pub fn upgrade_pair(&mut self, pair: AccountId) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            // Pairs from before `stable` existed are all classic, and only weighted pairs and
+            // concentrated liquidity pools answer `weighted` and `concentrated`.
+            let code_hash = if Self::query_pair(pair, ink::selector_bytes!("stable")) == Some(true)
+            {
+                self.stable_pair_code_hash
+                    .get()
+                    .expect("Uniswap: NO_STABLE_PAIR_CODE")
+            } else if Self::query_pair(pair, ink::selector_bytes!("weighted")) == Some(true) {
+                self.weighted_pair_code_hash
+                    .get()
+                    .expect("Uniswap: NO_WEIGHTED_PAIR_CODE")
+            } else if Self::query_pair(pair, ink::selector_bytes!("concentrated")) == Some(true) {
+                self.cl_pool_code_hash
+                    .get()
+                    .expect("Uniswap: NO_CL_POOL_CODE")
+            } else {
+                self.pair_code_hash
+            };
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("set_code")))
+                        .push_arg(&code_hash),
+                )
+                .returns::<()>()
+                .invoke();
+        }
+
+        /// Replaces the factory's own code in place. Storage is kept as is, so the new code
+        /// must keep the current storage layout.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) {
+            let caller = self.env().caller();
+            assert!(caller == self.fee_to_setter, "Uniswap: Forbidden Caller");
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .expect("Uniswap: SET_CODE_FAILED");
+        }
+
+        fn create(&mut self, token_a: AccountId, token_b: AccountId, kind: PairKind) -> AccountId {
+            assert!(token_a != token_b, "Uniswap: IDENTICAL_ADDRESSES");
+            let (token_0, token_1) = if token_a < token_b {
+                (token_a, token_b)
+            } else {
+                (token_b, token_a)
+            };
+            assert!(token_0 != Self::zero_address(), "Uniswap: ZERO_ADDRESS");
+            // Weights are given for `token_a`; from here on they are token 0's.
+            let kind = match kind {
+                PairKind::Weighted(weight_a) => {
+                    assert!(
+                        weighted::is_valid_weight(weight_a),
+                        "Uniswap: INVALID_WEIGHT"
+                    );
+                    if token_0 == token_a {
+                        PairKind::Weighted(weight_a)
+                    } else {
+                        PairKind::Weighted(WEIGHT_DENOMINATOR - weight_a)
+                    }
+                }
+                PairKind::Concentrated(fee) => {
+                    assert!(
+                        concentrated::tick_spacing(fee).is_some(),
+                        "Uniswap: INVALID_FEE"
+                    );
+                    kind
+                }
+                kind => kind,
+            };
+            let (existing, code_hash, salt) = match kind {
+                PairKind::Classic => (
+                    self.get_pairs.get((token_0, token_1)),
+                    self.pair_code_hash,
+                    pair_salt(&token_0, &token_1),
+                ),
+                PairKind::Stable => (
+                    self.stable_pairs.get((token_0, token_1)),
+                    self.stable_pair_code_hash
+                        .get()
+                        .expect("Uniswap: NO_STABLE_PAIR_CODE"),
+                    stable_pair_salt(&token_0, &token_1),
+                ),
+                PairKind::Weighted(weight_0) => (
+                    self.weighted_pairs.get((token_0, token_1, weight_0)),
+                    self.weighted_pair_code_hash
+                        .get()
+                        .expect("Uniswap: NO_WEIGHTED_PAIR_CODE"),
+                    weighted_pair_salt(&token_0, &token_1, weight_0),
+                ),
+                PairKind::Concentrated(fee) => (
+                    self.cl_pools.get((token_0, token_1, fee)),
+                    self.cl_pool_code_hash
+                        .get()
+                        .expect("Uniswap: NO_CL_POOL_CODE"),
+                    cl_pool_salt(&token_0, &token_1, fee),
+                ),
+            };
+            if existing.is_some() {
+                panic!("Uniswap: PAIR_EXISTS")
+            }
+            self.ensure_can_create(token_0, token_1);
+
+            let pair = build_create::<Deployed>()
+                .code_hash(code_hash)
+                .gas_limit(0)
+                .endowment(0)
+                .exec_input(ExecutionInput::new(Selector::new(PAIR_CONSTRUCTOR)))
+                .salt_bytes(salt)
+                .returns::<Deployed>()
+                .instantiate()
+                .0;
+            match kind {
+                PairKind::Classic => {
+                    self.initialize_pair(pair, token_0, token_1, LpKind::Classic, salt)
+                }
+                PairKind::Stable => {
+                    self.initialize_pair(pair, token_0, token_1, LpKind::Stable, salt)
+                }
+                PairKind::Weighted(weight_0) => {
+                    self.initialize_pair(pair, token_0, token_1, LpKind::Weighted(weight_0), salt)
+                }
+                // Concentrated liquidity pools hold their positions themselves, without an
+                // LP token.
+                PairKind::Concentrated(fee) => Self::initialize(
+                    pair,
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("initialize")))
+                        .push_arg(&token_0)
+                        .push_arg(&token_1)
+                        .push_arg(&fee),
+                ),
+            }
+
+            match kind {
+                PairKind::Classic => {
+                    self.get_pairs.insert((token_0, token_1), &pair);
+                    self.get_pairs.insert((token_1, token_0), &pair);
+                }
+                PairKind::Stable => {
+                    self.stable_pairs.insert((token_0, token_1), &pair);
+                    self.stable_pairs.insert((token_1, token_0), &pair);
+                }
+                PairKind::Weighted(weight_0) => {
+                    self.weighted_pairs
+                        .insert((token_0, token_1, weight_0), &pair);
+                    self.weighted_pairs
+                        .insert((token_1, token_0, WEIGHT_DENOMINATOR - weight_0), &pair);
+                }
+                PairKind::Concentrated(fee) => {
+                    self.cl_pools.insert((token_0, token_1, fee), &pair);
+                    self.cl_pools.insert((token_1, token_0, fee), &pair);
+                }
+            }
+            self.all_pairs.push(pair);
+            self.env().emit_event(PairCreated {
+                token0: Some(token_0),
+                token1: Some(token_1),
+                pair: Some(pair),
+                pair_index: self.all_pairs.len() as u32,
+                kind,
+            });
+
+            pair
+        }
+
+        /// Deploys the LP token of a new pair of `kind` and initializes the pair with it.
+        fn initialize_pair(
+            &self,
+            pair: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+            kind: LpKind,
+            salt: [u8; 32],
+        ) {
+            let lp_token = self.create_lp_token(pair, token_0, token_1, kind, salt);
+            let initialize = ExecutionInput::new(Selector::new(ink::selector_bytes!("initialize")))
+                .push_arg(&lp_token)
+                .push_arg(&token_0)
+                .push_arg(&token_1);
+            match kind {
+                LpKind::Weighted(weight_0) => {
+                    Self::initialize(pair, initialize.push_arg(&weight_0))
+                }
+                LpKind::Classic | LpKind::Stable => Self::initialize(pair, initialize),
+            }
+        }
+
+        /// Deploys the LP token of a new pair, named after its tokens and minted only by
+        /// `pair`.
+        fn create_lp_token(
+            &self,
+            pair: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+            kind: LpKind,
+            salt: [u8; 32],
+        ) -> AccountId {
+            let (name, symbol) = lp_metadata(&token_symbol(token_0), &token_symbol(token_1), kind);
+            build_create::<Deployed>()
+                .code_hash(self.lp_token_code_hash)
+                .gas_limit(0)
+                .endowment(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("new")))
+                        .push_arg(&pair)
+                        .push_arg(&Some(name))
+                        .push_arg(&Some(symbol))
+                        .push_arg(&LP_TOKEN_DECIMALS),
+                )
+                .salt_bytes(salt)
+                .returns::<Deployed>()
+                .instantiate()
+                .0
+        }
+
+        /// Calls `initialize` on a newly created pair, with the arguments of its kind.
+        fn initialize<Args: scale::Encode>(pair: AccountId, input: ExecutionInput<Args>) {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(input)
+                .returns::<()>()
+                .invoke();
+        }
+
+        fn ensure_can_create(&self, token_0: AccountId, token_1: AccountId) {
+            assert!(
+                !self.is_token_denied(token_0) && !self.is_token_denied(token_1),
+                "Uniswap: TOKEN_DENIED"
+            );
+            match self.creation_mode() {
+                CreationMode::Open => {}
+                CreationMode::Allowlisted => assert!(
+                    self.is_token_allowed(token_0) && self.is_token_allowed(token_1),
+                    "Uniswap: TOKEN_NOT_ALLOWED"
+                ),
+                CreationMode::Permissioned => assert!(
+                    self.is_pair_creator(self.env().caller()),
+                    "Uniswap: Forbidden Caller"
+                ),
+            }
+        }
+
+        fn page(&self, start: u32, count: u32) -> &[AccountId] {
+            let start = (start as usize).min(self.all_pairs.len());
+            let end = start
+                .saturating_add(count as usize)
+                .min(self.all_pairs.len());
+            &self.all_pairs[start..end]
+        }
+
+        fn pool_state(pair: AccountId) -> Option<PoolState> {
+            let (reserve_0, reserve_1, block_timestamp_last) =
+                Self::query_pair(pair, ink::selector_bytes!("get_reserves"))?;
+            Some(PoolState {
+                token_0: Self::query_pair(pair, ink::selector_bytes!("token_0"))?,
+                token_1: Self::query_pair(pair, ink::selector_bytes!("token_1"))?,
+                lp_token: Self::query_pair(pair, ink::selector_bytes!("lp_token"))?,
+                reserve_0,
+                reserve_1,
+                block_timestamp_last,
+                total_supply: Self::query_pair(pair, ink::selector_bytes!("get_total_supply"))?,
+                fee: Self::query_pair(pair, ink::selector_bytes!("fee"))?,
+                // Pairs on code older than `stable` and `weight_0` are classic ones.
+                stable: Self::query_pair(pair, ink::selector_bytes!("stable")).unwrap_or(false),
+                weight_0: Self::query_pair(pair, ink::selector_bytes!("weight_0"))
+                    .unwrap_or(WEIGHT_DENOMINATOR / 2),
+                concentrated: Self::query_pair(pair, ink::selector_bytes!("concentrated"))
+                    == Some(true),
+            })
+        }
+
+        /// Calls a read-only message of `pair` that takes no arguments.
+        fn query_pair<T: scale::Decode>(pair: AccountId, selector: [u8; 4]) -> Option<T> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(ExecutionInput::new(Selector::new(selector)))
+                .returns::<T>()
+                .try_invoke()
+                .ok()?
+                .ok()
+        }
+
+        fn ensure_guardian(&self) {
+            let caller = self.env().caller();
+            assert!(caller == self.guardian, "Uniswap: Forbidden Caller");
         }
 
         pub fn zero_address() -> AccountId {
             AccountId::from([0x0; 32])
         }
     }
+
+    /// Name and symbol of the LP token of a pair of tokens with these symbols.
+    fn lp_metadata(symbol_0: &str, symbol_1: &str, kind: LpKind) -> (String, String) {
+        match kind {
+            LpKind::Classic => (
+                format!("Uniswap V2 {symbol_0}/{symbol_1}"),
+                format!("UNI-V2 {symbol_0}/{symbol_1}"),
+            ),
+            LpKind::Stable => (
+                format!("Uniswap V2 Stable {symbol_0}/{symbol_1}"),
+                format!("sUNI-V2 {symbol_0}/{symbol_1}"),
+            ),
+            LpKind::Weighted(weight_0) => {
+                let weight_1 = WEIGHT_DENOMINATOR - weight_0;
+                (
+                    format!("Uniswap V2 Weighted {weight_0}{symbol_0}/{weight_1}{symbol_1}"),
+                    format!("wUNI-V2 {weight_0}{symbol_0}/{weight_1}{symbol_1}"),
+                )
+            }
+        }
+    }
+
+    /// The PSP22 symbol of `token`, or the start of its address when it has none that fits
+    /// in an LP token symbol.
+    fn token_symbol(token: AccountId) -> String {
+        let symbol = build_call::<DefaultEnvironment>()
+            .call(token)
+            .gas_limit(0)
+            .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                "PSP22Metadata::token_symbol"
+            ))))
+            .returns::<Option<String>>()
+            .try_invoke();
+        symbol_or_address(symbol.ok().and_then(Result::ok).flatten(), &token)
+    }
+
+    fn symbol_or_address(symbol: Option<String>, token: &AccountId) -> String {
+        match symbol {
+            Some(symbol) if is_valid_symbol(&symbol) => symbol,
+            _ => {
+                let bytes: &[u8] = token.as_ref();
+                let digits: String = bytes[..4]
+                    .iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect();
+                format!("0x{digits}")
+            }
+        }
+    }
+
+    /// Short, and without spaces, `/` or anything a wallet could render misleadingly.
+    fn is_valid_symbol(symbol: &str) -> bool {
+        (1..=MAX_SYMBOL_LEN).contains(&symbol.len())
+            && symbol
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"-_.+".contains(&byte))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::test;
+        use scale::{Decode, Encode};
+        use uniswap_support::layout;
+
+        /// Packed fields of the deployed storage, in order.
+        const PACKED_FIELDS: &[&str] = &[
+            "fee_to",
+            "fee_to_setter",
+            "all_pairs",
+            "guardian",
+            "all_paused",
+            "pending_fee_to_setter",
+            "pair_code_hash",
+            "lp_token_code_hash",
+        ];
+
+        /// Fields stored in their own cell, with the key it lives under.
+        const ROOT_FIELDS: &[(&str, u32)] = &[
+            ("creation_mode", 2701730934),
+            ("allowed_tokens", 3348253191),
+            ("denied_tokens", 1432484353),
+            ("pair_creators", 4171855546),
+            ("stable_pair_code_hash", 1162720974),
+            ("stable_pairs", 3136390052),
+            ("weighted_pair_code_hash", 3768576661),
+            ("weighted_pairs", 1831456999),
+            ("cl_pool_code_hash", 2792632444),
+            ("cl_pools", 3575093613),
+        ];
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        /// A factory whose fee-to setter, Alice, is the caller.
+        fn setup() -> UniswapCore {
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            UniswapCore::new(
+                accounts().alice,
+                Hash::from([0x2; 32]),
+                Hash::from([0x3; 32]),
+            )
+        }
+
+        #[ink::test]
+        fn creation_is_open_until_restricted() {
+            let mut core = setup();
+            let (token_a, token_b) = (accounts().django, accounts().eve);
+            assert_eq!(core.creation_mode(), CreationMode::Open);
+            core.ensure_can_create(token_a, token_b);
+
+            core.set_creation_mode(CreationMode::Allowlisted);
+            core.set_token_allowed(token_a, true);
+            core.set_token_allowed(token_b, true);
+            core.ensure_can_create(token_a, token_b);
+
+            core.set_creation_mode(CreationMode::Permissioned);
+            core.set_pair_creator(accounts().bob, true);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.ensure_can_create(token_a, token_b);
+
+            assert_eq!(core.creation_mode(), CreationMode::Permissioned);
+            assert!(core.is_token_allowed(token_a));
+            assert!(core.is_pair_creator(accounts().alice));
+            assert!(!core.is_pair_creator(accounts().charlie));
+            assert_eq!(test::recorded_events().count(), 5);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: TOKEN_DENIED")]
+        fn denied_tokens_cannot_be_paired() {
+            let mut core = setup();
+            core.set_token_denied(accounts().eve, true);
+            core.create_pair(accounts().django, accounts().eve);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: TOKEN_NOT_ALLOWED")]
+        fn allowlisted_mode_rejects_unlisted_tokens() {
+            let mut core = setup();
+            core.set_creation_mode(CreationMode::Allowlisted);
+            core.set_token_allowed(accounts().django, true);
+            core.create_pair(accounts().django, accounts().eve);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn permissioned_mode_rejects_other_creators() {
+            let mut core = setup();
+            core.set_creation_mode(CreationMode::Permissioned);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.create_pair(accounts().django, accounts().eve);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_fee_to_setter_restricts_creation() {
+            let mut core = setup();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.set_creation_mode(CreationMode::Permissioned);
+        }
+
+        #[test]
+        fn lp_tokens_are_named_after_their_tokens() {
+            let token = AccountId::from([0xab; 32]);
+            let symbol = |symbol: Option<&str>| symbol_or_address(symbol.map(Into::into), &token);
+
+            assert_eq!(symbol(Some("USDC.e")), "USDC.e");
+            assert_eq!(symbol(None), "0xabababab");
+            assert_eq!(symbol(Some("")), "0xabababab");
+            assert_eq!(symbol(Some("TWELVE_CHARS")), "0xabababab");
+            assert_eq!(symbol(Some("A/B")), "0xabababab");
+            assert_eq!(symbol(Some("USD\u{202e}C")), "0xabababab");
+            assert_eq!(
+                lp_metadata("ABC", "XYZ", LpKind::Classic),
+                ("Uniswap V2 ABC/XYZ".into(), "UNI-V2 ABC/XYZ".into())
+            );
+            assert_eq!(
+                lp_metadata("ABC", "XYZ", LpKind::Stable),
+                ("Uniswap V2 Stable ABC/XYZ".into(), "sUNI-V2 ABC/XYZ".into())
+            );
+            assert_eq!(
+                lp_metadata("ABC", "XYZ", LpKind::Weighted(80)),
+                (
+                    "Uniswap V2 Weighted 80ABC/20XYZ".into(),
+                    "wUNI-V2 80ABC/20XYZ".into()
+                )
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: NO_STABLE_PAIR_CODE")]
+        fn stable_pairs_need_their_code_set_first() {
+            let mut core = setup();
+            assert_eq!(core.stable_pair_code_hash(), None);
+            core.create_stable_pair(accounts().django, accounts().eve);
+        }
+
+        #[ink::test]
+        fn stable_pair_code_is_set_by_the_fee_to_setter() {
+            let mut core = setup();
+            let code_hash = Hash::from([0x4; 32]);
+
+            core.set_stable_pair_code_hash(code_hash);
+
+            assert_eq!(core.stable_pair_code_hash(), Some(code_hash));
+            assert_eq!(core.pair_code_hash(), Hash::from([0x2; 32]));
+            assert_ne!(
+                core.compute_stable_pair_address(accounts().django, accounts().eve),
+                core.compute_pair_address(accounts().django, accounts().eve)
+            );
+            assert_eq!(
+                core.get_stable_pair(accounts().django, accounts().eve),
+                None
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: NO_WEIGHTED_PAIR_CODE")]
+        fn weighted_pairs_need_their_code_set_first() {
+            let mut core = setup();
+            assert_eq!(core.weighted_pair_code_hash(), None);
+            core.create_weighted_pair(accounts().django, accounts().eve, 80);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: INVALID_WEIGHT")]
+        fn weighted_pairs_need_both_tokens_weighted() {
+            let mut core = setup();
+            core.set_weighted_pair_code_hash(Hash::from([0x5; 32]));
+            core.create_weighted_pair(accounts().django, accounts().eve, 100);
+        }
+
+        #[ink::test]
+        fn weighted_pair_code_is_set_by_the_fee_to_setter() {
+            let mut core = setup();
+            let (token_a, token_b) = (accounts().django, accounts().eve);
+
+            core.set_weighted_pair_code_hash(Hash::from([0x5; 32]));
+
+            assert_eq!(core.weighted_pair_code_hash(), Some(Hash::from([0x5; 32])));
+            assert_eq!(
+                core.compute_weighted_pair_address(token_a, token_b, 80),
+                core.compute_weighted_pair_address(token_b, token_a, 20)
+            );
+            assert_ne!(
+                core.compute_weighted_pair_address(token_a, token_b, 80),
+                core.compute_weighted_pair_address(token_a, token_b, 20)
+            );
+            assert_eq!(core.get_weighted_pair(token_a, token_b, 80), None);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: NO_CL_POOL_CODE")]
+        fn cl_pools_need_their_code_set_first() {
+            let mut core = setup();
+            assert_eq!(core.cl_pool_code_hash(), None);
+            core.create_cl_pool(accounts().django, accounts().eve, 3_000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: INVALID_FEE")]
+        fn cl_pools_need_a_fee_tier() {
+            let mut core = setup();
+            core.set_cl_pool_code_hash(Hash::from([0x6; 32]));
+            core.create_cl_pool(accounts().django, accounts().eve, 2_500);
+        }
+
+        #[ink::test]
+        fn cl_pool_code_is_set_by_the_fee_to_setter() {
+            let mut core = setup();
+            let (token_a, token_b) = (accounts().django, accounts().eve);
+
+            core.set_cl_pool_code_hash(Hash::from([0x6; 32]));
+
+            assert_eq!(core.cl_pool_code_hash(), Some(Hash::from([0x6; 32])));
+            let event = test::recorded_events().last().unwrap();
+            let changed = PairCodeHashChanged::decode(&mut &event.data[1..]).unwrap();
+            assert_eq!(
+                (changed.previous, changed.new, changed.kind),
+                (
+                    Hash::default(),
+                    Hash::from([0x6; 32]),
+                    PairKind::Concentrated(0)
+                )
+            );
+            assert_eq!(
+                core.compute_cl_pool_address(token_a, token_b, 500),
+                core.compute_cl_pool_address(token_b, token_a, 500)
+            );
+            assert_ne!(
+                core.compute_cl_pool_address(token_a, token_b, 500),
+                core.compute_cl_pool_address(token_a, token_b, 3_000)
+            );
+            assert_eq!(core.get_cl_pool(token_a, token_b, 500), None);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn dynamic_fees_are_set_by_the_fee_to_setter() {
+            let mut core = setup();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.set_dynamic_fee(accounts().django, Some((1_000, 10_000)));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: INVALID_FEE")]
+        fn dynamic_fees_stay_below_the_maximum() {
+            let mut core = setup();
+            core.set_dynamic_fee(accounts().django, Some((1_000, dynamic_fee::MAX_FEE + 1)));
+        }
+
+        /// A topic as ink! records it: the encoding padded when it fits, else its hash.
+        fn topic_of(encoded: Vec<u8>) -> Vec<u8> {
+            let mut topic = vec![0; 32];
+            if encoded.len() <= 32 {
+                topic[..encoded.len()].copy_from_slice(&encoded);
+            } else {
+                let hash = topic.as_mut_slice().try_into().unwrap();
+                ink::env::hash_bytes::<ink::env::hash::Blake2x256>(&encoded, hash);
+            }
+            topic
+        }
+
+        /// The topic of a `#[ink(topic)]` field, prefixed with its path.
+        fn topic<T: scale::Encode>(event: &str, field: &str, value: &T) -> Vec<u8> {
+            let prefix = format!("UniswapCore::{event}::{field}");
+            topic_of(
+                ink::env::topics::PrefixedValue {
+                    prefix: prefix.as_bytes(),
+                    value,
+                }
+                .encode(),
+            )
+        }
+
+        /// The leading topic naming the event, an empty prefix then the raw path.
+        fn signature(event: &str) -> Vec<u8> {
+            topic_of([&[0][..], format!("UniswapCore::{event}").as_bytes()].concat())
+        }
+
+        fn pause_topics(event: &str, pair: Option<AccountId>) -> Vec<Vec<u8>> {
+            vec![signature(event), topic(event, "pair", &pair)]
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_fee_to_setter_sets_the_guardian() {
+            let mut core = setup();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.set_guardian(accounts().bob);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_guardian_pauses() {
+            let mut core = setup();
+            core.set_guardian(accounts().bob);
+            core.pause(accounts().django);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_guardian_unpauses() {
+            let mut core = setup();
+            core.set_guardian(accounts().bob);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.pause(accounts().django);
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            core.unpause(accounts().django);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_guardian_pauses_every_pair() {
+            let mut core = setup();
+            core.set_guardian(accounts().bob);
+            core.pause_all();
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_guardian_unpauses_every_pair() {
+            let mut core = setup();
+            core.set_guardian(accounts().bob);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.pause_all();
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            core.unpause_all();
+        }
+
+        #[ink::test]
+        fn pause_all_overrides_pair_state() {
+            let mut core = setup();
+            let (pair_a, pair_b) = (accounts().django, accounts().eve);
+
+            core.pause(pair_a);
+            core.pause_all();
+            assert!(core.is_paused(pair_a) && core.is_paused(pair_b));
+            core.unpause(pair_a);
+            assert!(
+                core.is_paused(pair_a),
+                "unpausing a pair lifts no global pause"
+            );
+
+            core.pause(pair_b);
+            core.unpause_all();
+            assert!(!core.is_paused(pair_a));
+            assert!(
+                core.is_paused(pair_b),
+                "a pair paused on its own stays paused"
+            );
+        }
+
+        #[ink::test]
+        fn pausing_emits_the_pair_as_topic() {
+            let mut core = setup();
+            let pair = accounts().django;
+
+            core.pause(pair);
+            core.unpause(pair);
+            core.pause_all();
+            core.unpause_all();
+
+            let topics: Vec<_> = test::recorded_events().map(|event| event.topics).collect();
+            assert_eq!(
+                topics,
+                [
+                    pause_topics("Paused", Some(pair)),
+                    pause_topics("Unpaused", Some(pair)),
+                    pause_topics("Paused", None),
+                    pause_topics("Unpaused", None),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn guardian_changes_are_emitted() {
+            let mut core = setup();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            core.set_guardian(bob);
+
+            assert_eq!(core.guardian(), bob);
+            let event = test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                [
+                    signature("GuardianChanged"),
+                    topic("GuardianChanged", "previous", &alice),
+                    topic("GuardianChanged", "new", &bob),
+                ]
+            );
+            let changed = GuardianChanged::decode(&mut &event.data[1..]).unwrap();
+            assert_eq!((changed.previous, changed.new), (alice, bob));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_fee_to_setter_proposes_a_successor() {
+            let mut core = setup();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.propose_fee_to_setter(accounts().bob);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_fee_to_setter_cancels_a_proposal() {
+            let mut core = setup();
+            core.propose_fee_to_setter(accounts().bob);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.cancel_fee_to_setter_proposal();
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn only_the_proposed_account_accepts() {
+            let mut core = setup();
+            core.propose_fee_to_setter(accounts().bob);
+            test::set_caller::<DefaultEnvironment>(accounts().charlie);
+            core.accept_fee_to_setter();
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "Uniswap: Forbidden Caller")]
+        fn cancelled_proposals_cannot_be_accepted() {
+            let mut core = setup();
+            core.propose_fee_to_setter(accounts().bob);
+            core.cancel_fee_to_setter_proposal();
+            assert_eq!(core.pending_fee_to_setter(), None);
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            core.accept_fee_to_setter();
+        }
+
+        #[ink::test]
+        fn fee_to_setter_changes_once_accepted() {
+            let mut core = setup();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            let charlie = accounts().charlie;
+
+            core.propose_fee_to_setter(charlie);
+            core.cancel_fee_to_setter_proposal();
+            core.propose_fee_to_setter(bob);
+            assert_eq!(core.pending_fee_to_setter(), Some(bob));
+            assert_eq!(core.get_fee_to_setter(), alice);
+            let events: Vec<_> = test::recorded_events().collect();
+            assert_eq!(events.len(), 3);
+            assert_eq!(
+                events[0].topics,
+                [
+                    signature("FeeToSetterProposed"),
+                    topic("FeeToSetterProposed", "current", &alice),
+                    topic("FeeToSetterProposed", "pending", &charlie),
+                ]
+            );
+            let cancelled =
+                FeeToSetterProposalCancelled::decode(&mut &events[1].data[1..]).unwrap();
+            assert_eq!(cancelled.pending, Some(charlie));
+            let proposed = FeeToSetterProposed::decode(&mut &events[2].data[1..]).unwrap();
+            assert_eq!((proposed.current, proposed.pending), (alice, bob));
+
+            test::set_caller::<DefaultEnvironment>(bob);
+            core.accept_fee_to_setter();
+
+            assert_eq!(core.get_fee_to_setter(), bob);
+            assert_eq!(core.pending_fee_to_setter(), None);
+            let events: Vec<_> = test::recorded_events().collect();
+            assert_eq!(events.len(), 4);
+            assert_eq!(
+                events[3].topics,
+                [
+                    signature("FeeToSetterChanged"),
+                    topic("FeeToSetterChanged", "previous", &alice),
+                    topic("FeeToSetterChanged", "new", &bob),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn pool_pages_stop_at_the_last_pair() {
+            let mut core = UniswapCore::new(
+                AccountId::from([0x1; 32]),
+                Hash::from([0x2; 32]),
+                Hash::from([0x3; 32]),
+            );
+            assert_eq!(core.pools(0, 10), Vec::new());
+
+            let pairs: Vec<_> = (10..15).map(|i| AccountId::from([i; 32])).collect();
+            core.all_pairs = pairs.clone();
+
+            assert_eq!(core.page(0, 2), &pairs[..2]);
+            assert_eq!(core.page(3, 10), &pairs[3..]);
+            assert_eq!(core.page(5, 1), &[]);
+            assert_eq!(core.page(u32::MAX, u32::MAX), &[]);
+        }
+
+        #[test]
+        fn storage_layout_is_upgrade_compatible() {
+            layout::assert_upgrade_compatible::<UniswapCore>(PACKED_FIELDS, ROOT_FIELDS);
+        }
+    }
 }
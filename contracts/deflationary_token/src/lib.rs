@@ -0,0 +1,67 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// PSP22 that burns a fixed share of every transfer, used to exercise the
+/// fee-on-transfer paths of `pair` and `router`.
+#[openbrush::implementation(PSP22, PSP22Metadata)]
+#[openbrush::contract]
+pub mod deflationary_token {
+    use openbrush::traits::Storage;
+
+    /// Basis points denominator for `burn_bps`.
+    const BPS: u128 = 10_000;
+
+    #[ink(storage)]
+    #[derive(Storage, Default)]
+    pub struct DeflationaryToken {
+        #[storage_field]
+        psp22: psp22::Data,
+        #[storage_field]
+        metadata: metadata::Data,
+        /// Share of each transfer that is burnt, in basis points
+        burn_bps: u16,
+    }
+
+    #[overrider(psp22::Internal)]
+    fn _transfer_from_to(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error> {
+        let burnt = amount * self.burn_bps as u128 / BPS;
+        if burnt > 0 {
+            psp22::InternalImpl::_burn_from(self, from, burnt)?;
+        }
+        psp22::InternalImpl::_transfer_from_to(self, from, to, amount - burnt, data)
+    }
+
+    impl DeflationaryToken {
+        #[ink(constructor)]
+        pub fn new(
+            total_supply: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimal: u8,
+            burn_bps: u16,
+        ) -> Self {
+            assert!(
+                burn_bps as u128 <= BPS,
+                "DeflationaryToken: INVALID_BURN_BPS"
+            );
+            let mut instance = Self::default();
+            psp22::Internal::_mint_to(&mut instance, Self::env().caller(), total_supply)
+                .expect("Should mint");
+            instance.metadata.name.set(&name);
+            instance.metadata.symbol.set(&symbol);
+            instance.metadata.decimals.set(&decimal);
+            instance.burn_bps = burn_bps;
+            instance
+        }
+
+        #[ink(message)]
+        pub fn burn_bps(&self) -> u16 {
+            self.burn_bps
+        }
+    }
+}
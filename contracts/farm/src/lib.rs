@@ -0,0 +1,644 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Liquidity mining after SushiSwap's MasterChef. Users stake a pair's LP token in one of
+/// the farm's pools and earn `reward_token`, emitted at `reward_per_ms` and split between
+/// pools by their allocation points. Rewards are paid out of the farm's own balance, so
+/// the owner funds it by transferring reward tokens to it.
+#[ink::contract]
+pub mod farm {
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use uniswap_math::{pair::mul_div, MathError};
+
+    /// Scale `acc_reward_per_share` is kept in, so small rewards on large stakes add up.
+    const ACC_REWARD_PRECISION: Balance = 1_000_000_000_000;
+
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        pid: u32,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdraw {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        pid: u32,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Harvest {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        pid: u32,
+        amount: Balance,
+    }
+
+    /// A user took their stake out of `pid` and forfeited its pending reward.
+    #[ink(event)]
+    pub struct EmergencyWithdraw {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        pid: u32,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PoolAdded {
+        #[ink(topic)]
+        pid: u32,
+        #[ink(topic)]
+        lp_token: AccountId,
+        alloc_point: u32,
+    }
+
+    #[ink(event)]
+    pub struct AllocPointChanged {
+        #[ink(topic)]
+        pid: u32,
+        alloc_point: u32,
+    }
+
+    #[ink(event)]
+    pub struct RewardRateChanged {
+        previous: Balance,
+        new: Balance,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        NotOwner,
+        PoolNotFound,
+        /// The LP token already has a pool, or is the reward token.
+        InvalidLpToken,
+        /// Withdrawing more than the caller staked.
+        InsufficientStake,
+        TransferFailed,
+        Math(MathError),
+    }
+
+    impl From<MathError> for Error {
+        fn from(error: MathError) -> Self {
+            Error::Math(error)
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PoolInfo {
+        pub lp_token: AccountId,
+        /// Share of the emission, out of `total_alloc_point`.
+        pub alloc_point: u32,
+        /// Block timestamp rewards have been accounted up to.
+        pub last_reward_time: Timestamp,
+        /// Reward earned per staked LP token since the pool was added, times
+        /// `ACC_REWARD_PRECISION`.
+        pub acc_reward_per_share: u128,
+        pub total_staked: Balance,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct UserInfo {
+        pub amount: Balance,
+        /// Reward the stake would have earned had it always been there, already paid out
+        /// or never owed.
+        pub reward_debt: Balance,
+    }
+
+    #[ink(storage)]
+    pub struct Farm {
+        owner: AccountId,
+        reward_token: AccountId,
+        /// Reward emitted per millisecond, across all pools
+        reward_per_ms: Balance,
+        total_alloc_point: u32,
+        pools: Vec<PoolInfo>,
+        /// Pool of each LP token, so none is added twice
+        pool_ids: Mapping<AccountId, u32>,
+        users: Mapping<(u32, AccountId), UserInfo>,
+    }
+
+    impl Farm {
+        #[ink(constructor)]
+        pub fn new(reward_token: AccountId, reward_per_ms: Balance) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                reward_token,
+                reward_per_ms,
+                total_alloc_point: 0,
+                pools: Vec::new(),
+                pool_ids: Mapping::default(),
+                users: Mapping::default(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.owner = new_owner;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reward_token(&self) -> AccountId {
+            self.reward_token
+        }
+
+        #[ink(message)]
+        pub fn reward_per_ms(&self) -> Balance {
+            self.reward_per_ms
+        }
+
+        #[ink(message)]
+        pub fn total_alloc_point(&self) -> u32 {
+            self.total_alloc_point
+        }
+
+        #[ink(message)]
+        pub fn pool_length(&self) -> u32 {
+            self.pools.len() as u32
+        }
+
+        #[ink(message)]
+        pub fn pool_info(&self, pid: u32) -> Option<PoolInfo> {
+            self.pools.get(pid as usize).cloned()
+        }
+
+        #[ink(message)]
+        pub fn pool_id(&self, lp_token: AccountId) -> Option<u32> {
+            self.pool_ids.get(lp_token)
+        }
+
+        #[ink(message)]
+        pub fn user_info(&self, pid: u32, user: AccountId) -> UserInfo {
+            self.users.get((pid, user)).unwrap_or_default()
+        }
+
+        /// Reward `user` would get from harvesting `pid` now.
+        #[ink(message)]
+        pub fn pending_reward(&self, pid: u32, user: AccountId) -> Result<Balance> {
+            let pool = self.accrue(self.pool(pid)?)?;
+            Self::pending(&pool, &self.user_info(pid, user))
+        }
+
+        /// Opens a pool for `lp_token` earning `alloc_point` shares of the emission.
+        /// Returns its id.
+        #[ink(message)]
+        pub fn add(&mut self, lp_token: AccountId, alloc_point: u32) -> Result<u32> {
+            self.ensure_owner()?;
+            if lp_token == self.reward_token || self.pool_ids.contains(lp_token) {
+                return Err(Error::InvalidLpToken);
+            }
+            self.mass_update_pools()?;
+
+            self.total_alloc_point = self
+                .total_alloc_point
+                .checked_add(alloc_point)
+                .ok_or(MathError::Overflow)?;
+            let pid = self.pools.len() as u32;
+            self.pools.push(PoolInfo {
+                lp_token,
+                alloc_point,
+                last_reward_time: self.env().block_timestamp(),
+                acc_reward_per_share: 0,
+                total_staked: 0,
+            });
+            self.pool_ids.insert(lp_token, &pid);
+            self.env().emit_event(PoolAdded {
+                pid,
+                lp_token,
+                alloc_point,
+            });
+            Ok(pid)
+        }
+
+        /// Changes the share of the emission `pid` earns from now on.
+        #[ink(message)]
+        pub fn set(&mut self, pid: u32, alloc_point: u32) -> Result<()> {
+            self.ensure_owner()?;
+            self.pool(pid)?;
+            self.mass_update_pools()?;
+
+            let pool = &mut self.pools[pid as usize];
+            self.total_alloc_point = (self.total_alloc_point - pool.alloc_point)
+                .checked_add(alloc_point)
+                .ok_or(MathError::Overflow)?;
+            pool.alloc_point = alloc_point;
+            self.env()
+                .emit_event(AllocPointChanged { pid, alloc_point });
+            Ok(())
+        }
+
+        /// Changes the emission from now on. Rewards up to now are accounted at the old rate.
+        #[ink(message)]
+        pub fn set_reward_per_ms(&mut self, reward_per_ms: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.mass_update_pools()?;
+
+            let previous = self.reward_per_ms;
+            self.reward_per_ms = reward_per_ms;
+            self.env().emit_event(RewardRateChanged {
+                previous,
+                new: reward_per_ms,
+            });
+            Ok(())
+        }
+
+        /// Accounts the rewards of `pid` up to now.
+        #[ink(message)]
+        pub fn update_pool(&mut self, pid: u32) -> Result<()> {
+            let pool = self.accrue(self.pool(pid)?)?;
+            self.pools[pid as usize] = pool;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn mass_update_pools(&mut self) -> Result<()> {
+            for pid in 0..self.pool_length() {
+                self.update_pool(pid)?;
+            }
+            Ok(())
+        }
+
+        /// Stakes `amount` of the pool's LP token, which the farm must be allowed to spend,
+        /// and pays out the reward pending so far.
+        #[ink(message)]
+        pub fn deposit(&mut self, pid: u32, amount: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let (mut pool, mut user, reward) = self.settle(pid, caller)?;
+            if amount > 0 {
+                if !ext::transfer_from(pool.lp_token, caller, self.env().account_id(), amount) {
+                    return Err(Error::TransferFailed);
+                }
+                user.amount = user.amount.checked_add(amount).ok_or(MathError::Overflow)?;
+                pool.total_staked = pool
+                    .total_staked
+                    .checked_add(amount)
+                    .ok_or(MathError::Overflow)?;
+            }
+            self.store(pid, caller, pool, user)?;
+
+            self.pay_reward(pid, caller, reward)?;
+            self.env().emit_event(Deposit {
+                user: caller,
+                pid,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Unstakes `amount` and pays out the reward pending so far.
+        #[ink(message)]
+        pub fn withdraw(&mut self, pid: u32, amount: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let (mut pool, mut user, reward) = self.settle(pid, caller)?;
+            if user.amount < amount {
+                return Err(Error::InsufficientStake);
+            }
+            user.amount -= amount;
+            pool.total_staked -= amount;
+            let lp_token = pool.lp_token;
+            self.store(pid, caller, pool, user)?;
+
+            if amount > 0 && !ext::transfer(lp_token, caller, amount) {
+                return Err(Error::TransferFailed);
+            }
+            self.pay_reward(pid, caller, reward)?;
+            self.env().emit_event(Withdraw {
+                user: caller,
+                pid,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Pays out the reward pending so far, leaving the stake as is. Returns it.
+        #[ink(message)]
+        pub fn harvest(&mut self, pid: u32) -> Result<Balance> {
+            let caller = self.env().caller();
+            let (pool, user, reward) = self.settle(pid, caller)?;
+            self.store(pid, caller, pool, user)?;
+            self.pay_reward(pid, caller, reward)?;
+            Ok(reward)
+        }
+
+        /// Returns the whole stake without touching rewards, which are forfeited. A way out
+        /// should reward accounting ever fail.
+        #[ink(message)]
+        pub fn emergency_withdraw(&mut self, pid: u32) -> Result<()> {
+            let caller = self.env().caller();
+            let mut pool = self.pool(pid)?;
+            let amount = self.user_info(pid, caller).amount;
+            pool.total_staked -= amount;
+            let lp_token = pool.lp_token;
+            self.pools[pid as usize] = pool;
+            self.users.remove((pid, caller));
+
+            if amount > 0 && !ext::transfer(lp_token, caller, amount) {
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(EmergencyWithdraw {
+                user: caller,
+                pid,
+                amount,
+            });
+            Ok(())
+        }
+
+        fn pool(&self, pid: u32) -> Result<PoolInfo> {
+            self.pool_info(pid).ok_or(Error::PoolNotFound)
+        }
+
+        /// `pool` with its rewards accounted up to now. Rewards of a pool nobody stakes in
+        /// are not emitted.
+        fn accrue(&self, mut pool: PoolInfo) -> Result<PoolInfo> {
+            let now = self.env().block_timestamp();
+            if now <= pool.last_reward_time {
+                return Ok(pool);
+            }
+            if pool.total_staked > 0 && self.total_alloc_point > 0 {
+                let emitted = Balance::from(now - pool.last_reward_time)
+                    .checked_mul(self.reward_per_ms)
+                    .ok_or(MathError::Overflow)?;
+                let reward = mul_div(
+                    emitted,
+                    pool.alloc_point.into(),
+                    self.total_alloc_point.into(),
+                )?;
+                pool.acc_reward_per_share = pool
+                    .acc_reward_per_share
+                    .checked_add(mul_div(reward, ACC_REWARD_PRECISION, pool.total_staked)?)
+                    .ok_or(MathError::Overflow)?;
+            }
+            pool.last_reward_time = now;
+            Ok(pool)
+        }
+
+        /// Reward `amount` staked since the pool was added would have earned.
+        fn earned(pool: &PoolInfo, amount: Balance) -> Result<Balance> {
+            Ok(mul_div(
+                amount,
+                pool.acc_reward_per_share,
+                ACC_REWARD_PRECISION,
+            )?)
+        }
+
+        fn pending(pool: &PoolInfo, user: &UserInfo) -> Result<Balance> {
+            Ok(Self::earned(pool, user.amount)?.saturating_sub(user.reward_debt))
+        }
+
+        /// `pid` accounted up to now, with `account`'s stake and pending reward.
+        fn settle(&self, pid: u32, account: AccountId) -> Result<(PoolInfo, UserInfo, Balance)> {
+            let pool = self.accrue(self.pool(pid)?)?;
+            let user = self.user_info(pid, account);
+            let reward = Self::pending(&pool, &user)?;
+            Ok((pool, user, reward))
+        }
+
+        /// Saves `pool` and `account`'s stake, owed nothing from here on.
+        fn store(
+            &mut self,
+            pid: u32,
+            account: AccountId,
+            pool: PoolInfo,
+            mut user: UserInfo,
+        ) -> Result<()> {
+            user.reward_debt = Self::earned(&pool, user.amount)?;
+            self.pools[pid as usize] = pool;
+            if user == UserInfo::default() {
+                self.users.remove((pid, account));
+            } else {
+                self.users.insert((pid, account), &user);
+            }
+            Ok(())
+        }
+
+        fn pay_reward(&self, pid: u32, to: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+            if !ext::transfer(self.reward_token, to, amount) {
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(Harvest {
+                user: to,
+                pid,
+                amount,
+            });
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() == self.owner {
+                Ok(())
+            } else {
+                Err(Error::NotOwner)
+            }
+        }
+    }
+
+    #[cfg(not(test))]
+    use uniswap_support::ext;
+    #[cfg(test)]
+    use uniswap_support::mock as ext;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use uniswap_support::mock;
+
+        const FARM: [u8; 32] = [0x20; 32];
+        const REWARD: [u8; 32] = [0x21; 32];
+        const LP_A: [u8; 32] = [0x22; 32];
+        const LP_B: [u8; 32] = [0x23; 32];
+
+        /// Reward emitted per millisecond in the tests.
+        const RATE: Balance = 1_000;
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        fn set_time(timestamp: Timestamp) {
+            test::set_block_timestamp::<DefaultEnvironment>(timestamp);
+        }
+
+        fn set_caller(caller: AccountId) {
+            test::set_caller::<DefaultEnvironment>(caller);
+        }
+
+        /// A funded farm owned by Alice with a pool for `LP_A`, at time 1_000. Alice and
+        /// Bob hold `LP_A` and `LP_B`.
+        fn setup() -> Farm {
+            set_time(1_000);
+            set_caller(accounts().alice);
+            test::set_callee::<DefaultEnvironment>(FARM.into());
+            mock::mint(REWARD.into(), FARM.into(), 1_000_000_000);
+            for user in [accounts().alice, accounts().bob] {
+                mock::mint(LP_A.into(), user, 1_000_000);
+                mock::mint(LP_B.into(), user, 1_000_000);
+            }
+            let mut farm = Farm::new(REWARD.into(), RATE);
+            assert_eq!(farm.add(LP_A.into(), 100), Ok(0));
+            farm
+        }
+
+        #[ink::test]
+        fn only_the_owner_manages_pools() {
+            let mut farm = setup();
+
+            set_caller(accounts().bob);
+            assert_eq!(farm.add(LP_B.into(), 100), Err(Error::NotOwner));
+            assert_eq!(farm.set(0, 50), Err(Error::NotOwner));
+            assert_eq!(farm.set_reward_per_ms(1), Err(Error::NotOwner));
+            assert_eq!(
+                farm.transfer_ownership(accounts().bob),
+                Err(Error::NotOwner)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(farm.transfer_ownership(accounts().bob), Ok(()));
+            assert_eq!(farm.owner(), accounts().bob);
+        }
+
+        #[ink::test]
+        fn lp_tokens_get_one_pool() {
+            let mut farm = setup();
+
+            assert_eq!(farm.add(LP_A.into(), 100), Err(Error::InvalidLpToken));
+            assert_eq!(farm.add(REWARD.into(), 100), Err(Error::InvalidLpToken));
+            assert_eq!(farm.add(LP_B.into(), 100), Ok(1));
+            assert_eq!(farm.pool_id(LP_B.into()), Some(1));
+            assert_eq!(farm.pool_length(), 2);
+            assert_eq!(farm.set(2, 100), Err(Error::PoolNotFound));
+        }
+
+        #[ink::test]
+        fn rewards_are_shared_by_stake_over_time() {
+            let mut farm = setup();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            farm.deposit(0, 100).unwrap();
+            set_time(1_010);
+            set_caller(bob);
+            farm.deposit(0, 300).unwrap();
+            set_time(1_030);
+
+            // Alice had the pool to herself for 10ms, then a quarter of it for 20ms.
+            assert_eq!(farm.pending_reward(0, alice), Ok(10 * RATE + 20 * RATE / 4));
+            assert_eq!(farm.pending_reward(0, bob), Ok(20 * RATE * 3 / 4));
+
+            assert_eq!(farm.harvest(0), Ok(15 * RATE));
+            assert_eq!(mock::balance(REWARD, bob), 15 * RATE);
+            assert_eq!(farm.pending_reward(0, bob), Ok(0));
+            assert_eq!(farm.user_info(0, bob).amount, 300);
+        }
+
+        #[ink::test]
+        fn pools_split_the_emission_by_alloc_point() {
+            let mut farm = setup();
+            farm.add(LP_B.into(), 300).unwrap();
+
+            farm.deposit(0, 100).unwrap();
+            farm.deposit(1, 100).unwrap();
+            set_time(1_100);
+
+            assert_eq!(farm.pending_reward(0, accounts().alice), Ok(100 * RATE / 4));
+            assert_eq!(
+                farm.pending_reward(1, accounts().alice),
+                Ok(100 * RATE * 3 / 4)
+            );
+
+            farm.set(1, 100).unwrap();
+            assert_eq!(farm.total_alloc_point(), 200);
+            set_time(1_200);
+            assert_eq!(
+                farm.pending_reward(1, accounts().alice),
+                Ok(75 * RATE + 50 * RATE)
+            );
+        }
+
+        #[ink::test]
+        fn rate_changes_apply_from_then_on() {
+            let mut farm = setup();
+            farm.deposit(0, 100).unwrap();
+
+            set_time(1_010);
+            farm.set_reward_per_ms(3 * RATE).unwrap();
+            set_time(1_020);
+
+            assert_eq!(
+                farm.pending_reward(0, accounts().alice),
+                Ok(10 * RATE + 30 * RATE)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_returns_the_stake_and_pays_the_reward() {
+            let mut farm = setup();
+            let alice = accounts().alice;
+            farm.deposit(0, 100).unwrap();
+            assert_eq!(mock::balance(LP_A, alice), 1_000_000 - 100);
+
+            set_time(1_010);
+            assert_eq!(farm.withdraw(0, 101), Err(Error::InsufficientStake));
+            farm.withdraw(0, 50).unwrap();
+
+            assert_eq!(mock::balance(LP_A, alice), 1_000_000 - 50);
+            assert_eq!(mock::balance(REWARD, alice), 10 * RATE);
+            assert_eq!(farm.pool_info(0).unwrap().total_staked, 50);
+
+            set_time(1_020);
+            farm.withdraw(0, 50).unwrap();
+            assert_eq!(mock::balance(REWARD, alice), 20 * RATE);
+            assert_eq!(farm.user_info(0, alice), UserInfo::default());
+        }
+
+        #[ink::test]
+        fn emergency_withdraw_forfeits_the_reward() {
+            let mut farm = setup();
+            let alice = accounts().alice;
+            farm.deposit(0, 100).unwrap();
+            set_time(1_010);
+
+            farm.emergency_withdraw(0).unwrap();
+
+            assert_eq!(mock::balance(LP_A, alice), 1_000_000);
+            assert_eq!(mock::balance(REWARD, alice), 0);
+            assert_eq!(farm.user_info(0, alice), UserInfo::default());
+            assert_eq!(farm.pool_info(0).unwrap().total_staked, 0);
+        }
+
+        #[ink::test]
+        fn deposits_need_the_lp_tokens() {
+            let mut farm = setup();
+            set_caller(accounts().charlie);
+
+            assert_eq!(farm.deposit(0, 1), Err(Error::TransferFailed));
+            assert_eq!(farm.deposit(1, 1), Err(Error::PoolNotFound));
+        }
+    }
+}
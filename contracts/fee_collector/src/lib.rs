@@ -0,0 +1,639 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Turns the protocol fee into a single token, after SushiSwap's SushiMaker. Set as the
+/// factory's `fee_to`, it accrues the LP tokens `mint_fee` mints. For each listed pair,
+/// `convert` burns them and swaps both tokens into `target` through their pair with it,
+/// sending the proceeds to `recipient`. Only the owner converts, with a minimum output,
+/// so conversions cannot be sandwiched at a bad price.
+#[ink::contract]
+pub mod fee_collector {
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use uniswap_math::{dynamic_fee, sort_tokens, MathError};
+
+    #[ink(event)]
+    pub struct Converted {
+        #[ink(topic)]
+        pair: AccountId,
+        /// LP tokens burned.
+        liquidity: Balance,
+        amount_0: Balance,
+        amount_1: Balance,
+        /// `target` sent to the recipient.
+        amount_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PairListed {
+        #[ink(topic)]
+        pair: AccountId,
+        listed: bool,
+    }
+
+    #[ink(event)]
+    pub struct RecipientChanged {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        NotOwner,
+        PairNotListed,
+        /// The collector holds none of the pair's LP token.
+        NothingToConvert,
+        /// A token of the pair has no pair with `target` to be swapped through.
+        NoRoute,
+        /// The conversion yields less `target` than asked for.
+        InsufficientOutputAmount,
+        TransferFailed,
+        CallFailed,
+        Math(MathError),
+    }
+
+    impl From<MathError> for Error {
+        fn from(error: MathError) -> Self {
+            Error::Math(error)
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[ink(storage)]
+    pub struct FeeCollector {
+        owner: AccountId,
+        factory: AccountId,
+        /// Token the fees are converted into
+        target: AccountId,
+        /// Receives the converted fees
+        recipient: AccountId,
+        listed_pairs: Mapping<AccountId, ()>,
+    }
+
+    impl FeeCollector {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId, target: AccountId, recipient: AccountId) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                factory,
+                target,
+                recipient,
+                listed_pairs: Mapping::default(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.owner = new_owner;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn factory(&self) -> AccountId {
+            self.factory
+        }
+
+        #[ink(message)]
+        pub fn target(&self) -> AccountId {
+            self.target
+        }
+
+        #[ink(message)]
+        pub fn recipient(&self) -> AccountId {
+            self.recipient
+        }
+
+        #[ink(message)]
+        pub fn set_recipient(&mut self, recipient: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let previous = self.recipient;
+            self.recipient = recipient;
+            self.env().emit_event(RecipientChanged {
+                previous,
+                new: recipient,
+            });
+            Ok(())
+        }
+
+        /// Whether the fees accrued in `pair` may be converted.
+        #[ink(message)]
+        pub fn is_listed(&self, pair: AccountId) -> bool {
+            self.listed_pairs.contains(pair)
+        }
+
+        #[ink(message)]
+        pub fn set_listed(&mut self, pair: AccountId, listed: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if listed {
+                self.listed_pairs.insert(pair, &());
+            } else {
+                self.listed_pairs.remove(pair);
+            }
+            self.env().emit_event(PairListed { pair, listed });
+            Ok(())
+        }
+
+        /// Burns the LP tokens of `pair` the collector holds and swaps what they return
+        /// into `target`, for at least `min_amount_out`. Returns the `target` sent to the
+        /// recipient.
+        #[ink(message)]
+        pub fn convert(&mut self, pair: AccountId, min_amount_out: Balance) -> Result<Balance> {
+            self.ensure_owner()?;
+            self.convert_pair(pair, min_amount_out)
+        }
+
+        /// `convert` on each pair with its minimum output, all or nothing. Returns the
+        /// total `target` sent to the recipient.
+        #[ink(message)]
+        pub fn convert_many(&mut self, pairs: Vec<(AccountId, Balance)>) -> Result<Balance> {
+            self.ensure_owner()?;
+            let mut total: Balance = 0;
+            for (pair, min_amount_out) in pairs {
+                let amount_out = self.convert_pair(pair, min_amount_out)?;
+                total = total.checked_add(amount_out).ok_or(MathError::Overflow)?;
+            }
+            Ok(total)
+        }
+
+        fn convert_pair(&mut self, pair: AccountId, min_amount_out: Balance) -> Result<Balance> {
+            if !self.is_listed(pair) {
+                return Err(Error::PairNotListed);
+            }
+            let lp_token = ext::lp_token(pair).ok_or(Error::CallFailed)?;
+            let liquidity =
+                ext::balance_of(lp_token, self.env().account_id()).ok_or(Error::CallFailed)?;
+            if liquidity == 0 {
+                return Err(Error::NothingToConvert);
+            }
+            let (token_0, token_1) = ext::tokens(pair).ok_or(Error::CallFailed)?;
+
+            if !ext::transfer(lp_token, pair, liquidity) {
+                return Err(Error::TransferFailed);
+            }
+            let (amount_0, amount_1) =
+                ext::burn(pair, self.env().account_id()).ok_or(Error::CallFailed)?;
+            let amount_out = self
+                .swap_to_target(token_0, amount_0)?
+                .checked_add(self.swap_to_target(token_1, amount_1)?)
+                .ok_or(MathError::Overflow)?;
+            if amount_out < min_amount_out {
+                return Err(Error::InsufficientOutputAmount);
+            }
+
+            self.env().emit_event(Converted {
+                pair,
+                liquidity,
+                amount_0,
+                amount_1,
+                amount_out,
+            });
+            Ok(amount_out)
+        }
+
+        /// Sends `amount_in` of `token` to the recipient as `target`, swapped through the
+        /// classic pair of the two unless it is `target` already. Returns the `target` sent.
+        fn swap_to_target(&self, token: AccountId, amount_in: Balance) -> Result<Balance> {
+            if amount_in == 0 {
+                return Ok(0);
+            }
+            if token == self.target {
+                return if ext::transfer(token, self.recipient, amount_in) {
+                    Ok(amount_in)
+                } else {
+                    Err(Error::TransferFailed)
+                };
+            }
+
+            let pair = ext::get_pair(self.factory, token, self.target).ok_or(Error::NoRoute)?;
+            let (reserve_0, reserve_1, _) = ext::get_reserves(pair).ok_or(Error::CallFailed)?;
+            let fee = ext::fee(pair).ok_or(Error::CallFailed)?;
+            let (token_0, _) = sort_tokens(token, self.target)?;
+            let (reserve_in, reserve_out) = if token == token_0 {
+                (reserve_0, reserve_1)
+            } else {
+                (reserve_1, reserve_0)
+            };
+            let amount_out = dynamic_fee::get_amount_out(amount_in, reserve_in, reserve_out, fee)?;
+            let (amount_0_out, amount_1_out) = if token == token_0 {
+                (0, amount_out)
+            } else {
+                (amount_out, 0)
+            };
+
+            if !ext::transfer(token, pair, amount_in) {
+                return Err(Error::TransferFailed);
+            }
+            if !ext::swap(pair, amount_0_out, amount_1_out, self.recipient) {
+                return Err(Error::CallFailed);
+            }
+            Ok(amount_out)
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() == self.owner {
+                Ok(())
+            } else {
+                Err(Error::NotOwner)
+            }
+        }
+    }
+
+    /// Calls into the pairs and the factory, next to the PSP22 calls of `uniswap_support`.
+    /// Unit tests swap this module for `tests::mock`, since the off-chain environment
+    /// cannot call other contracts. Calls that fail return `None` or `false`.
+    #[cfg(not(test))]
+    mod ext {
+        use super::{AccountId, Balance};
+        use ink::env::{
+            call::{build_call, ExecutionInput, Selector},
+            DefaultEnvironment,
+        };
+        use ink::prelude::vec::Vec;
+        use uniswap_support::ext::query;
+
+        pub use uniswap_support::ext::{balance_of, transfer};
+
+        pub fn lp_token(pair: AccountId) -> Option<AccountId> {
+            query(pair, ink::selector_bytes!("lp_token"))
+        }
+
+        pub fn tokens(pair: AccountId) -> Option<(AccountId, AccountId)> {
+            Some((
+                query(pair, ink::selector_bytes!("token_0"))?,
+                query(pair, ink::selector_bytes!("token_1"))?,
+            ))
+        }
+
+        pub fn get_reserves(pair: AccountId) -> Option<(u128, u128, u128)> {
+            query(pair, ink::selector_bytes!("get_reserves"))
+        }
+
+        pub fn fee(pair: AccountId) -> Option<u32> {
+            query(pair, ink::selector_bytes!("fee"))
+        }
+
+        pub fn burn(pair: AccountId, to: AccountId) -> Option<(Balance, Balance)> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("burn"))).push_arg(to),
+                )
+                .returns::<(Balance, Balance)>()
+                .try_invoke()
+                .ok()?
+                .ok()
+        }
+
+        pub fn swap(
+            pair: AccountId,
+            amount_0_out: Balance,
+            amount_1_out: Balance,
+            to: AccountId,
+        ) -> bool {
+            let result = build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("swap")))
+                        .push_arg(amount_0_out)
+                        .push_arg(amount_1_out)
+                        .push_arg(to)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<()>()
+                .try_invoke();
+
+            matches!(result, Ok(Ok(())))
+        }
+
+        pub fn get_pair(
+            factory: AccountId,
+            token_a: AccountId,
+            token_b: AccountId,
+        ) -> Option<AccountId> {
+            build_call::<DefaultEnvironment>()
+                .call(factory)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("get_pair")))
+                        .push_arg(token_a)
+                        .push_arg(token_b),
+                )
+                .returns::<Option<AccountId>>()
+                .try_invoke()
+                .ok()?
+                .ok()?
+        }
+    }
+
+    #[cfg(test)]
+    use tests::mock as ext;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+
+        /// Constant product pairs on the PSP22 ledgers of `uniswap_support::mock`, standing in
+        /// for `ext`.
+        pub mod mock {
+            use super::super::{AccountId, Balance};
+            use std::cell::RefCell;
+            use std::collections::BTreeMap;
+            use uniswap_math::dynamic_fee;
+            use uniswap_support::mock as ledger;
+
+            pub use uniswap_support::mock::{balance, balance_of, mint, send, transfer};
+
+            #[derive(Clone, Copy)]
+            pub struct Pair {
+                pub lp_token: AccountId,
+                pub token_0: AccountId,
+                pub token_1: AccountId,
+                pub reserve_0: Balance,
+                pub reserve_1: Balance,
+                pub fee: u32,
+            }
+
+            thread_local! {
+                static PAIRS: RefCell<BTreeMap<AccountId, Pair>> =
+                    const { RefCell::new(BTreeMap::new()) };
+            }
+
+            fn pair(address: AccountId) -> Option<Pair> {
+                PAIRS.with(|pairs| pairs.borrow().get(&address).copied())
+            }
+
+            /// Registers a pair holding `reserve_0` and `reserve_1`, with `supply` LP tokens
+            /// minted to `lp_holder`.
+            pub fn add_pair(address: AccountId, pair: Pair, supply: Balance, lp_holder: AccountId) {
+                mint(pair.token_0, address, pair.reserve_0);
+                mint(pair.token_1, address, pair.reserve_1);
+                mint(pair.lp_token, lp_holder, supply);
+                PAIRS.with(|pairs| pairs.borrow_mut().insert(address, pair));
+            }
+
+            fn sync(address: AccountId) {
+                let mut pair = pair(address).unwrap();
+                pair.reserve_0 = balance(pair.token_0, address);
+                pair.reserve_1 = balance(pair.token_1, address);
+                PAIRS.with(|pairs| pairs.borrow_mut().insert(address, pair));
+            }
+
+            pub fn lp_token(pair_address: AccountId) -> Option<AccountId> {
+                pair(pair_address).map(|pair| pair.lp_token)
+            }
+
+            pub fn tokens(pair_address: AccountId) -> Option<(AccountId, AccountId)> {
+                pair(pair_address).map(|pair| (pair.token_0, pair.token_1))
+            }
+
+            pub fn get_reserves(pair_address: AccountId) -> Option<(u128, u128, u128)> {
+                pair(pair_address).map(|pair| (pair.reserve_0, pair.reserve_1, 0))
+            }
+
+            pub fn fee(pair_address: AccountId) -> Option<u32> {
+                pair(pair_address).map(|pair| pair.fee)
+            }
+
+            /// Burns the LP tokens sent to the pair for a proportional share of its reserves.
+            pub fn burn(pair_address: AccountId, to: AccountId) -> Option<(Balance, Balance)> {
+                let pair = pair(pair_address)?;
+                let liquidity = balance(pair.lp_token, pair_address);
+                let supply = ledger::total_supply(pair.lp_token).unwrap();
+                let amount_0 = liquidity * pair.reserve_0 / supply;
+                let amount_1 = liquidity * pair.reserve_1 / supply;
+                ledger::burn(pair.lp_token, pair_address, liquidity);
+                send(pair.token_0, pair_address, to, amount_0);
+                send(pair.token_1, pair_address, to, amount_1);
+                sync(pair_address);
+                Some((amount_0, amount_1))
+            }
+
+            /// Sends out the amounts if the tokens sent in beforehand keep K after the fee.
+            pub fn swap(
+                pair_address: AccountId,
+                amount_0_out: Balance,
+                amount_1_out: Balance,
+                to: AccountId,
+            ) -> bool {
+                let Some(pair) = pair(pair_address) else {
+                    return false;
+                };
+                let balance_0 = balance(pair.token_0, pair_address) - amount_0_out;
+                let balance_1 = balance(pair.token_1, pair_address) - amount_1_out;
+                let amount_0_in = balance_0.saturating_sub(pair.reserve_0 - amount_0_out);
+                let amount_1_in = balance_1.saturating_sub(pair.reserve_1 - amount_1_out);
+                if !dynamic_fee::k_holds(
+                    balance_0,
+                    balance_1,
+                    amount_0_in,
+                    amount_1_in,
+                    pair.reserve_0,
+                    pair.reserve_1,
+                    pair.fee,
+                ) {
+                    return false;
+                }
+                send(pair.token_0, pair_address, to, amount_0_out);
+                send(pair.token_1, pair_address, to, amount_1_out);
+                sync(pair_address);
+                true
+            }
+
+            pub fn get_pair(
+                _factory: AccountId,
+                token_a: AccountId,
+                token_b: AccountId,
+            ) -> Option<AccountId> {
+                PAIRS.with(|pairs| {
+                    pairs
+                        .borrow()
+                        .iter()
+                        .find(|(_, pair)| {
+                            (pair.token_0, pair.token_1) == (token_a, token_b)
+                                || (pair.token_0, pair.token_1) == (token_b, token_a)
+                        })
+                        .map(|(address, _)| *address)
+                })
+            }
+        }
+
+        const FACTORY: [u8; 32] = [0x30; 32];
+        const COLLECTOR: [u8; 32] = [0x31; 32];
+        const TOKEN_A: [u8; 32] = [0x32; 32];
+        const TOKEN_B: [u8; 32] = [0x33; 32];
+        const TARGET: [u8; 32] = [0x34; 32];
+        const TOKEN_C: [u8; 32] = [0x35; 32];
+        const PAIR_AB: [u8; 32] = [0x40; 32];
+        const PAIR_AT: [u8; 32] = [0x41; 32];
+        const PAIR_BT: [u8; 32] = [0x42; 32];
+        const PAIR_BC: [u8; 32] = [0x43; 32];
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        fn recipient() -> AccountId {
+            accounts().django
+        }
+
+        fn pair(
+            lp_token: [u8; 32],
+            tokens: ([u8; 32], [u8; 32]),
+            reserves: (Balance, Balance),
+            fee: u32,
+        ) -> mock::Pair {
+            mock::Pair {
+                lp_token: lp_token.into(),
+                token_0: tokens.0.into(),
+                token_1: tokens.1.into(),
+                reserve_0: reserves.0,
+                reserve_1: reserves.1,
+                fee,
+            }
+        }
+
+        /// A collector owned by Alice holding a tenth of the A/B pair's LP tokens, with
+        /// A/target and B/target pairs to swap through; B/target charges a dynamic 1%.
+        fn setup() -> FeeCollector {
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            test::set_callee::<DefaultEnvironment>(COLLECTOR.into());
+            let pairs = [
+                (
+                    PAIR_AB,
+                    pair(PAIR_AB, (TOKEN_A, TOKEN_B), (100_000, 400_000), 3_000),
+                ),
+                (
+                    PAIR_AT,
+                    pair(PAIR_AT, (TOKEN_A, TARGET), (1_000_000, 2_000_000), 3_000),
+                ),
+                (
+                    PAIR_BT,
+                    pair(PAIR_BT, (TOKEN_B, TARGET), (4_000_000, 2_000_000), 10_000),
+                ),
+            ];
+            for (address, pair) in pairs {
+                mock::add_pair(address.into(), pair, 200_000, accounts().bob);
+            }
+            let lp_fees = 20_000;
+            assert!(mock::send(
+                PAIR_AB.into(),
+                accounts().bob,
+                COLLECTOR.into(),
+                lp_fees
+            ));
+
+            let mut collector = FeeCollector::new(FACTORY.into(), TARGET.into(), recipient());
+            collector.set_listed(PAIR_AB.into(), true).unwrap();
+            collector
+        }
+
+        /// `target` the A/B fees convert into: 10_000 A and 40_000 B.
+        fn expected_amount_out() -> Balance {
+            dynamic_fee::get_amount_out(10_000, 1_000_000, 2_000_000, 3_000).unwrap()
+                + dynamic_fee::get_amount_out(40_000, 4_000_000, 2_000_000, 10_000).unwrap()
+        }
+
+        #[ink::test]
+        fn converts_lp_fees_into_the_target() {
+            let mut collector = setup();
+
+            assert_eq!(
+                collector.convert(PAIR_AB.into(), expected_amount_out()),
+                Ok(expected_amount_out())
+            );
+
+            assert_eq!(mock::balance(TARGET, recipient()), expected_amount_out());
+            assert_eq!(mock::balance(PAIR_AB, COLLECTOR.into()), 0);
+            assert_eq!(mock::balance(TOKEN_A, COLLECTOR.into()), 0);
+            assert_eq!(mock::balance(TOKEN_B, COLLECTOR.into()), 0);
+            assert_eq!(
+                collector.convert(PAIR_AB.into(), 0),
+                Err(Error::NothingToConvert)
+            );
+        }
+
+        #[ink::test]
+        fn conversions_below_the_minimum_fail() {
+            let mut collector = setup();
+
+            assert_eq!(
+                collector.convert(PAIR_AB.into(), expected_amount_out() + 1),
+                Err(Error::InsufficientOutputAmount)
+            );
+        }
+
+        #[ink::test]
+        fn only_listed_pairs_are_converted_by_the_owner() {
+            let mut collector = setup();
+
+            assert_eq!(
+                collector.convert(PAIR_AT.into(), 0),
+                Err(Error::PairNotListed)
+            );
+            collector.set_listed(PAIR_AB.into(), false).unwrap();
+            assert_eq!(
+                collector.convert(PAIR_AB.into(), 0),
+                Err(Error::PairNotListed)
+            );
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(collector.convert(PAIR_AB.into(), 0), Err(Error::NotOwner));
+            assert_eq!(
+                collector.set_listed(PAIR_AB.into(), true),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(
+                collector.set_recipient(accounts().bob),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn tokens_need_a_pair_with_the_target() {
+            let mut collector = setup();
+            mock::add_pair(
+                PAIR_BC.into(),
+                pair(PAIR_BC, (TOKEN_B, TOKEN_C), (1_000, 1_000), 3_000),
+                1_000,
+                COLLECTOR.into(),
+            );
+            collector.set_listed(PAIR_BC.into(), true).unwrap();
+
+            assert_eq!(
+                collector.convert_many(vec![(PAIR_BC.into(), 0)]),
+                Err(Error::NoRoute)
+            );
+        }
+
+        #[ink::test]
+        fn convert_many_sums_the_output() {
+            let mut collector = setup();
+            collector.set_listed(PAIR_AT.into(), true).unwrap();
+            assert!(mock::send(
+                PAIR_AT.into(),
+                accounts().bob,
+                COLLECTOR.into(),
+                20_000
+            ));
+
+            let total = collector
+                .convert_many(vec![(PAIR_AB.into(), 0), (PAIR_AT.into(), 0)])
+                .unwrap();
+
+            assert_eq!(mock::balance(TARGET, recipient()), total);
+            assert!(total > expected_amount_out());
+        }
+    }
+}
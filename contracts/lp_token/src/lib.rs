@@ -0,0 +1,397 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+//! The LP token of a pair. A plain PSP22 whose supply only its pair can mint or burn,
+//! with the selectors and events of `psp22token` so clients read both alike.
+
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+
+type Balance = u128;
+
+/// The errors of openbrush's `PSP22Error`, in the same order.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+#[ink::trait_definition]
+pub trait PSP22 {
+    #[ink(message)]
+    fn total_supply(&self) -> Balance;
+
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> Balance;
+
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn increase_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn decrease_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+}
+
+#[ink::trait_definition]
+pub trait PSP22Metadata {
+    #[ink(message)]
+    fn token_name(&self) -> Option<String>;
+
+    #[ink(message)]
+    fn token_symbol(&self) -> Option<String>;
+
+    #[ink(message)]
+    fn token_decimals(&self) -> u8;
+}
+
+#[ink::trait_definition]
+pub trait PSP22Mintable {
+    #[ink(message)]
+    fn mint(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error>;
+}
+
+#[ink::trait_definition]
+pub trait PSP22Burnable {
+    #[ink(message)]
+    fn burn(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error>;
+}
+
+#[ink::contract]
+pub mod lp_token {
+    use super::{PSP22Burnable, PSP22Error, PSP22Metadata, PSP22Mintable, PSP22};
+    use ink::prelude::{string::String, vec::Vec};
+    use ink::storage::Mapping;
+
+    #[ink(event)]
+    pub struct TransferEvent {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalEvent {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        value: Balance,
+    }
+
+    #[ink(storage)]
+    pub struct LpToken {
+        /// The pair minting and burning the supply.
+        pair: AccountId,
+        total_supply: Balance,
+        balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+        name: Option<String>,
+        symbol: Option<String>,
+        decimals: u8,
+    }
+
+    impl LpToken {
+        #[ink(constructor)]
+        pub fn new(
+            pair: AccountId,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            Self {
+                pair,
+                total_supply: 0,
+                balances: Mapping::new(),
+                allowances: Mapping::new(),
+                name,
+                symbol,
+                decimals,
+            }
+        }
+
+        #[ink(message)]
+        pub fn pair(&self) -> AccountId {
+            self.pair
+        }
+
+        fn ensure_pair(&self) -> Result<(), PSP22Error> {
+            if self.env().caller() != self.pair {
+                return Err(PSP22Error::Custom(String::from("UniswapV2: FORBIDDEN")));
+            }
+            Ok(())
+        }
+
+        fn transfer_from_to(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), PSP22Error> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            self.env().emit_event(TransferEvent {
+                from: Some(from),
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            self.allowances.insert((owner, spender), &value);
+            self.env().emit_event(ApprovalEvent {
+                owner,
+                spender,
+                value,
+            });
+        }
+    }
+
+    impl PSP22 for LpToken {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let from = self.env().caller();
+            self.transfer_from_to(from, to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.transfer_from_to(from, to, value)?;
+            self.set_allowance(from, spender, allowance - value);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            self.set_allowance(owner, spender, value);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            let allowance = self.allowance(owner, spender).saturating_add(delta_value);
+            self.set_allowance(owner, spender, allowance);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            let allowance = self
+                .allowance(owner, spender)
+                .checked_sub(delta_value)
+                .ok_or(PSP22Error::InsufficientAllowance)?;
+            self.set_allowance(owner, spender, allowance);
+            Ok(())
+        }
+    }
+
+    impl PSP22Metadata for LpToken {
+        #[ink(message)]
+        fn token_name(&self) -> Option<String> {
+            self.name.clone()
+        }
+
+        #[ink(message)]
+        fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
+        }
+
+        #[ink(message)]
+        fn token_decimals(&self) -> u8 {
+            self.decimals
+        }
+    }
+
+    impl PSP22Mintable for LpToken {
+        /// Only the pair mints, as it takes in liquidity.
+        #[ink(message)]
+        fn mint(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
+            self.ensure_pair()?;
+            self.total_supply = self
+                .total_supply
+                .checked_add(amount)
+                .ok_or(PSP22Error::Custom(String::from("UniswapV2: OVERFLOW")))?;
+            let balance = self.balance_of(account);
+            self.balances.insert(account, &(balance + amount));
+            self.env().emit_event(TransferEvent {
+                from: None,
+                to: Some(account),
+                value: amount,
+            });
+            Ok(())
+        }
+    }
+
+    impl PSP22Burnable for LpToken {
+        /// Only the pair burns, from the liquidity sent back to it.
+        #[ink(message)]
+        fn burn(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
+            self.ensure_pair()?;
+            let balance = self.balance_of(account);
+            if balance < amount {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(account, &(balance - amount));
+            self.total_supply -= amount;
+            self.env().emit_event(TransferEvent {
+                from: Some(account),
+                to: None,
+                value: amount,
+            });
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        /// An LP token of Alice's pair, with 1_000 minted to Bob.
+        fn setup() -> LpToken {
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            let mut token = LpToken::new(accounts().alice, None, Some("UNI-V2".into()), 18);
+            token.mint(accounts().bob, 1_000).unwrap();
+            token
+        }
+
+        fn forbidden() -> Result<(), PSP22Error> {
+            Err(PSP22Error::Custom("UniswapV2: FORBIDDEN".into()))
+        }
+
+        #[ink::test]
+        fn the_pair_mints_and_burns() {
+            let mut token = setup();
+            let bob = accounts().bob;
+
+            token.burn(bob, 400).unwrap();
+
+            assert_eq!(token.balance_of(bob), 600);
+            assert_eq!(token.total_supply(), 600);
+            assert_eq!(token.pair(), accounts().alice);
+            assert_eq!(token.burn(bob, 601), Err(PSP22Error::InsufficientBalance));
+            assert_eq!(test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn others_cannot_mint_or_burn() {
+            let mut token = setup();
+            let (bob, charlie) = (accounts().bob, accounts().charlie);
+
+            test::set_caller::<DefaultEnvironment>(charlie);
+            assert_eq!(token.mint(charlie, 1_000), forbidden());
+            assert_eq!(token.burn(bob, 1_000), forbidden());
+            // Not even holders, over their own balance.
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(token.burn(bob, 1_000), forbidden());
+
+            assert_eq!(token.balance_of(bob), 1_000);
+            assert_eq!(token.balance_of(charlie), 0);
+            assert_eq!(token.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn transfers_spend_the_allowance() {
+            let mut token = setup();
+            let (bob, charlie) = (accounts().bob, accounts().charlie);
+
+            test::set_caller::<DefaultEnvironment>(bob);
+            token.approve(charlie, 300).unwrap();
+            test::set_caller::<DefaultEnvironment>(charlie);
+            token.transfer_from(bob, charlie, 200, Vec::new()).unwrap();
+
+            assert_eq!(
+                token.transfer_from(bob, charlie, 200, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(token.allowance(bob, charlie), 100);
+            assert_eq!(token.balance_of(charlie), 200);
+            assert_eq!(
+                token.transfer(bob, 201, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Batches calls to other contracts. Batched calls are made with this contract as the
+/// caller, so each user deploys their own, grants it the allowances the batch spends, and
+/// is the only one who can run batches through it. Approving it for two tokens and then
+/// batching `PSP22::transfer_from(owner, pair, amount)` on both with `mint(owner)` on the
+/// pair adds liquidity in one transaction, leaving nothing in the pair for anyone to `skim`
+/// or `mint` in between.
+#[ink::contract]
+pub mod multicall {
+    use ink::env::{
+        call::{build_call, ExecutionInput, Selector},
+        DefaultEnvironment,
+    };
+    use ink::prelude::vec::Vec;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        NotOwner,
+        /// The values of the calls do not add up to the value sent with the batch.
+        ValueMismatch,
+        /// The call at this index failed without allowing failure.
+        CallFailed(u32),
+        /// The value of the calls that failed could not be sent back.
+        RefundFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// A message to call. `input` holds its SCALE encoded arguments, without the selector.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Call {
+        pub callee: AccountId,
+        pub selector: [u8; 4],
+        pub input: Vec<u8>,
+        /// Native value sent along, for payable messages.
+        pub value: Balance,
+        /// Whether the batch carries on if this call fails, instead of reverting.
+        pub allow_failure: bool,
+    }
+
+    /// The result of one call: its SCALE encoded return value when it succeeded.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Outcome {
+        pub success: bool,
+        pub output: Vec<u8>,
+    }
+
+    /// Arguments that are already encoded, written out as they are.
+    struct RawInput<'a>(&'a [u8]);
+
+    impl scale::Encode for RawInput<'_> {
+        fn size_hint(&self) -> usize {
+            self.0.len()
+        }
+
+        fn encode_to<T: scale::Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0)
+        }
+    }
+
+    /// A return value left encoded: all that follows the `LangError` result byte.
+    struct RawOutput(Vec<u8>);
+
+    impl scale::Decode for RawOutput {
+        fn decode<I: scale::Input>(input: &mut I) -> core::result::Result<Self, scale::Error> {
+            let mut output = Vec::new();
+            while let Ok(byte) = input.read_byte() {
+                output.push(byte);
+            }
+            Ok(Self(output))
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Multicall {
+        owner: AccountId,
+    }
+
+    impl Default for Multicall {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Multicall {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.owner = new_owner;
+            Ok(())
+        }
+
+        /// Makes `calls` in order. The value sent must be the sum of their values. A failing
+        /// call reverts everything unless it allows failure, in which case its outcome says
+        /// so, its value is refunded to the caller and the batch carries on.
+        #[ink(message, payable)]
+        pub fn aggregate(&mut self, calls: Vec<Call>) -> Result<Vec<Outcome>> {
+            self.ensure_owner()?;
+            let value = calls
+                .iter()
+                .try_fold(0, |sum: Balance, call| sum.checked_add(call.value))
+                .ok_or(Error::ValueMismatch)?;
+            if value != self.env().transferred_value() {
+                return Err(Error::ValueMismatch);
+            }
+
+            let mut outcomes = Vec::with_capacity(calls.len());
+            let mut refund: Balance = 0;
+            for (index, call) in (0..).zip(&calls) {
+                let outcome = match self.invoke(call) {
+                    Some(output) => Outcome {
+                        success: true,
+                        output,
+                    },
+                    None if call.allow_failure => {
+                        // The failed call's transfer was rolled back, leaving its value here.
+                        refund += call.value;
+                        Outcome {
+                            success: false,
+                            output: Vec::new(),
+                        }
+                    }
+                    None => return Err(Error::CallFailed(index)),
+                };
+                outcomes.push(outcome);
+            }
+            if refund > 0 {
+                self.env()
+                    .transfer(self.env().caller(), refund)
+                    .map_err(|_| Error::RefundFailed)?;
+            }
+            Ok(outcomes)
+        }
+
+        /// `get_reserves` of each pair, or `None` where the call failed. Open to anyone, and
+        /// meant to be dry-run to read many pairs in one round trip.
+        #[ink(message)]
+        pub fn get_reserves(&self, pairs: Vec<AccountId>) -> Vec<Option<(u128, u128, u128)>> {
+            pairs
+                .into_iter()
+                .map(|pair| {
+                    build_call::<DefaultEnvironment>()
+                        .call(pair)
+                        .gas_limit(0)
+                        .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                            "get_reserves"
+                        ))))
+                        .returns::<(u128, u128, u128)>()
+                        .try_invoke()
+                        .ok()?
+                        .ok()
+                })
+                .collect()
+        }
+
+        /// PSP22 balance of `owner` in each token, or `None` where the call failed.
+        #[ink(message)]
+        pub fn balances_of(
+            &self,
+            owner: AccountId,
+            tokens: Vec<AccountId>,
+        ) -> Vec<Option<Balance>> {
+            tokens
+                .into_iter()
+                .map(|token| {
+                    build_call::<DefaultEnvironment>()
+                        .call(token)
+                        .gas_limit(0)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                                "PSP22::balance_of"
+                            )))
+                            .push_arg(owner),
+                        )
+                        .returns::<Balance>()
+                        .try_invoke()
+                        .ok()?
+                        .ok()
+                })
+                .collect()
+        }
+
+        /// The encoded return value of `call`, or `None` if it trapped, reverted or was
+        /// rejected by ink!.
+        fn invoke(&mut self, call: &Call) -> Option<Vec<u8>> {
+            build_call::<DefaultEnvironment>()
+                .call(call.callee)
+                .gas_limit(0)
+                .transferred_value(call.value)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(call.selector))
+                        .push_arg(RawInput(&call.input)),
+                )
+                .returns::<RawOutput>()
+                .try_invoke()
+                .ok()?
+                .ok()
+                .map(|output| output.0)
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() == self.owner {
+                Ok(())
+            } else {
+                Err(Error::NotOwner)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::test;
+        use scale::{Decode, Encode};
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        fn call(value: Balance) -> Call {
+            Call {
+                callee: accounts().django,
+                selector: ink::selector_bytes!("mint"),
+                input: accounts().alice.encode(),
+                value,
+                allow_failure: false,
+            }
+        }
+
+        #[ink::test]
+        fn only_the_owner_runs_batches() {
+            let mut multicall = Multicall::new();
+            assert_eq!(multicall.owner(), accounts().alice);
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(multicall.aggregate(vec![call(0)]), Err(Error::NotOwner));
+            assert_eq!(
+                multicall.transfer_ownership(accounts().bob),
+                Err(Error::NotOwner)
+            );
+
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            assert_eq!(multicall.transfer_ownership(accounts().bob), Ok(()));
+            assert_eq!(multicall.owner(), accounts().bob);
+            assert_eq!(multicall.aggregate(Vec::new()), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn value_must_match_the_calls() {
+            let mut multicall = Multicall::new();
+
+            test::set_value_transferred::<DefaultEnvironment>(5);
+            assert_eq!(
+                multicall.aggregate(vec![call(2), call(2)]),
+                Err(Error::ValueMismatch)
+            );
+            assert_eq!(
+                multicall.aggregate(vec![call(Balance::MAX), call(1)]),
+                Err(Error::ValueMismatch)
+            );
+
+            test::set_value_transferred::<DefaultEnvironment>(0);
+            assert_eq!(multicall.aggregate(Vec::new()), Ok(Vec::new()));
+        }
+
+        #[test]
+        fn raw_input_and_output_pass_bytes_through() {
+            let args = (accounts().alice, 42u128).encode();
+            let mut input = ink::selector_bytes!("mint").to_vec();
+            RawInput(&args).encode_to(&mut input);
+            assert_eq!(input[4..], args[..]);
+
+            // A message returning `Ok(7u128)` through `Result<_, LangError>`.
+            let returned = Ok::<u128, ink::LangError>(7).encode();
+            let decoded =
+                <core::result::Result<RawOutput, ink::LangError>>::decode(&mut &returned[..])
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(u128::decode(&mut &decoded.0[..]), Ok(7));
+        }
+    }
+}
@@ -1,36 +1,31 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 #[ink::contract]
-mod pair {
-    use ink::env::{
-        call::{build_call, ExecutionInput, Selector},
-        DefaultEnvironment,
-    };
+pub mod pair {
     use ink::prelude::vec::Vec;
-    use scale::CompactAs;
-    use sp_arithmetic::FixedU128;
-
-    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Error {
-        Overflow,
-        TokenMintingFailed,
-    }
+    use ink::storage::Lazy;
+    use uniswap_math::dynamic_fee;
+    use uniswap_math::pair::{self as math, MINIMUM_LIQUIDITY};
 
     #[ink(event)]
     pub struct Sync {
         reserve_0: u128,
         reserve_1: u128,
+        /// The emitting pair, so `Sync` can be filtered by topic across pairs.
+        #[ink(topic)]
+        pair: AccountId,
     }
 
-    pub type Result<T> = core::result::Result<T, Error>;
-
     #[ink(event)]
     pub struct Mint {
         #[ink(topic)]
         owner: Option<AccountId>,
         amount_0: Balance,
         amount_1: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// LP tokens minted to `to`.
+        liquidity: Balance,
     }
 
     #[ink(event)]
@@ -39,20 +34,47 @@ mod pair {
         sender: Option<AccountId>,
         amount_0: Balance,
         amount_1: Balance,
+        #[ink(topic)]
         to: AccountId,
+        /// LP tokens burned, those sent to the pair beforehand.
+        liquidity: Balance,
     }
 
     #[ink(event)]
     pub struct Swap {
+        #[ink(topic)]
         sender: Option<AccountId>,
         amount_0_in: Balance,
         amount_1_in: Balance,
         amount_0_out: Balance,
         amount_1_out: Balance,
+        #[ink(topic)]
         to: AccountId,
+        /// Reserves after the swap.
+        reserve_0: Balance,
+        reserve_1: Balance,
+    }
+
+    /// A pair's dynamic fee: the bounds the factory set and the volatility of the price
+    /// oracle, measured window by window.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DynamicFee {
+        pub min_fee: u32,
+        pub max_fee: u32,
+        /// Smoothed change of the average price from window to window, in millionths.
+        pub volatility: u32,
+        /// Block timestamp the current window opened at, 0 until the first one opens.
+        pub window_start: u128,
+        /// `price_0_cumulative_last` as of `window_start`.
+        pub price_0_cumulative: u128,
+        /// Average price of token 0 over the last closed window, 0 until one closes.
+        pub price_0_average: u128,
     }
 
-    const MINIMUM_LIQUIDITY: u64 = 10_u64.pow(3_u32);
     // Defines the storage of your contract.
     // Add new fields to the below struct in order
     // to add new static storage fields to your contract.
@@ -68,11 +90,15 @@ mod pair {
         reserve_1: u128,
         price_0_cumulative_last: u128,
         price_1_cumulative_last: u128,
+        /// `sqrt(reserve_0 * reserve_1)` as of the last mint or burn while the protocol fee
+        /// is on, the baseline `mint_fee` measures fee growth against.
         k_last: u128,
         psp22token: AccountId,
-        total_supply: Balance,
+        /// Block timestamp, in milliseconds, of the last reserves update.
         block_timestamp_last: u128,
         // unlocked: u32,
+        /// Unset while the pair charges the fixed fee
+        dynamic_fee: Lazy<Option<DynamicFee>>,
     }
 
     impl Pair {
@@ -93,16 +119,46 @@ mod pair {
                 price_0_cumulative_last: 0,
                 price_1_cumulative_last: 0,
                 k_last: 0,
-                total_supply: 0,
                 block_timestamp_last: 0,
                 // unlocked: 1
+                dynamic_fee: Lazy::new(),
             }
         }
 
+        /// Constructor used by `UniswapCore::create_pair`. The caller becomes the factory and
+        /// the tokens are set right after through `initialize`, so the constructor input is
+        /// the same for every pair.
         #[ink(constructor)]
-        pub fn initialize(psp22: AccountId, token_0: AccountId, token_1: AccountId) -> Self {
-            let caller = Self::env().caller();
-            Self::new(caller, psp22, token_0, token_1)
+        pub fn create() -> Self {
+            let zero = AccountId::from([0x0; 32]);
+            Self::new(Self::env().caller(), zero, zero, zero)
+        }
+
+        #[ink(message)]
+        pub fn initialize(
+            &mut self,
+            psp22token: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+        ) {
+            assert!(self.env().caller() == self.factory, "UniswapV2: FORBIDDEN");
+            assert!(
+                self.token_0 == AccountId::from([0x0; 32]),
+                "UniswapV2: ALREADY_INITIALIZED"
+            );
+            self.psp22token = psp22token;
+            self.token_0 = token_0;
+            self.token_1 = token_1;
+        }
+
+        /// Swaps this pair's code for `code_hash`, keeping its storage. Only the factory
+        /// can upgrade a pair, see `UniswapCore::upgrade_pair`.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) {
+            assert!(self.env().caller() == self.factory, "UniswapV2: FORBIDDEN");
+            self.env()
+                .set_code_hash(&code_hash)
+                .expect("UniswapV2: SET_CODE_FAILED");
         }
 
         #[ink(message)]
@@ -113,164 +169,156 @@ mod pair {
             (reserve_0, reserve_1, timestamp)
         }
 
+        /// Returns the PSP22 contract holding this pair's liquidity shares.
         #[ink(message)]
-        pub fn update(
-            &mut self,
-            balance_0: u128,
-            balance_1: u128,
-            reserve_0: u128,
-            reserve_1: u128,
-        ) -> Result<()> {
-            if balance_0 <= u128::MAX && balance_1 <= u128::MAX {
-                let block_timestamp = (self.env().block_timestamp() % 2_u64.pow(32)) as u32;
-                let time_elapsed = block_timestamp - self.block_timestamp_last as u32;
-
-                if time_elapsed > 0 && reserve_0 != 0 && reserve_1 != 0 {
-                    self.price_0_cumulative_last += reserve_0
-                        .checked_div(reserve_1)
-                        .unwrap()
-                        .checked_mul(time_elapsed as u128)
-                        .unwrap();
-                    self.price_1_cumulative_last += reserve_1
-                        .checked_div(reserve_0)
-                        .unwrap()
-                        .checked_mul(time_elapsed as u128)
-                        .unwrap();
-                }
+        pub fn lp_token(&self) -> AccountId {
+            self.psp22token
+        }
 
-                self.reserve_0 = balance_0;
-                self.reserve_1 = balance_1;
-                self.block_timestamp_last = block_timestamp as u128;
+        #[ink(message)]
+        pub fn token_0(&self) -> AccountId {
+            self.token_0
+        }
 
-                self.env().emit_event(Sync {
-                    reserve_0: self.reserve_0,
-                    reserve_1: self.reserve_1,
-                });
+        #[ink(message)]
+        pub fn token_1(&self) -> AccountId {
+            self.token_1
+        }
 
-                Ok(())
-            } else {
-                return Err(Error::Overflow);
+        /// Swap fee in millionths of the amount in, e.g. 3000 for 0.3%. While the dynamic
+        /// fee is on, it follows the volatility measured as of the last closed window.
+        #[ink(message)]
+        pub fn fee(&self) -> u32 {
+            match self.dynamic_fee() {
+                Some(state) => dynamic_fee::fee(state.volatility, state.min_fee, state.max_fee),
+                None => {
+                    (uniswap_math::FEE_NUMERATOR * 1_000_000 / uniswap_math::FEE_DENOMINATOR) as u32
+                }
             }
         }
 
+        /// The dynamic fee's bounds and volatility, or `None` while the fee is fixed.
         #[ink(message)]
-        pub fn mint(&mut self) {
-            let (reserve_0, reserve_1, _) = self.get_reserves();
-            let balance_0 = self.get_token_balance(self.psp22token, self.token_0);
-            let balance_1 = self.get_token_balance(self.psp22token, self.token_1);
-
-            let amount_0 = balance_0 - reserve_0;
-            let amount_1 = balance_1 - reserve_1;
-
-            let fee_on = self.get_fee_to() != AccountId::from([0x0; 32]);
-            let liquidity = if self.get_total_supply() == 0 {
-                let mint_result = build_call::<DefaultEnvironment>()
-                    .call(self.psp22token)
-                    .gas_limit(0)
-                    .exec_input(
-                        ExecutionInput::new(Selector::new(ink::selector_bytes!("mint")))
-                            .push_arg(&self.get_fee_to())
-                            .push_arg(&MINIMUM_LIQUIDITY),
-                    )
-                    .returns::<()>()
-                    .try_invoke();
-
-                match mint_result {
-                    Ok(Ok(_)) => Ok(()),
-                    _ => Err(Error::TokenMintingFailed),
-                };
-                self.get_squareroot(amount_1.checked_mul(amount_1).unwrap()) as u64
-                    - MINIMUM_LIQUIDITY
-            } else {
-                let liquidity_0 =
-                    amount_0.checked_mul(self.get_total_supply()).unwrap() / reserve_0;
-                let liquidity_1 =
-                    amount_1.checked_mul(self.get_total_supply()).unwrap() / reserve_1;
-                if liquidity_0 > liquidity_1 {
-                    liquidity_0 as u64
-                } else {
-                    liquidity_1 as u64
+        pub fn dynamic_fee(&self) -> Option<DynamicFee> {
+            self.dynamic_fee.get().flatten()
+        }
+
+        /// Makes the fee follow the oracle's volatility within `(min_fee, max_fee)`, or
+        /// fixes it again with `None`. Only the factory can, see
+        /// `UniswapCore::set_dynamic_fee`. The volatility measured so far is kept.
+        #[ink(message)]
+        pub fn set_dynamic_fee(&mut self, bounds: Option<(u32, u32)>) {
+            assert!(self.env().caller() == self.factory, "UniswapV2: FORBIDDEN");
+            let state = bounds.map(|(min_fee, max_fee)| {
+                assert!(
+                    dynamic_fee::is_valid_bounds(min_fee, max_fee),
+                    "UniswapV2: INVALID_FEE"
+                );
+                DynamicFee {
+                    min_fee,
+                    max_fee,
+                    ..self.dynamic_fee().unwrap_or_default()
                 }
-            };
+            });
+            self.dynamic_fee.set(&state);
+        }
 
-            assert!(liquidity > 0, "UniswapV2: INSUFFICIENT_LIQUIDITY_MINTED");
-            let mint_result = build_call::<DefaultEnvironment>()
-                .call(self.psp22token)
-                .gas_limit(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("mint")))
-                        .push_arg(&self.get_fee_to())
-                        .push_arg(&liquidity),
-                )
-                .returns::<()>()
-                .try_invoke();
-
-            match mint_result {
-                Ok(Ok(_)) => Ok(()),
-                _ => Err(Error::TokenMintingFailed),
-            };
+        /// Whether this is a `StablePair`; classic pairs trade on `x * y >= k`.
+        #[ink(message)]
+        pub fn stable(&self) -> bool {
+            false
+        }
+
+        /// Share of the pair's value in token 0, in percent; always half, unlike a
+        /// `WeightedPair`.
+        #[ink(message)]
+        pub fn weight_0(&self) -> u32 {
+            uniswap_math::weighted::WEIGHT_DENOMINATOR / 2
+        }
+
+        /// Time-weighted sum of the token 0 price in token 1 (Q64.64, per millisecond).
+        #[ink(message)]
+        pub fn price_0_cumulative_last(&self) -> u128 {
+            self.price_0_cumulative_last
+        }
+
+        /// Time-weighted sum of the token 1 price in token 0 (Q64.64, per millisecond).
+        #[ink(message)]
+        pub fn price_1_cumulative_last(&self) -> u128 {
+            self.price_1_cumulative_last
+        }
+
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId) -> Balance {
+            assert!(!self.is_paused(), "UniswapV2: PAUSED");
+            let (reserve_0, reserve_1, _) = self.get_reserves();
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+            let amount_0 = balance_0.saturating_sub(reserve_0);
+            let amount_1 = balance_1.saturating_sub(reserve_1);
+
+            let fee_on = self.mint_fee(reserve_0, reserve_1);
+            // Read after `mint_fee`, which can mint to `fee_to`.
+            let total_supply = self.get_total_supply();
+            let liquidity =
+                math::liquidity_minted(amount_0, amount_1, reserve_0, reserve_1, total_supply)
+                    .expect("UniswapV2: INSUFFICIENT_LIQUIDITY_MINTED");
+            if total_supply == 0 {
+                self.lp_mint(AccountId::from([0x0; 32]), MINIMUM_LIQUIDITY);
+            }
+            self.lp_mint(to, liquidity);
 
             self.update(balance_0, balance_1, reserve_0, reserve_1);
-            self.k_last = if fee_on {
-                reserve_0.checked_mul(reserve_1).unwrap()
-            } else {
-                self.k_last
-            };
+            if fee_on {
+                self.k_last = math::sqrt_product(self.reserve_0, self.reserve_1);
+            }
             self.env().emit_event(Mint {
                 owner: Some(self.env().caller()),
                 amount_0,
                 amount_1,
+                to,
+                liquidity,
             });
+            liquidity
         }
 
         #[ink(message)]
-        pub fn burn(&mut self, to: AccountId) {
+        pub fn burn(&mut self, to: AccountId) -> (Balance, Balance) {
             let (reserve_0, reserve_1, _) = self.get_reserves();
-            let balance_0 = self.get_token_balance(self.token_0, self.token_0);
-            let balance_1 = self.get_token_balance(self.token_1, self.token_1);
-
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
             let liquidity = self.get_token_balance(self.psp22token, self.env().account_id());
 
-            let fee_to = self.get_fee_to();
-            let amount_0 = liquidity.checked_mul(balance_0).unwrap();
-            let amount_1 = liquidity.checked_mul(balance_1).unwrap();
+            let fee_on = self.mint_fee(reserve_0, reserve_1);
+            let total_supply = self.get_total_supply();
+            let (amount_0, amount_1) =
+                math::amounts_burned(liquidity, balance_0, balance_1, total_supply)
+                    .expect("UniswapV2: INSUFFICIENT_LIQUIDITY_BURNED");
 
-            assert!(
-                amount_0 > 0 && amount_1 > 0,
-                "UNISWAPV2: INSUFFICIENT_LIQUIDITY_BURNED"
-            );
-
-            build_call::<DefaultEnvironment>()
-                .call(self.psp22token)
-                .gas_limit(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("burn")))
-                        .push_arg(&self.env().account_id())
-                        .push_arg(&liquidity),
-                )
-                .returns::<()>()
-                .try_invoke();
-
-            self.transfer_from(self.token_0, self.env().account_id(), to, amount_0);
-            self.transfer_from(self.token_1, self.env().account_id(), to, amount_1);
+            self.lp_burn(self.env().account_id(), liquidity);
+            self.safe_transfer(self.token_0, to, amount_0);
+            self.safe_transfer(self.token_1, to, amount_1);
 
             let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
             let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
 
             self.update(balance_0, balance_1, reserve_0, reserve_1);
-            self.k_last = if fee_to != AccountId::from([0x0; 32]) {
-                reserve_0.checked_mul(reserve_1).unwrap()
-            } else {
-                self.k_last
-            };
+            if fee_on {
+                self.k_last = math::sqrt_product(self.reserve_0, self.reserve_1);
+            }
             self.env().emit_event(Burn {
                 sender: Some(self.env().caller()),
                 amount_0,
                 amount_1,
                 to,
+                liquidity,
             });
+            (amount_0, amount_1)
         }
 
+        /// Sends out the requested amounts, then checks the tokens sent in beforehand
+        /// keep K. `data` is accepted for interface parity with V2 but flash swaps are
+        /// not supported, so it is ignored.
         #[ink(message)]
         pub fn swap(
             &mut self,
@@ -279,66 +327,53 @@ mod pair {
             to: AccountId,
             data: Vec<u8>,
         ) {
+            let _ = data;
+            assert!(!self.is_paused(), "UniswapV2: PAUSED");
             assert!(
                 amount_0_out > 0 || amount_1_out > 0,
-                "UNISWAPV2: INSUFFICIENT_OUTPUT_AMOUNT"
+                "UniswapV2: INSUFFICIENT_OUTPUT_AMOUNT"
             );
             let (reserve_0, reserve_1, _) = self.get_reserves();
             assert!(
                 amount_0_out < reserve_0 && amount_1_out < reserve_1,
-                "UNISWAPV2: INSUFFICIENT_LIQUIDITY"
+                "UniswapV2: INSUFFICIENT_LIQUIDITY"
             );
 
-            let mut balance_0: Balance;
-            let mut balance_1: Balance;
+            let balance_0: Balance;
+            let balance_1: Balance;
             {
                 assert!(
                     to != self.token_0 && to != self.token_1,
                     "UniswapV2: INVALID_TO_ADDRESS"
                 );
                 if amount_0_out > 0 {
-                    self.transfer_from(self.token_0, self.env().account_id(), to, amount_0_out);
+                    self.safe_transfer(self.token_0, to, amount_0_out);
                 }
                 if amount_1_out > 0 {
-                    self.transfer_from(self.token_1, self.env().account_id(), to, amount_1_out);
+                    self.safe_transfer(self.token_1, to, amount_1_out);
                 }
                 balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
                 balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
             }
 
-            let amount_0_in: u128 = if balance_0 > (reserve_0 - amount_0_out) {
-                balance_0 - (reserve_0 - amount_0_out)
-            } else {
-                0
-            };
-            let amount_1_in: u128 = if balance_1 > (reserve_1 - amount_1_out) {
-                balance_1 - (reserve_1 - amount_1_out)
-            } else {
-                0
-            };
+            let amount_0_in = balance_0.saturating_sub(reserve_0 - amount_0_out);
+            let amount_1_in = balance_1.saturating_sub(reserve_1 - amount_1_out);
             assert!(
                 amount_0_in > 0 || amount_1_in > 0,
-                "Uniswap: INSUFFICIENT_INPUT_AMOUNT"
+                "UniswapV2: INSUFFICIENT_INPUT_AMOUNT"
+            );
+            assert!(
+                dynamic_fee::k_holds(
+                    balance_0,
+                    balance_1,
+                    amount_0_in,
+                    amount_1_in,
+                    reserve_0,
+                    reserve_1,
+                    self.fee()
+                ),
+                "UniswapV2: K"
             );
-            {
-                let balance_0_adjusted = (balance_0 as u128)
-                    .checked_mul(1000)
-                    .unwrap()
-                    .checked_sub((amount_0_in.checked_mul(3)).unwrap())
-                    .unwrap();
-                let balance_1_adjusted =
-                    balance_1.checked_mul(1000).unwrap() - (amount_1_in.checked_mul(3).unwrap());
-                // require(balance0Adjusted.mul(balance1Adjusted) >= uint(_reserve0).mul(_reserve1).mul(1000**2), 'UniswapV2: K');
-                assert!(
-                    balance_0_adjusted.checked_mul(balance_1_adjusted).unwrap()
-                        >= reserve_0
-                            .checked_mul(reserve_1)
-                            .unwrap()
-                            .checked_mul(1000_u128.checked_pow(2).unwrap())
-                            .unwrap(),
-                    "UniswapV2: K"
-                )
-            }
 
             self.update(balance_0, balance_1, reserve_0, reserve_1);
             self.env().emit_event(Swap {
@@ -348,20 +383,30 @@ mod pair {
                 amount_0_out,
                 amount_1_out,
                 to,
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
             });
         }
 
+        /// Sends whatever the pair holds above its reserves to `to`.
         #[ink(message)]
         pub fn skim(&mut self, to: AccountId) {
-            let balance_0_of =
-                self.get_token_balance(self.token_0, self.env().account_id()) - self.reserve_0;
-            let balance_1_of =
-                self.get_token_balance(self.token_1, self.env().account_id()) - self.reserve_1;
+            let excess_0 = self
+                .get_token_balance(self.token_0, self.env().account_id())
+                .saturating_sub(self.reserve_0);
+            let excess_1 = self
+                .get_token_balance(self.token_1, self.env().account_id())
+                .saturating_sub(self.reserve_1);
 
-            self.transfer_from(self.token_0, self.env().account_id(), to, balance_0_of);
-            self.transfer_from(self.token_1, self.env().account_id(), to, balance_1_of);
+            if excess_0 > 0 {
+                self.safe_transfer(self.token_0, to, excess_0);
+            }
+            if excess_1 > 0 {
+                self.safe_transfer(self.token_1, to, excess_1);
+            }
         }
 
+        /// Sets the reserves to the pair's balances.
         #[ink(message)]
         pub fn sync(&mut self) {
             let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
@@ -371,145 +416,1099 @@ mod pair {
 
         #[ink(message)]
         pub fn get_token_balance(&self, token: AccountId, owner: AccountId) -> Balance {
-            let result = build_call::<DefaultEnvironment>()
-                .call(token)
-                .gas_limit(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("mint")))
-                        .push_arg(&owner),
-                )
-                .returns::<Balance>()
-                .try_invoke();
-
-            match result {
-                Ok(Ok(value)) => value,
-                _ => 0,
-            }
+            ext::balance_of(token, owner).unwrap_or(0)
         }
 
         #[ink(message)]
         pub fn get_total_supply(&self) -> Balance {
-            let result = build_call::<DefaultEnvironment>()
-                .call(self.psp22token)
-                .gas_limit(0)
-                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                    "total_supply"
-                ))))
-                .returns::<Balance>()
-                .try_invoke();
-
-            match result {
-                Ok(Ok(value)) => value,
-                _ => 0,
-            }
+            ext::total_supply(self.psp22token).unwrap_or(0)
         }
 
         #[ink(message)]
-        pub fn transfer_from(
-            &self,
-            token: AccountId,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
-        ) {
-            build_call::<DefaultEnvironment>()
-                .call(token)
-                .gas_limit(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("transfer_from")))
-                        .push_arg(&from)
-                        .push_arg(&to)
-                        .push_arg(&value),
-                )
-                .returns::<()>()
-                .try_invoke();
+        pub fn get_fee_to(&self) -> AccountId {
+            ext::fee_to(self.factory).unwrap_or(AccountId::from([0x0; 32]))
         }
 
+        /// Asks the factory whether its guardian has halted trading on this pair.
         #[ink(message)]
-        pub fn get_fee_to(&self) -> AccountId {
-            let fee_to_result = build_call::<DefaultEnvironment>()
-                .call(self.factory)
-                .gas_limit(0)
-                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                    "get_fee_to"
-                ))))
-                .returns::<AccountId>()
-                .try_invoke();
-
-            let fee_to = match fee_to_result {
-                Ok(Ok(account)) => account,
-                _ => AccountId::from([0x0; 32]),
-            };
-
-            fee_to
+        pub fn is_paused(&self) -> bool {
+            ext::is_paused(self.factory, self.env().account_id())
         }
 
-        pub fn mint_fee(&mut self, reserve_0: u128, reserve_1: u128) -> Result<()> {
-            let fee_to_result = build_call::<DefaultEnvironment>()
-                .call(self.factory)
-                .gas_limit(0)
-                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                    "get_fee_to"
-                ))))
-                .returns::<AccountId>()
-                .try_invoke();
+        /// Stores the new reserves and, on the first call in a block, accumulates the
+        /// prices they replace into the Q64.64 oracle. The accumulators are meant to be
+        /// differenced, so they wrap like V2's.
+        fn update(
+            &mut self,
+            balance_0: Balance,
+            balance_1: Balance,
+            reserve_0: Balance,
+            reserve_1: Balance,
+        ) {
+            let block_timestamp = self.env().block_timestamp() as u128;
+            let time_elapsed = block_timestamp.saturating_sub(self.block_timestamp_last);
+
+            if time_elapsed > 0 && reserve_0 != 0 && reserve_1 != 0 {
+                self.price_0_cumulative_last = self.price_0_cumulative_last.wrapping_add(
+                    math::encode_price(reserve_0, reserve_1).wrapping_mul(time_elapsed),
+                );
+                self.price_1_cumulative_last = self.price_1_cumulative_last.wrapping_add(
+                    math::encode_price(reserve_1, reserve_0).wrapping_mul(time_elapsed),
+                );
+            }
+
+            self.reserve_0 = balance_0;
+            self.reserve_1 = balance_1;
+            self.block_timestamp_last = block_timestamp;
+            self.observe(block_timestamp);
 
-            let fee_to = match fee_to_result {
-                Ok(Ok(account)) => account,
-                _ => AccountId::from([0x0; 32]),
+            self.env().emit_event(Sync {
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
+                pair: self.env().account_id(),
+            });
+        }
+
+        /// Closes the dynamic fee's window once it lasted `dynamic_fee::WINDOW`, folding the
+        /// change of its average price into the volatility, and opens the next one.
+        fn observe(&mut self, block_timestamp: u128) {
+            let Some(mut state) = self.dynamic_fee() else {
+                return;
             };
+            let elapsed = block_timestamp.saturating_sub(state.window_start);
+            if state.window_start != 0 {
+                if elapsed < dynamic_fee::WINDOW {
+                    return;
+                }
+                let average = self
+                    .price_0_cumulative_last
+                    .wrapping_sub(state.price_0_cumulative)
+                    / elapsed;
+                let change = dynamic_fee::price_change(state.price_0_average, average);
+                state.volatility = dynamic_fee::next_volatility(
+                    state.volatility,
+                    change,
+                    elapsed / dynamic_fee::WINDOW,
+                );
+                state.price_0_average = average;
+            }
+            state.window_start = block_timestamp;
+            state.price_0_cumulative = self.price_0_cumulative_last;
+            self.dynamic_fee.set(&Some(state));
+        }
 
-            if fee_to != AccountId::from([0x0; 32]) {
+        /// Mints the protocol's share of the fees earned since the last liquidity event to
+        /// `fee_to`, if the factory has one. Returns whether the protocol fee is on.
+        fn mint_fee(&mut self, reserve_0: Balance, reserve_1: Balance) -> bool {
+            let fee_to = self.get_fee_to();
+            let fee_on = fee_to != AccountId::from([0x0; 32]);
+            if fee_on {
                 if self.k_last != 0 {
-                    let root_k = self
-                        .get_squareroot(reserve_0)
-                        .checked_mul(reserve_1)
-                        .unwrap();
-                    let root_k_last = self.get_squareroot(reserve_1);
-
-                    if root_k > root_k_last {
-                        let numerator =
-                            self.total_supply.checked_mul(root_k - root_k_last).unwrap();
-                        let denominator = root_k
-                            .checked_mul(5)
-                            .unwrap()
-                            .checked_add(root_k_last)
-                            .unwrap();
-                        let liquidity = numerator / denominator;
-
-                        if liquidity > 0 {
-                            let mint_result = build_call::<DefaultEnvironment>()
-                                .call(self.psp22token)
-                                .gas_limit(0)
-                                .exec_input(
-                                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                                        "mint"
-                                    )))
-                                    .push_arg(&fee_to)
-                                    .push_arg(&liquidity),
-                                )
-                                .returns::<()>()
-                                .try_invoke();
-
-                            match mint_result {
-                                Ok(Ok(_)) => Ok(()),
-                                _ => Err(Error::TokenMintingFailed),
-                            };
-                        }
+                    let root_k = math::sqrt_product(reserve_0, reserve_1);
+                    let liquidity =
+                        math::protocol_fee_liquidity(self.get_total_supply(), root_k, self.k_last)
+                            .expect("UniswapV2: OVERFLOW");
+                    if liquidity > 0 {
+                        self.lp_mint(fee_to, liquidity);
                     }
                 }
-            } else {
+            } else if self.k_last != 0 {
                 self.k_last = 0;
             }
+            fee_on
+        }
+
+        /// Sends `value` of `token` held by the pair to `to`.
+        fn safe_transfer(&self, token: AccountId, to: AccountId, value: Balance) {
+            assert!(
+                ext::transfer(token, to, value),
+                "UniswapV2: TRANSFER_FAILED"
+            );
+        }
+
+        fn lp_mint(&self, to: AccountId, value: Balance) {
+            assert!(
+                ext::mint(self.psp22token, to, value),
+                "UniswapV2: LP_MINT_FAILED"
+            );
+        }
+
+        fn lp_burn(&self, from: AccountId, value: Balance) {
+            assert!(
+                ext::burn(self.psp22token, from, value),
+                "UniswapV2: LP_BURN_FAILED"
+            );
+        }
+    }
+
+    #[cfg(not(test))]
+    use uniswap_support::ext;
+    #[cfg(test)]
+    use uniswap_support::mock as ext;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use scale::Decode;
+        use uniswap_support::{layout, mock};
+
+        /// Packed fields of the deployed storage, in order.
+        const PACKED_FIELDS: &[&str] = &[
+            "factory",
+            "token_0",
+            "token_1",
+            "reserve_0",
+            "reserve_1",
+            "price_0_cumulative_last",
+            "price_1_cumulative_last",
+            "k_last",
+            "psp22token",
+            "block_timestamp_last",
+        ];
+
+        /// Fields stored in their own cell, with the key it lives under.
+        const ROOT_FIELDS: &[(&str, u32)] = &[("dynamic_fee", 1436162949)];
+
+        #[test]
+        fn storage_layout_is_upgrade_compatible() {
+            layout::assert_upgrade_compatible::<Pair>(PACKED_FIELDS, ROOT_FIELDS);
+        }
+
+        const FACTORY: [u8; 32] = [0x10; 32];
+        const PAIR: [u8; 32] = [0x11; 32];
+        const LP_TOKEN: [u8; 32] = [0x12; 32];
+        const TOKEN_0: [u8; 32] = [0x13; 32];
+        const TOKEN_1: [u8; 32] = [0x14; 32];
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        /// A pair of `TOKEN_0`/`TOKEN_1` called by Alice, who holds plenty of both.
+        fn setup() -> Pair {
+            let alice = accounts().alice;
+            mock::mint(TOKEN_0.into(), alice, 1_000_000_000);
+            mock::mint(TOKEN_1.into(), alice, 1_000_000_000);
+            test::set_callee::<DefaultEnvironment>(PAIR.into());
+            test::set_caller::<DefaultEnvironment>(alice);
+            Pair::new(
+                FACTORY.into(),
+                LP_TOKEN.into(),
+                TOKEN_0.into(),
+                TOKEN_1.into(),
+            )
+        }
+
+        fn add_liquidity(pair: &mut Pair, amount_0: Balance, amount_1: Balance) -> Balance {
+            mock::pay(TOKEN_0, PAIR, amount_0);
+            mock::pay(TOKEN_1, PAIR, amount_1);
+            pair.mint(accounts().alice)
+        }
+
+        #[ink::test]
+        fn first_mint_locks_minimum_liquidity() {
+            let mut pair = setup();
+
+            let liquidity = add_liquidity(&mut pair, 1_000_000, 4_000_000);
+
+            assert_eq!(liquidity, 2_000_000 - MINIMUM_LIQUIDITY);
+            assert_eq!(mock::balance(LP_TOKEN, accounts().alice), liquidity);
+            assert_eq!(
+                mock::balance(LP_TOKEN, AccountId::from([0x0; 32])),
+                MINIMUM_LIQUIDITY
+            );
+            assert_eq!(pair.get_total_supply(), 2_000_000);
+            assert_eq!(pair.get_reserves().0, 1_000_000);
+            assert_eq!(pair.get_reserves().1, 4_000_000);
+        }
+
+        #[ink::test]
+        fn describes_its_tokens_and_fee() {
+            let pair = setup();
+
+            assert_eq!(pair.token_0(), AccountId::from(TOKEN_0));
+            assert_eq!(pair.token_1(), AccountId::from(TOKEN_1));
+            assert_eq!(pair.lp_token(), AccountId::from(LP_TOKEN));
+            assert_eq!(pair.fee(), 3_000);
+            assert!(!pair.stable());
+            assert_eq!(pair.weight_0(), 50);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INSUFFICIENT_LIQUIDITY_MINTED")]
+        fn first_mint_below_minimum_liquidity_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000, 1_000);
+        }
+
+        #[ink::test]
+        fn subsequent_mint_is_proportional_to_the_smaller_share() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+
+            // Token 1 is over-supplied; only the token 0 share counts.
+            let liquidity = add_liquidity(&mut pair, 500_000, 3_000_000);
+
+            assert_eq!(liquidity, 1_000_000);
+            assert_eq!(pair.get_total_supply(), 3_000_000);
+            assert_eq!(pair.get_reserves().0, 1_500_000);
+            assert_eq!(pair.get_reserves().1, 7_000_000);
+        }
+
+        #[ink::test]
+        fn burn_returns_a_proportional_share() {
+            let mut pair = setup();
+            let liquidity = add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let bob = accounts().bob;
+
+            mock::pay(LP_TOKEN, PAIR, liquidity);
+            let (amount_0, amount_1) = pair.burn(bob);
+
+            assert_eq!((amount_0, amount_1), (999_500, 3_998_000));
+            assert_eq!(mock::balance(TOKEN_0, bob), amount_0);
+            assert_eq!(mock::balance(TOKEN_1, bob), amount_1);
+            assert_eq!(mock::balance(LP_TOKEN, PAIR.into()), 0);
+            assert_eq!(pair.get_total_supply(), MINIMUM_LIQUIDITY);
+            assert_eq!(pair.get_reserves().0, 500);
+            assert_eq!(pair.get_reserves().1, 2_000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INSUFFICIENT_LIQUIDITY_BURNED")]
+        fn burn_without_liquidity_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            pair.burn(accounts().bob);
+        }
+
+        #[ink::test]
+        fn swap_token_0_for_token_1() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let bob = accounts().bob;
+            let amount_out = uniswap_math::get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
 
-            Ok(())
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out, bob, Vec::new());
+
+            assert_eq!(mock::balance(TOKEN_1, bob), amount_out);
+            assert_eq!(pair.get_reserves().0, 1_010_000);
+            assert_eq!(pair.get_reserves().1, 4_000_000 - amount_out);
+        }
+
+        #[ink::test]
+        fn events_carry_topics_liquidity_and_reserves() {
+            let mut pair = setup();
+            let alice = accounts().alice;
+            let bob = accounts().bob;
+            // The signature topic, then the `#[ink(topic)]` fields.
+            let last_event = |topics: usize| {
+                let event = test::recorded_events().last().unwrap();
+                assert_eq!(event.topics.len(), topics);
+                event.data
+            };
+
+            let liquidity = add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let mint = Mint::decode(&mut &last_event(3)[1..]).unwrap();
+            assert_eq!((mint.owner, mint.to), (Some(alice), alice));
+            assert_eq!(mint.liquidity, liquidity);
+
+            let amount_out = uniswap_math::get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out, bob, Vec::new());
+            let swap = Swap::decode(&mut &last_event(3)[1..]).unwrap();
+            assert_eq!((swap.sender, swap.to), (Some(alice), bob));
+            assert_eq!(
+                (swap.reserve_0, swap.reserve_1),
+                (1_010_000, 4_000_000 - amount_out)
+            );
+
+            pair.sync();
+            let sync = Sync::decode(&mut &last_event(2)[1..]).unwrap();
+            assert_eq!(sync.pair, PAIR.into());
+
+            mock::pay(LP_TOKEN, PAIR, liquidity);
+            pair.burn(bob);
+            let burn = Burn::decode(&mut &last_event(3)[1..]).unwrap();
+            assert_eq!((burn.sender, burn.to), (Some(alice), bob));
+            assert_eq!(burn.liquidity, liquidity);
+        }
+
+        #[ink::test]
+        fn swap_token_1_for_token_0() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let bob = accounts().bob;
+            let amount_out = uniswap_math::get_amount_out(40_000, 4_000_000, 1_000_000).unwrap();
+
+            mock::pay(TOKEN_1, PAIR, 40_000);
+            pair.swap(amount_out, 0, bob, Vec::new());
+
+            assert_eq!(mock::balance(TOKEN_0, bob), amount_out);
+            assert_eq!(pair.get_reserves().0, 1_000_000 - amount_out);
+            assert_eq!(pair.get_reserves().1, 4_040_000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: K")]
+        fn swap_taking_more_than_k_allows_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let amount_out = uniswap_math::get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out + 1, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: K")]
+        fn swap_ignoring_the_fee_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            // The fee-less constant product output for 10_000 in.
+            let amount_out = 4_000_000 - 4_000_000_000_000 / 1_010_000;
+
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INSUFFICIENT_INPUT_AMOUNT")]
+        fn swap_without_input_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            pair.swap(0, 1, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INSUFFICIENT_LIQUIDITY")]
+        fn swap_draining_a_reserve_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, 4_000_000, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        fn skim_sends_out_the_excess() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let bob = accounts().bob;
+
+            mock::pay(TOKEN_0, PAIR, 123);
+            mock::pay(TOKEN_1, PAIR, 456);
+            pair.skim(bob);
+
+            assert_eq!(mock::balance(TOKEN_0, bob), 123);
+            assert_eq!(mock::balance(TOKEN_1, bob), 456);
+            assert_eq!(mock::balance(TOKEN_0, PAIR.into()), pair.get_reserves().0);
+            assert_eq!(mock::balance(TOKEN_1, PAIR.into()), pair.get_reserves().1);
+        }
+
+        #[ink::test]
+        fn sync_matches_reserves_to_balances() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let events = test::recorded_events().count();
+
+            mock::pay(TOKEN_0, PAIR, 123);
+            pair.sync();
+
+            assert_eq!(pair.get_reserves().0, 1_000_123);
+            assert_eq!(pair.get_reserves().1, 4_000_000);
+            assert_eq!(test::recorded_events().count(), events + 1);
+        }
+
+        #[ink::test]
+        fn sync_accumulates_prices() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            let (_, _, last) = pair.get_reserves();
+
+            test::set_block_timestamp::<DefaultEnvironment>(last as u64 + 10);
+            pair.sync();
+
+            assert_eq!(pair.price_0_cumulative_last(), 10 * (4 << 64));
+            assert_eq!(pair.price_1_cumulative_last(), 10 * (1 << 62));
+            assert_eq!(pair.get_reserves().2, last + 10);
+        }
+
+        #[ink::test]
+        fn protocol_fee_is_minted_to_fee_to() {
+            let fee_to = accounts().charlie;
+            mock::set_fee_to(Some(fee_to));
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+            assert_eq!(pair.k_last, 2_000_000);
+
+            let amount_out = uniswap_math::get_amount_out(100_000, 1_000_000, 4_000_000).unwrap();
+            mock::pay(TOKEN_0, PAIR, 100_000);
+            pair.swap(0, amount_out, accounts().bob, Vec::new());
+            assert_eq!(mock::balance(LP_TOKEN, fee_to), 0);
+
+            add_liquidity(&mut pair, 1_000, 4_000);
+
+            assert!(mock::balance(LP_TOKEN, fee_to) > 0);
+            let (reserve_0, reserve_1, _) = pair.get_reserves();
+            assert_eq!(pair.k_last, math::sqrt_product(reserve_0, reserve_1));
+        }
+
+        #[ink::test]
+        fn k_last_is_cleared_once_the_protocol_fee_is_off() {
+            mock::set_fee_to(Some(accounts().charlie));
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+
+            mock::set_fee_to(None);
+            add_liquidity(&mut pair, 1_000, 4_000);
+
+            assert_eq!(pair.k_last, 0);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: PAUSED")]
+        fn swap_fails_while_paused() {
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 4_000_000);
+
+            mock::set_paused(true);
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, 1, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        fn burn_works_while_paused() {
+            let mut pair = setup();
+            let liquidity = add_liquidity(&mut pair, 1_000_000, 4_000_000);
+
+            mock::set_paused(true);
+            mock::pay(LP_TOKEN, PAIR, liquidity);
+
+            assert_eq!(pair.burn(accounts().bob), (999_500, 3_998_000));
+        }
+
+        /// Moves the block time `by` milliseconds and syncs, so the oracle sees the reserves.
+        fn sync_after(pair: &mut Pair, by: u128) {
+            let (_, _, last) = pair.get_reserves();
+            test::set_block_timestamp::<DefaultEnvironment>((last + by) as u64);
+            pair.sync();
+        }
+
+        /// A pair with 1_000_000 of each token whose dynamic fee, within `min_fee` and
+        /// `max_fee`, has an open window.
+        fn setup_dynamic_fee(min_fee: u32, max_fee: u32) -> Pair {
+            test::set_block_timestamp::<DefaultEnvironment>(1_000);
+            let mut pair = setup();
+            add_liquidity(&mut pair, 1_000_000, 1_000_000);
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            pair.set_dynamic_fee(Some((min_fee, max_fee)));
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            sync_after(&mut pair, 1);
+            pair
+        }
+
+        #[ink::test]
+        fn dynamic_fee_rises_with_volatility_and_decays() {
+            let mut pair = setup_dynamic_fee(1_000, 50_000);
+            assert_eq!(pair.fee(), 1_000);
+
+            // A steady window sets the baseline average, then the price drops 1/11.
+            sync_after(&mut pair, dynamic_fee::WINDOW);
+            assert_eq!(pair.fee(), 1_000);
+            mock::pay(TOKEN_0, PAIR, 100_000);
+            sync_after(&mut pair, 1);
+            sync_after(&mut pair, dynamic_fee::WINDOW);
+
+            let state = pair.dynamic_fee().unwrap();
+            assert!((45_000..45_500).contains(&state.volatility));
+            assert_eq!(pair.fee(), 1_000 + state.volatility / 2);
+
+            // Quiet windows halve the volatility each.
+            sync_after(&mut pair, 10 * dynamic_fee::WINDOW);
+            assert!(pair.fee() < 1_030);
+        }
+
+        #[ink::test]
+        fn swaps_pay_the_dynamic_fee() {
+            let mut pair = setup_dynamic_fee(20_000, 20_000);
+            let amount_out =
+                dynamic_fee::get_amount_out(10_000, 1_000_000, 1_000_000, 20_000).unwrap();
+
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out, accounts().bob, Vec::new());
+
+            assert_eq!(mock::balance(TOKEN_1, accounts().bob), amount_out);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: K")]
+        fn swaps_paying_the_fixed_fee_fail_above_it() {
+            let mut pair = setup_dynamic_fee(20_000, 20_000);
+            let amount_out = uniswap_math::get_amount_out(10_000, 1_000_000, 1_000_000).unwrap();
+
+            mock::pay(TOKEN_0, PAIR, 10_000);
+            pair.swap(0, amount_out, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        fn dynamic_fee_can_be_turned_off() {
+            let mut pair = setup_dynamic_fee(10_000, 10_000);
+            assert_eq!(pair.fee(), 10_000);
+
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            pair.set_dynamic_fee(None);
+
+            assert_eq!(pair.dynamic_fee(), None);
+            assert_eq!(pair.fee(), 3_000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: FORBIDDEN")]
+        fn dynamic_fee_is_set_by_the_factory() {
+            let mut pair = setup();
+            pair.set_dynamic_fee(Some((1_000, 10_000)));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INVALID_FEE")]
+        fn dynamic_fee_bounds_must_be_ordered() {
+            let mut pair = setup();
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            pair.set_dynamic_fee(Some((10_000, 1_000)));
+        }
+
+        #[ink::test]
+        fn initialize_is_factory_only_and_once() {
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            let mut pair = Pair::create();
+            assert_eq!(pair.factory, AccountId::from(FACTORY));
+
+            pair.initialize(LP_TOKEN.into(), TOKEN_0.into(), TOKEN_1.into());
+            assert_eq!(pair.lp_token(), AccountId::from(LP_TOKEN));
+
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                pair.initialize(LP_TOKEN.into(), TOKEN_1.into(), TOKEN_0.into())
+            }));
+            assert!(result.is_err());
         }
 
-        pub fn get_squareroot(&self, num: u128) -> u128 {
-            let d1 = FixedU128::from_u32(num as u32);
-            let d2 = FixedU128::sqrt(d1);
-            let d3 = *d2.encode_as();
-            d3
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: ALREADY_INITIALIZED")]
+        fn initialize_twice_fails() {
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            let mut pair = Pair::create();
+            pair.initialize(LP_TOKEN.into(), TOKEN_0.into(), TOKEN_1.into());
+            pair.initialize(LP_TOKEN.into(), TOKEN_1.into(), TOKEN_0.into());
+        }
+
+        /// Random sequences of pair operations, checking the pool stays sound after each and
+        /// agrees with `amm_sim::Pool` run alongside. Operations the simulator rejects are
+        /// skipped, since the mock cannot roll back a reverted message.
+        mod invariants {
+            use super::*;
+            use amm_sim::Pool;
+            use ink::storage::traits::Storable;
+            use proptest::prelude::*;
+
+            const FUNDS: Balance = 1_000_000_000_000_000;
+
+            #[derive(Debug, Clone)]
+            enum Op {
+                Mint {
+                    user: usize,
+                    amount_0: Balance,
+                    amount_1: Balance,
+                },
+                /// Burns `percent` of the user's LP tokens.
+                Burn {
+                    user: usize,
+                    percent: u8,
+                },
+                /// Mints and immediately burns the minted shares.
+                RoundTrip {
+                    user: usize,
+                    amount_0: Balance,
+                    amount_1: Balance,
+                },
+                Swap {
+                    user: usize,
+                    zero_for_one: bool,
+                    amount_in: Balance,
+                },
+                Donate {
+                    token_0: bool,
+                    amount: Balance,
+                },
+                Skim,
+                Sync,
+                Wait {
+                    millis: u64,
+                },
+                /// Turns the protocol fee on or off.
+                FeeSwitch {
+                    on: bool,
+                },
+                /// Bounds the swap fee to follow volatility, or fixes it with `None`. Bounds
+                /// out of order are rejected.
+                DynamicFee {
+                    bounds: Option<(u32, u32)>,
+                },
+                /// Asks one unit more than the quote for `amount_in`, breaking K.
+                OverQuote {
+                    user: usize,
+                    zero_for_one: bool,
+                    amount_in: Balance,
+                },
+                /// Burns one LP token more than the user holds.
+                OverBurn {
+                    user: usize,
+                },
+                /// Sends a swap's output to one of the pair's own tokens.
+                ToToken {
+                    user: usize,
+                    zero_for_one: bool,
+                    amount_in: Balance,
+                },
+            }
+
+            fn op() -> impl Strategy<Value = Op> {
+                let user = 0..3usize;
+                let amount = 1..1_000_000_000 as Balance;
+                prop_oneof![
+                    (user.clone(), amount.clone(), amount.clone()).prop_map(
+                        |(user, amount_0, amount_1)| Op::Mint {
+                            user,
+                            amount_0,
+                            amount_1
+                        }
+                    ),
+                    (user.clone(), 1..=100u8)
+                        .prop_map(|(user, percent)| Op::Burn { user, percent }),
+                    (user.clone(), amount.clone(), amount.clone()).prop_map(
+                        |(user, amount_0, amount_1)| Op::RoundTrip {
+                            user,
+                            amount_0,
+                            amount_1
+                        }
+                    ),
+                    (user.clone(), any::<bool>(), amount.clone()).prop_map(
+                        |(user, zero_for_one, amount_in)| Op::Swap {
+                            user,
+                            zero_for_one,
+                            amount_in
+                        }
+                    ),
+                    (user.clone(), any::<bool>(), amount.clone()).prop_map(
+                        |(user, zero_for_one, amount_in)| Op::OverQuote {
+                            user,
+                            zero_for_one,
+                            amount_in
+                        }
+                    ),
+                    user.clone().prop_map(|user| Op::OverBurn { user }),
+                    (user, any::<bool>(), amount.clone()).prop_map(
+                        |(user, zero_for_one, amount_in)| Op::ToToken {
+                            user,
+                            zero_for_one,
+                            amount_in
+                        }
+                    ),
+                    (any::<bool>(), amount)
+                        .prop_map(|(token_0, amount)| Op::Donate { token_0, amount }),
+                    Just(Op::Skim),
+                    Just(Op::Sync),
+                    (1..dynamic_fee::WINDOW as u64).prop_map(|millis| Op::Wait { millis }),
+                    any::<bool>().prop_map(|on| Op::FeeSwitch { on }),
+                    prop::option::of((0..=dynamic_fee::MAX_FEE, 0..=dynamic_fee::MAX_FEE))
+                        .prop_map(|bounds| Op::DynamicFee { bounds }),
+                ]
+            }
+
+            struct World {
+                pair: Pair,
+                sim: Pool,
+                users: [AccountId; 3],
+                fee_to: AccountId,
+            }
+
+            fn now() -> u64 {
+                ink::env::block_timestamp::<DefaultEnvironment>()
+            }
+
+            impl World {
+                fn new(amount_0: Balance, amount_1: Balance) -> Self {
+                    mock::reset();
+                    let accounts = accounts();
+                    let users = [accounts.alice, accounts.bob, accounts.charlie];
+                    for user in users {
+                        mock::mint(TOKEN_0.into(), user, FUNDS);
+                        mock::mint(TOKEN_1.into(), user, FUNDS);
+                    }
+                    test::set_callee::<DefaultEnvironment>(PAIR.into());
+                    let pair = Pair::new(
+                        FACTORY.into(),
+                        LP_TOKEN.into(),
+                        TOKEN_0.into(),
+                        TOKEN_1.into(),
+                    );
+                    let mut world = Self {
+                        pair,
+                        sim: Pool::new(TOKEN_0.into(), TOKEN_1.into()),
+                        users,
+                        fee_to: accounts.django,
+                    };
+                    world.mint(0, amount_0, amount_1);
+                    world
+                }
+
+                fn balances(&self) -> (Balance, Balance) {
+                    (
+                        mock::balance(TOKEN_0, PAIR.into()),
+                        mock::balance(TOKEN_1, PAIR.into()),
+                    )
+                }
+
+                fn reserves(&self) -> (Balance, Balance) {
+                    let (reserve_0, reserve_1, _) = self.pair.get_reserves();
+                    (reserve_0, reserve_1)
+                }
+
+                fn pay(&self, user: usize, token: [u8; 32], amount: Balance) {
+                    assert!(mock::send(
+                        token.into(),
+                        self.users[user],
+                        PAIR.into(),
+                        amount
+                    ));
+                }
+
+                /// Returns the minted shares, or `None` if the pair would reject the deposit.
+                fn mint(
+                    &mut self,
+                    user: usize,
+                    amount_0: Balance,
+                    amount_1: Balance,
+                ) -> Option<Balance> {
+                    let mut sim = self.sim.clone();
+                    sim.deposit(amount_0, amount_1);
+                    let expected = sim.mint(self.users[user], now()).ok()?;
+
+                    self.pay(user, TOKEN_0, amount_0);
+                    self.pay(user, TOKEN_1, amount_1);
+                    let liquidity = self.pair.mint(self.users[user]);
+                    self.sim = sim;
+
+                    assert_eq!(liquidity, expected);
+                    assert_eq!(self.reserves(), self.balances());
+                    Some(liquidity)
+                }
+
+                /// Returns the amounts paid out, or `None` if the pair would reject the burn.
+                fn burn(&mut self, user: usize, liquidity: Balance) -> Option<(Balance, Balance)> {
+                    let (balance_0, balance_1) = self.balances();
+                    let total_supply = self.pair.get_total_supply();
+                    let expected = self.sim.burn(self.users[user], liquidity, now()).ok()?;
+
+                    assert!(mock::send(
+                        LP_TOKEN.into(),
+                        self.users[user],
+                        PAIR.into(),
+                        liquidity
+                    ));
+                    let (amount_0, amount_1) = self.pair.burn(self.users[user]);
+                    assert_eq!((amount_0, amount_1), expected);
+
+                    // Never more than the burnt share of what the pair held.
+                    assert!(amount_0 * total_supply <= liquidity * balance_0);
+                    assert!(amount_1 * total_supply <= liquidity * balance_1);
+                    assert_eq!(self.reserves(), self.balances());
+                    Some((amount_0, amount_1))
+                }
+
+                /// Everything a rejected call could have touched.
+                fn state(&self) -> [Balance; 10] {
+                    let (reserve_0, reserve_1, block_timestamp_last) = self.pair.get_reserves();
+                    let (balance_0, balance_1) = self.balances();
+                    [
+                        reserve_0,
+                        reserve_1,
+                        block_timestamp_last,
+                        balance_0,
+                        balance_1,
+                        self.pair.get_total_supply(),
+                        self.pair.k_last,
+                        self.pair.price_0_cumulative_last(),
+                        self.pair.price_1_cumulative_last(),
+                        mock::balance(LP_TOKEN, self.fee_to),
+                    ]
+                }
+
+                /// Runs `attack` as one transaction that must panic with `reason`, then rolls
+                /// back the ledger and the pair's packed fields like a reverted call would.
+                /// Anything else it changed, such as cells under their own key or events,
+                /// would outlive the revert here and fails the test.
+                fn rejects(&mut self, reason: &str, attack: impl FnOnce(&mut Self)) {
+                    let before = self.state();
+                    let events = test::recorded_events().count();
+                    let mut storage = Vec::new();
+                    Storable::encode(&self.pair, &mut storage);
+
+                    let Err(panic) = mock::revert_on_panic(|| attack(self)) else {
+                        panic!("the pair accepted an attack it should reject with {reason}");
+                    };
+                    let message = panic
+                        .downcast_ref::<String>()
+                        .map(String::as_str)
+                        .or_else(|| panic.downcast_ref::<&str>().copied())
+                        .unwrap_or_default();
+                    assert!(message.contains(reason), "{message}");
+
+                    self.pair = Storable::decode(&mut &storage[..]).unwrap();
+                    assert_eq!(self.state(), before);
+                    assert_eq!(test::recorded_events().count(), events);
+                }
+
+                /// The quote for a swap of `amount_in`, with the token paid in, or `None` if
+                /// the pool cannot honour it.
+                fn quote(
+                    &self,
+                    zero_for_one: bool,
+                    amount_in: Balance,
+                ) -> Option<(Balance, [u8; 32])> {
+                    // A pending donation would count as input too.
+                    if self.reserves() != self.balances() {
+                        return None;
+                    }
+                    let amount_out = self
+                        .sim
+                        .clone()
+                        .swap_exact_in(zero_for_one, amount_in, now())
+                        .ok()?;
+                    Some((amount_out, if zero_for_one { TOKEN_0 } else { TOKEN_1 }))
+                }
+
+                fn apply(&mut self, op: &Op) {
+                    match *op {
+                        Op::Mint {
+                            user,
+                            amount_0,
+                            amount_1,
+                        } => {
+                            self.mint(user, amount_0, amount_1);
+                        }
+                        Op::Burn { user, percent } => {
+                            let liquidity = mock::balance(LP_TOKEN, self.users[user])
+                                * percent as Balance
+                                / 100;
+                            if liquidity > 0 {
+                                self.burn(user, liquidity);
+                            }
+                        }
+                        Op::RoundTrip {
+                            user,
+                            amount_0,
+                            amount_1,
+                        } => {
+                            // A pending donation would be credited to the depositor.
+                            if self.reserves() != self.balances() {
+                                return;
+                            }
+                            let Some(liquidity) = self.mint(user, amount_0, amount_1) else {
+                                return;
+                            };
+                            if let Some((out_0, out_1)) = self.burn(user, liquidity) {
+                                assert!(out_0 <= amount_0 && out_1 <= amount_1);
+                            }
+                        }
+                        Op::Swap {
+                            user,
+                            zero_for_one,
+                            amount_in,
+                        } => {
+                            let (reserve_0, reserve_1) = self.reserves();
+                            let Ok(amount_out) =
+                                self.sim.swap_exact_in(zero_for_one, amount_in, now())
+                            else {
+                                return;
+                            };
+                            let (token_in, amount_0_out, amount_1_out) = if zero_for_one {
+                                (TOKEN_0, 0, amount_out)
+                            } else {
+                                (TOKEN_1, amount_out, 0)
+                            };
+
+                            self.pay(user, token_in, amount_in);
+                            self.pair.swap(
+                                amount_0_out,
+                                amount_1_out,
+                                self.users[user],
+                                Vec::new(),
+                            );
+
+                            let (new_reserve_0, new_reserve_1) = self.reserves();
+                            assert!(new_reserve_0 * new_reserve_1 >= reserve_0 * reserve_1);
+                            assert_eq!(self.reserves(), self.balances());
+                        }
+                        Op::Donate { token_0, amount } => {
+                            if token_0 {
+                                self.pay(2, TOKEN_0, amount);
+                                self.sim.deposit(amount, 0);
+                            } else {
+                                self.pay(2, TOKEN_1, amount);
+                                self.sim.deposit(0, amount);
+                            }
+                        }
+                        Op::Skim => {
+                            self.pair.skim(self.users[2]);
+                            self.sim.skim();
+                            assert_eq!(self.reserves(), self.balances());
+                        }
+                        Op::Sync => {
+                            self.pair.sync();
+                            self.sim.sync(now());
+                            assert_eq!(self.reserves(), self.balances());
+                        }
+                        Op::Wait { millis } => {
+                            test::set_block_timestamp::<DefaultEnvironment>(now() + millis);
+                        }
+                        Op::FeeSwitch { on } => {
+                            let fee_to = on.then_some(self.fee_to);
+                            mock::set_fee_to(fee_to);
+                            self.sim.set_fee_to(fee_to);
+                        }
+                        Op::DynamicFee { bounds } => {
+                            let caller = ink::env::caller::<DefaultEnvironment>();
+                            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+                            if self.sim.set_dynamic_fee(bounds).is_ok() {
+                                self.pair.set_dynamic_fee(bounds);
+                            } else {
+                                self.rejects("UniswapV2: INVALID_FEE", |world| {
+                                    world.pair.set_dynamic_fee(bounds);
+                                });
+                            }
+                            test::set_caller::<DefaultEnvironment>(caller);
+                        }
+                        Op::OverQuote {
+                            user,
+                            zero_for_one,
+                            amount_in,
+                        } => {
+                            let Some((amount_out, token_in)) = self.quote(zero_for_one, amount_in)
+                            else {
+                                return;
+                            };
+                            let amount_out = amount_out + 1;
+                            let (reserve_0, reserve_1) = self.reserves();
+                            if amount_out >= if zero_for_one { reserve_1 } else { reserve_0 } {
+                                return;
+                            }
+                            let (amount_0_out, amount_1_out) = if zero_for_one {
+                                (0, amount_out)
+                            } else {
+                                (amount_out, 0)
+                            };
+                            self.rejects("UniswapV2: K", |world| {
+                                world.pay(user, token_in, amount_in);
+                                let to = world.users[user];
+                                world.pair.swap(amount_0_out, amount_1_out, to, Vec::new());
+                            });
+                        }
+                        Op::OverBurn { user } => {
+                            let owner = self.users[user];
+                            let liquidity = mock::balance(LP_TOKEN, owner) + 1;
+                            self.rejects("UniswapV2: INSUFFICIENT_LIQUIDITY_BURNED", |world| {
+                                // The LP token refuses the transfer, so nothing reaches the pair.
+                                let _ = mock::send(LP_TOKEN.into(), owner, PAIR.into(), liquidity);
+                                world.pair.burn(owner);
+                            });
+                        }
+                        Op::ToToken {
+                            user,
+                            zero_for_one,
+                            amount_in,
+                        } => {
+                            let Some((amount_out, token_in)) = self.quote(zero_for_one, amount_in)
+                            else {
+                                return;
+                            };
+                            let (amount_0_out, amount_1_out, to) = if zero_for_one {
+                                (0, amount_out, TOKEN_1)
+                            } else {
+                                (amount_out, 0, TOKEN_0)
+                            };
+                            self.rejects("UniswapV2: INVALID_TO_ADDRESS", |world| {
+                                world.pay(user, token_in, amount_in);
+                                world
+                                    .pair
+                                    .swap(amount_0_out, amount_1_out, to.into(), Vec::new());
+                            });
+                        }
+                    }
+                }
+
+                fn check(&self) {
+                    let (reserve_0, reserve_1) = self.reserves();
+                    let (balance_0, balance_1) = self.balances();
+                    assert!(reserve_0 <= balance_0 && reserve_1 <= balance_1);
+                    assert_eq!(
+                        self.pair.get_total_supply() == 0,
+                        reserve_0 == 0 && reserve_1 == 0
+                    );
+
+                    assert_eq!(self.sim.get_reserves(), self.pair.get_reserves());
+                    assert_eq!(self.sim.balances(), self.balances());
+                    assert_eq!(self.sim.total_supply(), self.pair.get_total_supply());
+                    assert_eq!(self.sim.k_last(), self.pair.k_last);
+                    assert_eq!(self.sim.fee(), self.pair.fee());
+                    assert_eq!(
+                        self.sim.dynamic_fee(),
+                        self.pair
+                            .dynamic_fee()
+                            .map(|state| amm_sim::pool::DynamicFee {
+                                min_fee: state.min_fee,
+                                max_fee: state.max_fee,
+                                volatility: state.volatility,
+                                window_start: state.window_start,
+                                price_0_cumulative: state.price_0_cumulative,
+                                price_0_average: state.price_0_average,
+                            })
+                    );
+                    assert_eq!(
+                        self.sim.price_0_cumulative_last(),
+                        self.pair.price_0_cumulative_last()
+                    );
+                    assert_eq!(
+                        self.sim.price_1_cumulative_last(),
+                        self.pair.price_1_cumulative_last()
+                    );
+                    let zero = AccountId::from([0x0; 32]);
+                    for owner in self.users.into_iter().chain([self.fee_to, zero]) {
+                        assert_eq!(self.sim.lp_balance(owner), mock::balance(LP_TOKEN, owner));
+                    }
+                }
+            }
+
+            proptest! {
+                #[test]
+                fn pair_invariants_hold(
+                    amount_0 in 1_000_000..1_000_000_000 as Balance,
+                    amount_1 in 1_000_000..1_000_000_000 as Balance,
+                    ops in prop::collection::vec(op(), 1..40),
+                ) {
+                    test::run_test::<DefaultEnvironment, _>(|_| {
+                        let mut world = World::new(amount_0, amount_1);
+                        world.check();
+                        for op in &ops {
+                            world.apply(op);
+                            world.check();
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+                }
+            }
         }
     }
 }
@@ -0,0 +1,990 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+pub mod router {
+    use ink::env::{
+        call::{build_call, ExecutionInput, Selector},
+        DefaultEnvironment,
+    };
+    use ink::prelude::vec::Vec;
+    use uniswap_math::{dynamic_fee, MathError};
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        Expired,
+        PairNotFound,
+        InvalidPath,
+        InsufficientAAmount,
+        InsufficientBAmount,
+        InsufficientOutputAmount,
+        ExcessiveInputAmount,
+        TransferFailed,
+        CallFailed,
+        Math(MathError),
+    }
+
+    impl From<MathError> for Error {
+        fn from(error: MathError) -> Self {
+            Error::Math(error)
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// The kind of pool a hop of a route trades through.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PoolKind {
+        /// The constant product pair of `get_pair`.
+        Classic,
+        /// The pair of `get_stable_pair`.
+        Stable,
+        /// The pair of `get_weighted_pair` giving the token swapped in this weight, in
+        /// percent: `Weighted(80)` sells into the heavy side of an 80/20 pair.
+        Weighted(u32),
+    }
+
+    /// One hop of a route: `token_in` swapped for `token_out` through a pool of `kind`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Hop {
+        pub token_in: AccountId,
+        pub token_out: AccountId,
+        pub kind: PoolKind,
+    }
+
+    /// Token calls are only told apart from success, so their error payload is not decoded.
+    type TokenResult = core::result::Result<(), ()>;
+
+    #[ink(storage)]
+    pub struct Router {
+        /// Stores the factory address
+        factory: AccountId,
+        /// Wrapped native token used for the `*_native` entry points
+        wnative: AccountId,
+    }
+
+    impl Router {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId, wnative: AccountId) -> Self {
+            Self { factory, wnative }
+        }
+
+        #[ink(message)]
+        pub fn factory(&self) -> AccountId {
+            self.factory
+        }
+
+        #[ink(message)]
+        pub fn wnative(&self) -> AccountId {
+            self.wnative
+        }
+
+        #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn add_liquidity(
+            &mut self,
+            token_a: AccountId,
+            token_b: AccountId,
+            amount_a_desired: Balance,
+            amount_b_desired: Balance,
+            amount_a_min: Balance,
+            amount_b_min: Balance,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<(Balance, Balance, Balance)> {
+            self.ensure(deadline)?;
+            let (amount_a, amount_b) = self.calculate_liquidity(
+                token_a,
+                token_b,
+                amount_a_desired,
+                amount_b_desired,
+                amount_a_min,
+                amount_b_min,
+            )?;
+            let pair = self.pair_for(token_a, token_b)?;
+            let caller = self.env().caller();
+            self.transfer_from(token_a, caller, pair, amount_a)?;
+            self.transfer_from(token_b, caller, pair, amount_b)?;
+            let liquidity = self.pair_mint(pair, to)?;
+            Ok((amount_a, amount_b, liquidity))
+        }
+
+        /// Adds liquidity to a `token`/WNATIVE pair, wrapping the native value sent along.
+        /// Any native value not needed at the current price is refunded to the caller.
+        #[ink(message, payable)]
+        pub fn add_liquidity_native(
+            &mut self,
+            token: AccountId,
+            amount_token_desired: Balance,
+            amount_token_min: Balance,
+            amount_native_min: Balance,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<(Balance, Balance, Balance)> {
+            self.ensure(deadline)?;
+            let value = self.env().transferred_value();
+            let (amount_token, amount_native) = self.calculate_liquidity(
+                token,
+                self.wnative,
+                amount_token_desired,
+                value,
+                amount_token_min,
+                amount_native_min,
+            )?;
+            let pair = self.pair_for(token, self.wnative)?;
+            let caller = self.env().caller();
+            self.transfer_from(token, caller, pair, amount_token)?;
+            self.wrap(amount_native)?;
+            self.transfer(self.wnative, pair, amount_native)?;
+            let liquidity = self.pair_mint(pair, to)?;
+            if value > amount_native {
+                self.send_native(caller, value - amount_native)?;
+            }
+            Ok((amount_token, amount_native, liquidity))
+        }
+
+        #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn remove_liquidity(
+            &mut self,
+            token_a: AccountId,
+            token_b: AccountId,
+            liquidity: Balance,
+            amount_a_min: Balance,
+            amount_b_min: Balance,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<(Balance, Balance)> {
+            self.ensure(deadline)?;
+            self.burn_liquidity(token_a, token_b, liquidity, amount_a_min, amount_b_min, to)
+        }
+
+        /// Removes liquidity from a `token`/WNATIVE pair and unwraps the native side for `to`.
+        #[ink(message)]
+        pub fn remove_liquidity_native(
+            &mut self,
+            token: AccountId,
+            liquidity: Balance,
+            amount_token_min: Balance,
+            amount_native_min: Balance,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<(Balance, Balance)> {
+            self.ensure(deadline)?;
+            let (amount_token, amount_native) = self.burn_liquidity(
+                token,
+                self.wnative,
+                liquidity,
+                amount_token_min,
+                amount_native_min,
+                self.env().account_id(),
+            )?;
+            self.transfer(token, to, amount_token)?;
+            self.unwrap(amount_native)?;
+            self.send_native(to, amount_native)?;
+            Ok((amount_token, amount_native))
+        }
+
+        #[ink(message)]
+        pub fn swap_exact_tokens_for_tokens(
+            &mut self,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            let amounts = self.amounts_out(amount_in, &path)?;
+            if amounts[amounts.len() - 1] < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amounts[0])?;
+            self.swap(&amounts, &path, to)?;
+            Ok(amounts)
+        }
+
+        #[ink(message)]
+        pub fn swap_tokens_for_exact_tokens(
+            &mut self,
+            amount_out: Balance,
+            amount_in_max: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            let amounts = self.amounts_in(amount_out, &path)?;
+            if amounts[0] > amount_in_max {
+                return Err(Error::ExcessiveInputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amounts[0])?;
+            self.swap(&amounts, &path, to)?;
+            Ok(amounts)
+        }
+
+        /// Like `swap_exact_tokens_for_tokens`, along a route whose hops may go through
+        /// stable and weighted pairs as well as classic ones.
+        #[ink(message)]
+        pub fn swap_exact_tokens_for_tokens_on_route(
+            &mut self,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            route: Vec<Hop>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            let (pools, amounts) = self.amounts_out_on_route(amount_in, &route)?;
+            if amounts[amounts.len() - 1] < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            self.transfer_from(route[0].token_in, self.env().caller(), pools[0], amounts[0])?;
+            for (i, hop) in route.iter().enumerate() {
+                let (token_0, _) = uniswap_math::sort_tokens(hop.token_in, hop.token_out)?;
+                let amount_out = amounts[i + 1];
+                let (amount_0_out, amount_1_out) = if hop.token_in == token_0 {
+                    (0, amount_out)
+                } else {
+                    (amount_out, 0)
+                };
+                let recipient = pools.get(i + 1).copied().unwrap_or(to);
+                self.pair_swap(pools[i], amount_0_out, amount_1_out, recipient)?;
+            }
+            Ok(amounts)
+        }
+
+        #[ink(message, payable)]
+        pub fn swap_exact_native_for_tokens(
+            &mut self,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            if path.first() != Some(&self.wnative) {
+                return Err(Error::InvalidPath);
+            }
+            let amounts = self.amounts_out(self.env().transferred_value(), &path)?;
+            if amounts[amounts.len() - 1] < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.wrap(amounts[0])?;
+            self.transfer(self.wnative, pair, amounts[0])?;
+            self.swap(&amounts, &path, to)?;
+            Ok(amounts)
+        }
+
+        #[ink(message)]
+        pub fn swap_tokens_for_exact_native(
+            &mut self,
+            amount_out: Balance,
+            amount_in_max: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            if path.last() != Some(&self.wnative) {
+                return Err(Error::InvalidPath);
+            }
+            let amounts = self.amounts_in(amount_out, &path)?;
+            if amounts[0] > amount_in_max {
+                return Err(Error::ExcessiveInputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amounts[0])?;
+            self.swap(&amounts, &path, self.env().account_id())?;
+            self.unwrap(amount_out)?;
+            self.send_native(to, amount_out)?;
+            Ok(amounts)
+        }
+
+        #[ink(message)]
+        pub fn swap_exact_tokens_for_native(
+            &mut self,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            if path.last() != Some(&self.wnative) {
+                return Err(Error::InvalidPath);
+            }
+            let amounts = self.amounts_out(amount_in, &path)?;
+            let amount_out = amounts[amounts.len() - 1];
+            if amount_out < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amounts[0])?;
+            self.swap(&amounts, &path, self.env().account_id())?;
+            self.unwrap(amount_out)?;
+            self.send_native(to, amount_out)?;
+            Ok(amounts)
+        }
+
+        /// Buys exactly `amount_out` of the last token in `path`, refunding unused native value.
+        #[ink(message, payable)]
+        pub fn swap_native_for_exact_tokens(
+            &mut self,
+            amount_out: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Vec<Balance>> {
+            self.ensure(deadline)?;
+            if path.first() != Some(&self.wnative) {
+                return Err(Error::InvalidPath);
+            }
+            let value = self.env().transferred_value();
+            let amounts = self.amounts_in(amount_out, &path)?;
+            if amounts[0] > value {
+                return Err(Error::ExcessiveInputAmount);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.wrap(amounts[0])?;
+            self.transfer(self.wnative, pair, amounts[0])?;
+            self.swap(&amounts, &path, to)?;
+            if value > amounts[0] {
+                self.send_native(self.env().caller(), value - amounts[0])?;
+            }
+            Ok(amounts)
+        }
+
+        /// Same as `remove_liquidity_native`, but forwards whatever `token` balance the router
+        /// actually received, for tokens that take a fee on transfer.
+        #[ink(message)]
+        pub fn remove_liquidity_native_supporting_fee_on_transfer(
+            &mut self,
+            token: AccountId,
+            liquidity: Balance,
+            amount_token_min: Balance,
+            amount_native_min: Balance,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<Balance> {
+            self.ensure(deadline)?;
+            let (_, amount_native) = self.burn_liquidity(
+                token,
+                self.wnative,
+                liquidity,
+                amount_token_min,
+                amount_native_min,
+                self.env().account_id(),
+            )?;
+            let amount_token = self.balance_of(token, self.env().account_id())?;
+            self.transfer(token, to, amount_token)?;
+            self.unwrap(amount_native)?;
+            self.send_native(to, amount_native)?;
+            Ok(amount_native)
+        }
+
+        /// Swaps along `path` sizing every hop from what each pair actually received, so tokens
+        /// that burn or tax a share of each transfer can be traded.
+        #[ink(message)]
+        pub fn swap_exact_tokens_for_tokens_supporting_fee_on_transfer(
+            &mut self,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<()> {
+            self.ensure(deadline)?;
+            if path.len() < 2 {
+                return Err(Error::InvalidPath);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amount_in)?;
+            let token_out = path[path.len() - 1];
+            let balance_before = self.balance_of(token_out, to)?;
+            self.swap_supporting_fee_on_transfer(&path, to)?;
+            let balance_after = self.balance_of(token_out, to)?;
+            if balance_after.saturating_sub(balance_before) < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            Ok(())
+        }
+
+        #[ink(message, payable)]
+        pub fn swap_exact_native_for_tokens_supporting_fee_on_transfer(
+            &mut self,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<()> {
+            self.ensure(deadline)?;
+            if path.len() < 2 || path[0] != self.wnative {
+                return Err(Error::InvalidPath);
+            }
+            let amount_in = self.env().transferred_value();
+            let pair = self.pair_for(path[0], path[1])?;
+            self.wrap(amount_in)?;
+            self.transfer(self.wnative, pair, amount_in)?;
+            let token_out = path[path.len() - 1];
+            let balance_before = self.balance_of(token_out, to)?;
+            self.swap_supporting_fee_on_transfer(&path, to)?;
+            let balance_after = self.balance_of(token_out, to)?;
+            if balance_after.saturating_sub(balance_before) < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn swap_exact_tokens_for_native_supporting_fee_on_transfer(
+            &mut self,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            path: Vec<AccountId>,
+            to: AccountId,
+            deadline: Timestamp,
+        ) -> Result<()> {
+            self.ensure(deadline)?;
+            if path.len() < 2 || path[path.len() - 1] != self.wnative {
+                return Err(Error::InvalidPath);
+            }
+            let pair = self.pair_for(path[0], path[1])?;
+            self.transfer_from(path[0], self.env().caller(), pair, amount_in)?;
+            self.swap_supporting_fee_on_transfer(&path, self.env().account_id())?;
+            let amount_out = self.balance_of(self.wnative, self.env().account_id())?;
+            if amount_out < amount_out_min {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            self.unwrap(amount_out)?;
+            self.send_native(to, amount_out)?;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn quote(
+            &self,
+            amount_a: Balance,
+            reserve_a: Balance,
+            reserve_b: Balance,
+        ) -> Result<Balance> {
+            Ok(uniswap_math::quote(amount_a, reserve_a, reserve_b)?)
+        }
+
+        /// Output of a classic pair charging `fee`, in millionths, as the pair's `fee`
+        /// reports: 3000 unless the pair has a dynamic fee.
+        #[ink(message)]
+        pub fn get_amount_out(
+            &self,
+            amount_in: Balance,
+            reserve_in: Balance,
+            reserve_out: Balance,
+            fee: u32,
+        ) -> Result<Balance> {
+            Ok(dynamic_fee::get_amount_out(
+                amount_in,
+                reserve_in,
+                reserve_out,
+                fee,
+            )?)
+        }
+
+        /// Input a classic pair charging `fee`, in millionths, needs for `amount_out`.
+        #[ink(message)]
+        pub fn get_amount_in(
+            &self,
+            amount_out: Balance,
+            reserve_in: Balance,
+            reserve_out: Balance,
+            fee: u32,
+        ) -> Result<Balance> {
+            Ok(dynamic_fee::get_amount_in(
+                amount_out,
+                reserve_in,
+                reserve_out,
+                fee,
+            )?)
+        }
+
+        #[ink(message)]
+        pub fn get_amounts_out(
+            &self,
+            amount_in: Balance,
+            path: Vec<AccountId>,
+        ) -> Result<Vec<Balance>> {
+            self.amounts_out(amount_in, &path)
+        }
+
+        #[ink(message)]
+        pub fn get_amounts_in(
+            &self,
+            amount_out: Balance,
+            path: Vec<AccountId>,
+        ) -> Result<Vec<Balance>> {
+            self.amounts_in(amount_out, &path)
+        }
+
+        /// Like `get_amounts_out`, along a route whose hops may go through stable and
+        /// weighted pairs, which price their hop themselves.
+        #[ink(message)]
+        pub fn get_amounts_out_on_route(
+            &self,
+            amount_in: Balance,
+            route: Vec<Hop>,
+        ) -> Result<Vec<Balance>> {
+            Ok(self.amounts_out_on_route(amount_in, &route)?.1)
+        }
+
+        fn ensure(&self, deadline: Timestamp) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::Expired);
+            }
+            Ok(())
+        }
+
+        fn calculate_liquidity(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+            amount_a_desired: Balance,
+            amount_b_desired: Balance,
+            amount_a_min: Balance,
+            amount_b_min: Balance,
+        ) -> Result<(Balance, Balance)> {
+            let (reserve_a, reserve_b) = self.get_reserves(token_a, token_b)?;
+            if reserve_a == 0 && reserve_b == 0 {
+                return Ok((amount_a_desired, amount_b_desired));
+            }
+            let amount_b_optimal = uniswap_math::quote(amount_a_desired, reserve_a, reserve_b)?;
+            if amount_b_optimal <= amount_b_desired {
+                if amount_b_optimal < amount_b_min {
+                    return Err(Error::InsufficientBAmount);
+                }
+                Ok((amount_a_desired, amount_b_optimal))
+            } else {
+                let amount_a_optimal = uniswap_math::quote(amount_b_desired, reserve_b, reserve_a)?;
+                if amount_a_optimal > amount_a_desired || amount_a_optimal < amount_a_min {
+                    return Err(Error::InsufficientAAmount);
+                }
+                Ok((amount_a_optimal, amount_b_desired))
+            }
+        }
+
+        fn burn_liquidity(
+            &mut self,
+            token_a: AccountId,
+            token_b: AccountId,
+            liquidity: Balance,
+            amount_a_min: Balance,
+            amount_b_min: Balance,
+            to: AccountId,
+        ) -> Result<(Balance, Balance)> {
+            let pair = self.pair_for(token_a, token_b)?;
+            let lp_token = self.pair_lp_token(pair)?;
+            self.transfer_from(lp_token, self.env().caller(), pair, liquidity)?;
+            let (amount_0, amount_1) = self.pair_burn(pair, to)?;
+            let (token_0, _) = uniswap_math::sort_tokens(token_a, token_b)?;
+            let (amount_a, amount_b) = if token_a == token_0 {
+                (amount_0, amount_1)
+            } else {
+                (amount_1, amount_0)
+            };
+            if amount_a < amount_a_min {
+                return Err(Error::InsufficientAAmount);
+            }
+            if amount_b < amount_b_min {
+                return Err(Error::InsufficientBAmount);
+            }
+            Ok((amount_a, amount_b))
+        }
+
+        /// Requires the initial amount to have already been sent to the first pair.
+        fn swap(&mut self, amounts: &[Balance], path: &[AccountId], to: AccountId) -> Result<()> {
+            for i in 0..path.len() - 1 {
+                let (input, output) = (path[i], path[i + 1]);
+                let (token_0, _) = uniswap_math::sort_tokens(input, output)?;
+                let amount_out = amounts[i + 1];
+                let (amount_0_out, amount_1_out) = if input == token_0 {
+                    (0, amount_out)
+                } else {
+                    (amount_out, 0)
+                };
+                let recipient = if i < path.len() - 2 {
+                    self.pair_for(output, path[i + 2])?
+                } else {
+                    to
+                };
+                let pair = self.pair_for(input, output)?;
+                self.pair_swap(pair, amount_0_out, amount_1_out, recipient)?;
+            }
+            Ok(())
+        }
+
+        /// Requires the initial amount to have already been sent to the first pair. Each hop's
+        /// input is read back from the pair's balance rather than trusted from a quote.
+        fn swap_supporting_fee_on_transfer(
+            &mut self,
+            path: &[AccountId],
+            to: AccountId,
+        ) -> Result<()> {
+            for i in 0..path.len() - 1 {
+                let (input, output) = (path[i], path[i + 1]);
+                let (token_0, _) = uniswap_math::sort_tokens(input, output)?;
+                let pair = self.pair_for(input, output)?;
+                let (reserve_input, reserve_output) = self.get_reserves(input, output)?;
+                let amount_input = self.balance_of(input, pair)?.saturating_sub(reserve_input);
+                let amount_output = dynamic_fee::get_amount_out(
+                    amount_input,
+                    reserve_input,
+                    reserve_output,
+                    self.pair_fee(pair)?,
+                )?;
+                let (amount_0_out, amount_1_out) = if input == token_0 {
+                    (0, amount_output)
+                } else {
+                    (amount_output, 0)
+                };
+                let recipient = if i < path.len() - 2 {
+                    self.pair_for(output, path[i + 2])?
+                } else {
+                    to
+                };
+                self.pair_swap(pair, amount_0_out, amount_1_out, recipient)?;
+            }
+            Ok(())
+        }
+
+        fn amounts_out(&self, amount_in: Balance, path: &[AccountId]) -> Result<Vec<Balance>> {
+            if path.len() < 2 {
+                return Err(Error::InvalidPath);
+            }
+            let mut amounts = Vec::with_capacity(path.len());
+            amounts.push(amount_in);
+            for i in 0..path.len() - 1 {
+                let (reserve_in, reserve_out) = self.get_reserves(path[i], path[i + 1])?;
+                let fee = self.pair_fee(self.pair_for(path[i], path[i + 1])?)?;
+                amounts.push(dynamic_fee::get_amount_out(
+                    amounts[i],
+                    reserve_in,
+                    reserve_out,
+                    fee,
+                )?);
+            }
+            Ok(amounts)
+        }
+
+        fn amounts_in(&self, amount_out: Balance, path: &[AccountId]) -> Result<Vec<Balance>> {
+            if path.len() < 2 {
+                return Err(Error::InvalidPath);
+            }
+            let mut amounts = ink::prelude::vec![0; path.len()];
+            amounts[path.len() - 1] = amount_out;
+            for i in (1..path.len()).rev() {
+                let (reserve_in, reserve_out) = self.get_reserves(path[i - 1], path[i])?;
+                let fee = self.pair_fee(self.pair_for(path[i - 1], path[i])?)?;
+                amounts[i - 1] =
+                    dynamic_fee::get_amount_in(amounts[i], reserve_in, reserve_out, fee)?;
+            }
+            Ok(amounts)
+        }
+
+        /// The pool of each hop of `route` and the amounts in and out along it.
+        fn amounts_out_on_route(
+            &self,
+            amount_in: Balance,
+            route: &[Hop],
+        ) -> Result<(Vec<AccountId>, Vec<Balance>)> {
+            let connected = route
+                .windows(2)
+                .all(|hops| hops[0].token_out == hops[1].token_in);
+            if route.is_empty() || !connected {
+                return Err(Error::InvalidPath);
+            }
+            let mut pools = Vec::with_capacity(route.len());
+            let mut amounts = Vec::with_capacity(route.len() + 1);
+            amounts.push(amount_in);
+            for (i, hop) in route.iter().enumerate() {
+                let pool = self.pool_for(hop)?;
+                amounts.push(self.pool_amount_out(hop, pool, amounts[i])?);
+                pools.push(pool);
+            }
+            Ok((pools, amounts))
+        }
+
+        fn pool_for(&self, hop: &Hop) -> Result<AccountId> {
+            let input = |selector| {
+                ExecutionInput::new(Selector::new(selector))
+                    .push_arg(hop.token_in)
+                    .push_arg(hop.token_out)
+            };
+            let call = build_call::<DefaultEnvironment>()
+                .call(self.factory)
+                .gas_limit(0);
+            let pool = match hop.kind {
+                PoolKind::Classic => return self.pair_for(hop.token_in, hop.token_out),
+                PoolKind::Stable => call
+                    .exec_input(input(ink::selector_bytes!("get_stable_pair")))
+                    .returns::<Option<AccountId>>()
+                    .try_invoke(),
+                PoolKind::Weighted(weight_in) => call
+                    .exec_input(
+                        input(ink::selector_bytes!("get_weighted_pair")).push_arg(weight_in),
+                    )
+                    .returns::<Option<AccountId>>()
+                    .try_invoke(),
+            };
+            pool.map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)?
+                .ok_or(Error::PairNotFound)
+        }
+
+        /// Output of `hop` for `amount_in`. Classic pairs are priced here from their reserves
+        /// and fee, other pools by their own `get_amount_out`.
+        fn pool_amount_out(
+            &self,
+            hop: &Hop,
+            pool: AccountId,
+            amount_in: Balance,
+        ) -> Result<Balance> {
+            if hop.kind == PoolKind::Classic {
+                let (reserve_in, reserve_out) = self.get_reserves(hop.token_in, hop.token_out)?;
+                let fee = self.pair_fee(pool)?;
+                return Ok(dynamic_fee::get_amount_out(
+                    amount_in,
+                    reserve_in,
+                    reserve_out,
+                    fee,
+                )?);
+            }
+            Ok(build_call::<DefaultEnvironment>()
+                .call(pool)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("get_amount_out")))
+                        .push_arg(amount_in)
+                        .push_arg(hop.token_in),
+                )
+                .returns::<core::result::Result<Balance, MathError>>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)??)
+        }
+
+        /// Fetches the reserves of the `token_a`/`token_b` pair ordered as the arguments.
+        fn get_reserves(
+            &self,
+            token_a: AccountId,
+            token_b: AccountId,
+        ) -> Result<(Balance, Balance)> {
+            let (token_0, _) = uniswap_math::sort_tokens(token_a, token_b)?;
+            let pair = self.pair_for(token_a, token_b)?;
+            let (reserve_0, reserve_1, _) = build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "get_reserves"
+                ))))
+                .returns::<(u128, u128, u128)>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)?;
+            if token_a == token_0 {
+                Ok((reserve_0, reserve_1))
+            } else {
+                Ok((reserve_1, reserve_0))
+            }
+        }
+
+        fn pair_for(&self, token_a: AccountId, token_b: AccountId) -> Result<AccountId> {
+            build_call::<DefaultEnvironment>()
+                .call(self.factory)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("get_pair")))
+                        .push_arg(token_a)
+                        .push_arg(token_b),
+                )
+                .returns::<Option<AccountId>>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)?
+                .ok_or(Error::PairNotFound)
+        }
+
+        /// Swap fee of `pair` in millionths, which changes over time for a dynamic fee.
+        fn pair_fee(&self, pair: AccountId) -> Result<u32> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "fee"
+                ))))
+                .returns::<u32>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn pair_lp_token(&self, pair: AccountId) -> Result<AccountId> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "lp_token"
+                ))))
+                .returns::<AccountId>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn pair_mint(&mut self, pair: AccountId, to: AccountId) -> Result<Balance> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("mint"))).push_arg(to),
+                )
+                .returns::<Balance>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn pair_burn(&mut self, pair: AccountId, to: AccountId) -> Result<(Balance, Balance)> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("burn"))).push_arg(to),
+                )
+                .returns::<(Balance, Balance)>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn pair_swap(
+            &mut self,
+            pair: AccountId,
+            amount_0_out: Balance,
+            amount_1_out: Balance,
+            to: AccountId,
+        ) -> Result<()> {
+            build_call::<DefaultEnvironment>()
+                .call(pair)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("swap")))
+                        .push_arg(amount_0_out)
+                        .push_arg(amount_1_out)
+                        .push_arg(to)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<()>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn balance_of(&self, token: AccountId, owner: AccountId) -> Result<Balance> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::balance_of")))
+                        .push_arg(owner),
+                )
+                .returns::<Balance>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        fn transfer(&mut self, token: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(value)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<TokenResult>()
+                .try_invoke()
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        fn transfer_from(
+            &mut self,
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(value)
+                    .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<TokenResult>()
+                .try_invoke()
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// Deposits `value` of native currency into WNATIVE on behalf of the router.
+        fn wrap(&mut self, value: Balance) -> Result<()> {
+            build_call::<DefaultEnvironment>()
+                .call(self.wnative)
+                .gas_limit(0)
+                .transferred_value(value)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "deposit"
+                ))))
+                .returns::<TokenResult>()
+                .try_invoke()
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// Redeems `value` of the router's WNATIVE back into native currency.
+        fn unwrap(&mut self, value: Balance) -> Result<()> {
+            build_call::<DefaultEnvironment>()
+                .call(self.wnative)
+                .gas_limit(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("withdraw")))
+                        .push_arg(value),
+                )
+                .returns::<TokenResult>()
+                .try_invoke()
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        fn send_native(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.env()
+                .transfer(to, value)
+                .map_err(|_| Error::TransferFailed)
+        }
+    }
+}
@@ -0,0 +1,607 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// A pair whose tokens make up uneven shares of its value, such as 80/20, trading on
+/// Balancer's `x^wx * y^wy >= k` curve. With a 50/50 split it is the constant product pair.
+/// It keeps `Pair`'s message surface and events, so the factory, clients and indexers treat
+/// both alike; routers price it with `get_amount_out` rather than the constant product
+/// formula.
+#[ink::contract]
+pub mod weighted_pair {
+    use ink::prelude::vec::Vec;
+    use uniswap_math::pair::{self as math, MINIMUM_LIQUIDITY};
+    use uniswap_math::{weighted, MathError};
+
+    #[ink(event)]
+    pub struct Sync {
+        reserve_0: u128,
+        reserve_1: u128,
+        /// The emitting pair, so `Sync` can be filtered by topic across pairs.
+        #[ink(topic)]
+        pair: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        owner: Option<AccountId>,
+        amount_0: Balance,
+        amount_1: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// LP tokens minted to `to`.
+        liquidity: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        sender: Option<AccountId>,
+        amount_0: Balance,
+        amount_1: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// LP tokens burned, those sent to the pair beforehand.
+        liquidity: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Swap {
+        #[ink(topic)]
+        sender: Option<AccountId>,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        #[ink(topic)]
+        to: AccountId,
+        /// Reserves after the swap.
+        reserve_0: Balance,
+        reserve_1: Balance,
+    }
+
+    /// Unlike `Pair`, no protocol fee is minted to the factory's `fee_to`, and there is no
+    /// price oracle, the reserve ratio not being the price on this curve.
+    #[ink(storage)]
+    pub struct WeightedPair {
+        factory: AccountId,
+        token_0: AccountId,
+        token_1: AccountId,
+        reserve_0: u128,
+        reserve_1: u128,
+        psp22token: AccountId,
+        /// Block timestamp, in milliseconds, of the last reserves update.
+        block_timestamp_last: u128,
+        /// Share of the pair's value in token 0, in percent. Token 1 makes up the rest.
+        weight_0: u32,
+    }
+
+    impl WeightedPair {
+        #[ink(constructor)]
+        pub fn new(
+            factory: AccountId,
+            psp22token: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+            weight_0: u32,
+        ) -> Self {
+            assert!(
+                weighted::is_valid_weight(weight_0),
+                "UniswapV2: INVALID_WEIGHT"
+            );
+            Self {
+                factory,
+                token_0,
+                token_1,
+                reserve_0: 0,
+                reserve_1: 0,
+                psp22token,
+                block_timestamp_last: 0,
+                weight_0,
+            }
+        }
+
+        /// Constructor used by `UniswapCore::create_weighted_pair`, which sets the tokens
+        /// and weights right after through `initialize`.
+        #[ink(constructor)]
+        pub fn create() -> Self {
+            let zero = AccountId::from([0x0; 32]);
+            Self::new(
+                Self::env().caller(),
+                zero,
+                zero,
+                zero,
+                weighted::WEIGHT_DENOMINATOR / 2,
+            )
+        }
+
+        #[ink(message)]
+        pub fn initialize(
+            &mut self,
+            psp22token: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+            weight_0: u32,
+        ) {
+            assert!(self.env().caller() == self.factory, "UniswapV2: FORBIDDEN");
+            assert!(
+                self.token_0 == AccountId::from([0x0; 32]),
+                "UniswapV2: ALREADY_INITIALIZED"
+            );
+            assert!(
+                weighted::is_valid_weight(weight_0),
+                "UniswapV2: INVALID_WEIGHT"
+            );
+            self.weight_0 = weight_0;
+            self.psp22token = psp22token;
+            self.token_0 = token_0;
+            self.token_1 = token_1;
+        }
+
+        /// Swaps this pair's code for `code_hash`, keeping its storage. Only the factory
+        /// can upgrade a pair, see `UniswapCore::upgrade_pair`.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) {
+            assert!(self.env().caller() == self.factory, "UniswapV2: FORBIDDEN");
+            self.env()
+                .set_code_hash(&code_hash)
+                .expect("UniswapV2: SET_CODE_FAILED");
+        }
+
+        #[ink(message)]
+        pub fn get_reserves(&self) -> (u128, u128, u128) {
+            (self.reserve_0, self.reserve_1, self.block_timestamp_last)
+        }
+
+        /// Returns the PSP22 contract holding this pair's liquidity shares.
+        #[ink(message)]
+        pub fn lp_token(&self) -> AccountId {
+            self.psp22token
+        }
+
+        #[ink(message)]
+        pub fn token_0(&self) -> AccountId {
+            self.token_0
+        }
+
+        #[ink(message)]
+        pub fn token_1(&self) -> AccountId {
+            self.token_1
+        }
+
+        /// Swap fee in millionths of the amount in, e.g. 3000 for 0.3%.
+        #[ink(message)]
+        pub fn fee(&self) -> u32 {
+            (uniswap_math::FEE_NUMERATOR * 1_000_000 / uniswap_math::FEE_DENOMINATOR) as u32
+        }
+
+        #[ink(message)]
+        pub fn stable(&self) -> bool {
+            false
+        }
+
+        /// Tells weighted pairs apart from classic and stable ones, which lack this message.
+        #[ink(message)]
+        pub fn weighted(&self) -> bool {
+            true
+        }
+
+        /// Share of the pair's value in token 0, in percent, e.g. 80 for an 80/20 pair.
+        #[ink(message)]
+        pub fn weight_0(&self) -> u32 {
+            self.weight_0
+        }
+
+        #[ink(message)]
+        pub fn weight_1(&self) -> u32 {
+            weighted::WEIGHT_DENOMINATOR - self.weight_0
+        }
+
+        /// Output of swapping `amount_in` of `token_in` at the current reserves.
+        #[ink(message)]
+        pub fn get_amount_out(
+            &self,
+            amount_in: Balance,
+            token_in: AccountId,
+        ) -> Result<Balance, MathError> {
+            if token_in == self.token_0 {
+                weighted::get_amount_out(
+                    amount_in,
+                    self.reserve_0,
+                    self.reserve_1,
+                    self.weight_0(),
+                    self.weight_1(),
+                )
+            } else if token_in == self.token_1 {
+                weighted::get_amount_out(
+                    amount_in,
+                    self.reserve_1,
+                    self.reserve_0,
+                    self.weight_1(),
+                    self.weight_0(),
+                )
+            } else {
+                Err(MathError::InvalidToken)
+            }
+        }
+
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId) -> Balance {
+            assert!(!self.is_paused(), "UniswapV2: PAUSED");
+            let (reserve_0, reserve_1, _) = self.get_reserves();
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+            let amount_0 = balance_0.saturating_sub(reserve_0);
+            let amount_1 = balance_1.saturating_sub(reserve_1);
+
+            let total_supply = self.get_total_supply();
+            let liquidity =
+                math::liquidity_minted(amount_0, amount_1, reserve_0, reserve_1, total_supply)
+                    .expect("UniswapV2: INSUFFICIENT_LIQUIDITY_MINTED");
+            if total_supply == 0 {
+                self.lp_mint(AccountId::from([0x0; 32]), MINIMUM_LIQUIDITY);
+            }
+            self.lp_mint(to, liquidity);
+
+            self.update(balance_0, balance_1);
+            self.env().emit_event(Mint {
+                owner: Some(self.env().caller()),
+                amount_0,
+                amount_1,
+                to,
+                liquidity,
+            });
+            liquidity
+        }
+
+        #[ink(message)]
+        pub fn burn(&mut self, to: AccountId) -> (Balance, Balance) {
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+            let liquidity = self.get_token_balance(self.psp22token, self.env().account_id());
+
+            let total_supply = self.get_total_supply();
+            let (amount_0, amount_1) =
+                math::amounts_burned(liquidity, balance_0, balance_1, total_supply)
+                    .expect("UniswapV2: INSUFFICIENT_LIQUIDITY_BURNED");
+
+            self.lp_burn(self.env().account_id(), liquidity);
+            self.safe_transfer(self.token_0, to, amount_0);
+            self.safe_transfer(self.token_1, to, amount_1);
+
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+
+            self.update(balance_0, balance_1);
+            self.env().emit_event(Burn {
+                sender: Some(self.env().caller()),
+                amount_0,
+                amount_1,
+                to,
+                liquidity,
+            });
+            (amount_0, amount_1)
+        }
+
+        /// Sends out the requested amounts, then checks the tokens sent in beforehand keep
+        /// the weighted invariant. `data` is ignored, as in `Pair::swap`.
+        #[ink(message)]
+        pub fn swap(
+            &mut self,
+            amount_0_out: Balance,
+            amount_1_out: Balance,
+            to: AccountId,
+            data: Vec<u8>,
+        ) {
+            let _ = data;
+            assert!(!self.is_paused(), "UniswapV2: PAUSED");
+            assert!(
+                amount_0_out > 0 || amount_1_out > 0,
+                "UniswapV2: INSUFFICIENT_OUTPUT_AMOUNT"
+            );
+            let (reserve_0, reserve_1, _) = self.get_reserves();
+            assert!(
+                amount_0_out < reserve_0 && amount_1_out < reserve_1,
+                "UniswapV2: INSUFFICIENT_LIQUIDITY"
+            );
+            assert!(
+                to != self.token_0 && to != self.token_1,
+                "UniswapV2: INVALID_TO_ADDRESS"
+            );
+            if amount_0_out > 0 {
+                self.safe_transfer(self.token_0, to, amount_0_out);
+            }
+            if amount_1_out > 0 {
+                self.safe_transfer(self.token_1, to, amount_1_out);
+            }
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+
+            let amount_0_in = balance_0.saturating_sub(reserve_0 - amount_0_out);
+            let amount_1_in = balance_1.saturating_sub(reserve_1 - amount_1_out);
+            assert!(
+                amount_0_in > 0 || amount_1_in > 0,
+                "UniswapV2: INSUFFICIENT_INPUT_AMOUNT"
+            );
+            assert!(
+                weighted::k_holds(
+                    balance_0,
+                    balance_1,
+                    amount_0_in,
+                    amount_1_in,
+                    reserve_0,
+                    reserve_1,
+                    self.weight_0(),
+                    self.weight_1(),
+                ),
+                "UniswapV2: K"
+            );
+
+            self.update(balance_0, balance_1);
+            self.env().emit_event(Swap {
+                sender: Some(self.env().caller()),
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+                to,
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
+            });
+        }
+
+        /// Sends whatever the pair holds above its reserves to `to`.
+        #[ink(message)]
+        pub fn skim(&mut self, to: AccountId) {
+            let excess_0 = self
+                .get_token_balance(self.token_0, self.env().account_id())
+                .saturating_sub(self.reserve_0);
+            let excess_1 = self
+                .get_token_balance(self.token_1, self.env().account_id())
+                .saturating_sub(self.reserve_1);
+
+            if excess_0 > 0 {
+                self.safe_transfer(self.token_0, to, excess_0);
+            }
+            if excess_1 > 0 {
+                self.safe_transfer(self.token_1, to, excess_1);
+            }
+        }
+
+        /// Sets the reserves to the pair's balances.
+        #[ink(message)]
+        pub fn sync(&mut self) {
+            let balance_0 = self.get_token_balance(self.token_0, self.env().account_id());
+            let balance_1 = self.get_token_balance(self.token_1, self.env().account_id());
+            self.update(balance_0, balance_1);
+        }
+
+        #[ink(message)]
+        pub fn get_token_balance(&self, token: AccountId, owner: AccountId) -> Balance {
+            ext::balance_of(token, owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_total_supply(&self) -> Balance {
+            ext::total_supply(self.psp22token).unwrap_or(0)
+        }
+
+        /// Asks the factory whether its guardian has halted trading on this pair.
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            ext::is_paused(self.factory, self.env().account_id())
+        }
+
+        fn update(&mut self, balance_0: Balance, balance_1: Balance) {
+            self.reserve_0 = balance_0;
+            self.reserve_1 = balance_1;
+            self.block_timestamp_last = self.env().block_timestamp() as u128;
+
+            self.env().emit_event(Sync {
+                reserve_0: self.reserve_0,
+                reserve_1: self.reserve_1,
+                pair: self.env().account_id(),
+            });
+        }
+
+        /// Sends `value` of `token` held by the pair to `to`.
+        fn safe_transfer(&self, token: AccountId, to: AccountId, value: Balance) {
+            assert!(
+                ext::transfer(token, to, value),
+                "UniswapV2: TRANSFER_FAILED"
+            );
+        }
+
+        fn lp_mint(&self, to: AccountId, value: Balance) {
+            assert!(
+                ext::mint(self.psp22token, to, value),
+                "UniswapV2: LP_MINT_FAILED"
+            );
+        }
+
+        fn lp_burn(&self, from: AccountId, value: Balance) {
+            assert!(
+                ext::burn(self.psp22token, from, value),
+                "UniswapV2: LP_BURN_FAILED"
+            );
+        }
+    }
+
+    #[cfg(not(test))]
+    use uniswap_support::ext;
+    #[cfg(test)]
+    use uniswap_support::mock as ext;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use uniswap_support::{layout, mock};
+
+        /// Packed fields of the deployed storage, in order.
+        const PACKED_FIELDS: &[&str] = &[
+            "factory",
+            "token_0",
+            "token_1",
+            "reserve_0",
+            "reserve_1",
+            "psp22token",
+            "block_timestamp_last",
+            "weight_0",
+        ];
+
+        #[test]
+        fn storage_layout_is_upgrade_compatible() {
+            layout::assert_upgrade_compatible::<WeightedPair>(PACKED_FIELDS, &[]);
+        }
+
+        const FACTORY: [u8; 32] = [0x10; 32];
+        const PAIR: [u8; 32] = [0x11; 32];
+        const LP_TOKEN: [u8; 32] = [0x12; 32];
+        const TOKEN_0: [u8; 32] = [0x13; 32];
+        const TOKEN_1: [u8; 32] = [0x14; 32];
+
+        const E6: Balance = 1_000_000;
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        /// An 80/20 pair of `TOKEN_0`/`TOKEN_1` called by Alice, who holds ten million of
+        /// both.
+        fn setup() -> WeightedPair {
+            let alice = accounts().alice;
+            mock::mint(TOKEN_0.into(), alice, 10_000_000 * E6);
+            mock::mint(TOKEN_1.into(), alice, 10_000_000 * E6);
+            test::set_callee::<DefaultEnvironment>(PAIR.into());
+            test::set_caller::<DefaultEnvironment>(alice);
+            WeightedPair::new(
+                FACTORY.into(),
+                LP_TOKEN.into(),
+                TOKEN_0.into(),
+                TOKEN_1.into(),
+                80,
+            )
+        }
+
+        /// Seeds the pair at a price of one: four times as much of the 80% token.
+        fn add_liquidity(pair: &mut WeightedPair) -> Balance {
+            mock::pay(TOKEN_0, PAIR, 4_000_000 * E6);
+            mock::pay(TOKEN_1, PAIR, 1_000_000 * E6);
+            pair.mint(accounts().alice)
+        }
+
+        #[ink::test]
+        fn burning_returns_a_share_of_both_weighted_reserves() {
+            let mut pair = setup();
+
+            let liquidity = add_liquidity(&mut pair);
+            let total_supply = pair.get_total_supply();
+            assert_eq!(total_supply, liquidity + MINIMUM_LIQUIDITY);
+
+            mock::pay(LP_TOKEN, PAIR, liquidity / 2);
+            let (amount_0, amount_1) = pair.burn(accounts().bob);
+
+            assert_eq!(amount_0, 4_000_000 * E6 * (liquidity / 2) / total_supply);
+            assert_eq!(mock::balance(TOKEN_1, accounts().bob), amount_1);
+            assert_eq!(pair.get_reserves().1, 1_000_000 * E6 - amount_1);
+        }
+
+        #[ink::test]
+        fn swaps_at_the_weighted_price() {
+            let mut pair = setup();
+            add_liquidity(&mut pair);
+            let bob = accounts().bob;
+
+            // At a price of one, the light token moves further than it would 50/50.
+            let amount_out = pair.get_amount_out(10_000 * E6, TOKEN_0.into()).unwrap();
+            let constant_product =
+                uniswap_math::get_amount_out(10_000 * E6, 4_000_000 * E6, 1_000_000 * E6).unwrap();
+            assert!(amount_out > 9_850 * E6 && amount_out < 9_970 * E6);
+            assert!(amount_out > 3 * constant_product);
+
+            mock::pay(TOKEN_0, PAIR, 10_000 * E6);
+            pair.swap(0, amount_out, bob, Vec::new());
+
+            assert_eq!(mock::balance(TOKEN_1, bob), amount_out);
+            assert_eq!(
+                pair.get_reserves(),
+                (4_010_000 * E6, 1_000_000 * E6 - amount_out, 0)
+            );
+            assert_eq!(test::recorded_events().count(), 4);
+        }
+
+        #[ink::test]
+        fn swap_token_1_for_token_0() {
+            let mut pair = setup();
+            add_liquidity(&mut pair);
+            let bob = accounts().bob;
+
+            let amount_out = pair.get_amount_out(10_000 * E6, TOKEN_1.into()).unwrap();
+            mock::pay(TOKEN_1, PAIR, 10_000 * E6);
+            pair.swap(amount_out, 0, bob, Vec::new());
+
+            assert_eq!(mock::balance(TOKEN_0, bob), amount_out);
+            assert!(amount_out > 9_900 * E6 && amount_out < 9_970 * E6);
+        }
+
+        #[ink::test]
+        fn quotes_only_the_pair_tokens() {
+            let mut pair = setup();
+            add_liquidity(&mut pair);
+
+            assert_eq!(
+                pair.get_amount_out(10_000 * E6, LP_TOKEN.into()),
+                Err(MathError::InvalidToken)
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: K")]
+        fn swap_taking_more_than_the_curve_allows_fails() {
+            let mut pair = setup();
+            add_liquidity(&mut pair);
+
+            let amount_out = pair.get_amount_out(10_000 * E6, TOKEN_0.into()).unwrap();
+            mock::pay(TOKEN_0, PAIR, 10_000 * E6);
+            pair.swap(
+                0,
+                amount_out + amount_out / 1_000,
+                accounts().bob,
+                Vec::new(),
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: PAUSED")]
+        fn swap_fails_while_paused() {
+            let mut pair = setup();
+            add_liquidity(&mut pair);
+            mock::set_paused(true);
+
+            mock::pay(TOKEN_0, PAIR, 10_000 * E6);
+            pair.swap(0, 9_000 * E6, accounts().bob, Vec::new());
+        }
+
+        #[ink::test]
+        fn initialize_sets_the_weights() {
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            let mut pair = WeightedPair::create();
+
+            pair.initialize(LP_TOKEN.into(), TOKEN_0.into(), TOKEN_1.into(), 20);
+
+            assert_eq!((pair.weight_0(), pair.weight_1()), (20, 80));
+            assert_eq!(pair.token_0(), AccountId::from(TOKEN_0));
+            assert_eq!(pair.fee(), 3000);
+            assert!(pair.weighted() && !pair.stable());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "UniswapV2: INVALID_WEIGHT")]
+        fn initialize_rejects_a_token_without_weight() {
+            test::set_caller::<DefaultEnvironment>(FACTORY.into());
+            let mut pair = WeightedPair::create();
+
+            pair.initialize(LP_TOKEN.into(), TOKEN_0.into(), TOKEN_1.into(), 100);
+        }
+    }
+}
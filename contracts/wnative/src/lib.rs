@@ -0,0 +1,70 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[openbrush::implementation(PSP22, PSP22Metadata)]
+#[openbrush::contract]
+pub mod wnative {
+    use openbrush::traits::Storage;
+
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        dst: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        src: AccountId,
+        amount: Balance,
+    }
+
+    /// Wraps the chain's native currency (SBY/ASTR) 1:1 into a PSP22 so it can be paired.
+    #[ink(storage)]
+    #[derive(Storage, Default)]
+    pub struct Wnative {
+        #[storage_field]
+        psp22: psp22::Data,
+        #[storage_field]
+        metadata: metadata::Data,
+    }
+
+    impl Wnative {
+        #[ink(constructor)]
+        pub fn new(name: Option<String>, symbol: Option<String>, decimal: u8) -> Self {
+            let mut instance = Self::default();
+            instance.metadata.name.set(&name);
+            instance.metadata.symbol.set(&symbol);
+            instance.metadata.decimals.set(&decimal);
+            instance
+        }
+
+        /// Mints wrapped tokens to the caller for the native value sent along.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<(), PSP22Error> {
+            let caller = self.env().caller();
+            let amount = self.env().transferred_value();
+            psp22::Internal::_mint_to(self, caller, amount)?;
+            self.env().emit_event(Deposit {
+                dst: caller,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Burns `amount` wrapped tokens of the caller and sends back the native value.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), PSP22Error> {
+            let caller = self.env().caller();
+            psp22::Internal::_burn_from(self, caller, amount)?;
+            self.env()
+                .transfer(caller, amount)
+                .map_err(|_| PSP22Error::Custom(String::from("WNATIVE: TRANSFER_FAILED")))?;
+            self.env().emit_event(Withdrawal {
+                src: caller,
+                amount,
+            });
+            Ok(())
+        }
+    }
+}
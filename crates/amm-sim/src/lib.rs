@@ -0,0 +1,45 @@
+//! Host-side model of the AMM for risk analysis: `Pool` follows `Pair` message by message
+//! (reserves, swap fee, dynamic fee, protocol fee and cumulative prices) and `Network` routes trades
+//! across pools the way the router does. All math comes from `uniswap-math`, the library
+//! the contracts use, so results match the chain to the unit.
+
+pub mod metrics;
+pub mod network;
+pub mod pool;
+
+pub use network::{Network, Trade};
+pub use pool::Pool;
+pub use uniswap_math::{Balance, MathError};
+
+/// Why the contracts would have rejected an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// `UniswapV2: INSUFFICIENT_OUTPUT_AMOUNT`, or a route paying out less than asked.
+    InsufficientOutputAmount,
+    /// `UniswapV2: INSUFFICIENT_LIQUIDITY`: the output would drain a reserve.
+    InsufficientLiquidity,
+    /// `UniswapV2: INSUFFICIENT_INPUT_AMOUNT`.
+    InsufficientInputAmount,
+    /// `UniswapV2: K`.
+    K,
+    /// `UniswapV2: INVALID_FEE`: dynamic fee bounds out of order or above the maximum.
+    InvalidFee,
+    /// Burning more LP tokens than the owner holds.
+    InsufficientLpBalance,
+    /// A route needing more input than the caller allowed.
+    ExcessiveInputAmount,
+    InsufficientAAmount,
+    InsufficientBAmount,
+    PairExists,
+    PairNotFound,
+    InvalidPath,
+    Math(MathError),
+}
+
+impl From<MathError> for SimError {
+    fn from(error: MathError) -> Self {
+        SimError::Math(error)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, SimError>;
@@ -0,0 +1,70 @@
+//! Slippage and impermanent loss, in floating point since they are only ever reported.
+
+use crate::Balance;
+
+/// A replayed trade as it executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub at: u64,
+    /// The amount at every hop, input first.
+    pub amounts: Vec<Balance>,
+    /// Fee-free marginal price along the path before the trade.
+    pub mid_price: f64,
+}
+
+impl Fill {
+    pub fn amount_in(&self) -> Balance {
+        self.amounts[0]
+    }
+
+    pub fn amount_out(&self) -> Balance {
+        self.amounts[self.amounts.len() - 1]
+    }
+
+    /// Output per unit of input actually received.
+    pub fn execution_price(&self) -> f64 {
+        self.amount_out() as f64 / self.amount_in() as f64
+    }
+
+    /// Shortfall of the execution price against the mid price, swap fees included.
+    pub fn slippage(&self) -> f64 {
+        1.0 - self.execution_price() / self.mid_price
+    }
+}
+
+/// Loss of a full-range position against holding, for a price that moved by `price_ratio`
+/// since deposit, fees aside: `2 * sqrt(r) / (1 + r) - 1`.
+pub fn impermanent_loss(price_ratio: f64) -> f64 {
+    2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0
+}
+
+/// Value of a position against holding what was deposited, both priced in token 1 at
+/// `price` (token 1 per token 0). Fees earned count in the position's favour.
+pub fn position_vs_hold(
+    deposited: (Balance, Balance),
+    withdrawable: (Balance, Balance),
+    price: f64,
+) -> f64 {
+    let value =
+        |(amount_0, amount_1): (Balance, Balance)| amount_0 as f64 * price + amount_1 as f64;
+    value(withdrawable) / value(deposited) - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impermanent_loss_matches_known_values() {
+        assert_eq!(impermanent_loss(1.0), 0.0);
+        assert!((impermanent_loss(4.0) + 0.2).abs() < 1e-12);
+        assert_eq!(impermanent_loss(4.0), impermanent_loss(0.25));
+    }
+
+    #[test]
+    fn position_tracks_impermanent_loss() {
+        // 100/100 at price 1, arbitraged to price 4: the pool holds 50/200.
+        let relative = position_vs_hold((100, 100), (50, 200), 4.0);
+        assert!((relative - impermanent_loss(4.0)).abs() < 1e-12);
+    }
+}
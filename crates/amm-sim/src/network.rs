@@ -0,0 +1,397 @@
+//! Pools keyed by token pair, traded through the way `Router` trades them.
+
+use std::collections::BTreeMap;
+
+use ink::primitives::AccountId;
+use uniswap_math::{dynamic_fee, sort_tokens};
+
+use crate::metrics::Fill;
+use crate::{Balance, Pool, Result, SimError};
+
+/// A historical swap to replay: `amount_in` of `path[0]` routed along `path` at `at`
+/// milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trade {
+    pub at: u64,
+    pub path: Vec<AccountId>,
+    pub amount_in: Balance,
+}
+
+/// Every pair the factory would have created, with a shared clock and `fee_to`. Operations
+/// that fail leave the network untouched, as a reverted transaction would.
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    pools: BTreeMap<(AccountId, AccountId), Pool>,
+    fee_to: Option<AccountId>,
+    now: u64,
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The block timestamp in milliseconds.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn advance_time(&mut self, millis: u64) {
+        self.now += millis;
+    }
+
+    /// Moves the clock forward to `at`. Earlier times are ignored.
+    pub fn advance_to(&mut self, at: u64) {
+        self.now = self.now.max(at);
+    }
+
+    pub fn set_fee_to(&mut self, fee_to: Option<AccountId>) {
+        self.fee_to = fee_to;
+        for pool in self.pools.values_mut() {
+            pool.set_fee_to(fee_to);
+        }
+    }
+
+    pub fn create_pair(&mut self, token_a: AccountId, token_b: AccountId) -> Result<()> {
+        let key = sort_tokens(token_a, token_b)?;
+        if self.pools.contains_key(&key) {
+            return Err(SimError::PairExists);
+        }
+        let mut pool = Pool::new(key.0, key.1);
+        pool.set_fee_to(self.fee_to);
+        self.pools.insert(key, pool);
+        Ok(())
+    }
+
+    pub fn pool(&self, token_a: AccountId, token_b: AccountId) -> Option<&Pool> {
+        self.pools.get(&sort_tokens(token_a, token_b).ok()?)
+    }
+
+    pub fn pool_mut(&mut self, token_a: AccountId, token_b: AccountId) -> Result<&mut Pool> {
+        self.pools
+            .get_mut(&sort_tokens(token_a, token_b)?)
+            .ok_or(SimError::PairNotFound)
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &Pool> {
+        self.pools.values()
+    }
+
+    /// Reserves of the `token_a`/`token_b` pair ordered as the arguments.
+    pub fn get_reserves(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<(Balance, Balance)> {
+        let pool = self.pool(token_a, token_b).ok_or(SimError::PairNotFound)?;
+        let (reserve_0, reserve_1, _) = pool.get_reserves();
+        if pool.tokens().0 == token_a {
+            Ok((reserve_0, reserve_1))
+        } else {
+            Ok((reserve_1, reserve_0))
+        }
+    }
+
+    /// Swap fee of the `token_a`/`token_b` pair, as `Pair::fee` reports it to the router.
+    pub fn fee(&self, token_a: AccountId, token_b: AccountId) -> Result<u32> {
+        let pool = self.pool(token_a, token_b).ok_or(SimError::PairNotFound)?;
+        Ok(pool.fee())
+    }
+
+    /// `Router::get_amounts_out`.
+    pub fn get_amounts_out(&self, amount_in: Balance, path: &[AccountId]) -> Result<Vec<Balance>> {
+        if path.len() < 2 {
+            return Err(SimError::InvalidPath);
+        }
+        let mut amounts = Vec::with_capacity(path.len());
+        amounts.push(amount_in);
+        for i in 0..path.len() - 1 {
+            let (reserve_in, reserve_out) = self.get_reserves(path[i], path[i + 1])?;
+            let fee = self.fee(path[i], path[i + 1])?;
+            amounts.push(dynamic_fee::get_amount_out(
+                amounts[i],
+                reserve_in,
+                reserve_out,
+                fee,
+            )?);
+        }
+        Ok(amounts)
+    }
+
+    /// `Router::get_amounts_in`.
+    pub fn get_amounts_in(&self, amount_out: Balance, path: &[AccountId]) -> Result<Vec<Balance>> {
+        if path.len() < 2 {
+            return Err(SimError::InvalidPath);
+        }
+        let mut amounts = vec![0; path.len()];
+        amounts[path.len() - 1] = amount_out;
+        for i in (1..path.len()).rev() {
+            let (reserve_in, reserve_out) = self.get_reserves(path[i - 1], path[i])?;
+            let fee = self.fee(path[i - 1], path[i])?;
+            amounts[i - 1] = dynamic_fee::get_amount_in(amounts[i], reserve_in, reserve_out, fee)?;
+        }
+        Ok(amounts)
+    }
+
+    /// `Router::add_liquidity`: deposits at the current price and mints to `to`. Returns
+    /// the amounts deposited and the shares minted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_liquidity(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        amount_a_desired: Balance,
+        amount_b_desired: Balance,
+        amount_a_min: Balance,
+        amount_b_min: Balance,
+        to: AccountId,
+    ) -> Result<(Balance, Balance, Balance)> {
+        let (reserve_a, reserve_b) = self.get_reserves(token_a, token_b)?;
+        let (amount_a, amount_b) = if reserve_a == 0 && reserve_b == 0 {
+            (amount_a_desired, amount_b_desired)
+        } else {
+            let amount_b_optimal = uniswap_math::quote(amount_a_desired, reserve_a, reserve_b)?;
+            if amount_b_optimal <= amount_b_desired {
+                if amount_b_optimal < amount_b_min {
+                    return Err(SimError::InsufficientBAmount);
+                }
+                (amount_a_desired, amount_b_optimal)
+            } else {
+                let amount_a_optimal = uniswap_math::quote(amount_b_desired, reserve_b, reserve_a)?;
+                if amount_a_optimal > amount_a_desired || amount_a_optimal < amount_a_min {
+                    return Err(SimError::InsufficientAAmount);
+                }
+                (amount_a_optimal, amount_b_desired)
+            }
+        };
+
+        let now = self.now;
+        self.atomically(|network| {
+            let pool = network.pool_mut(token_a, token_b)?;
+            if pool.tokens().0 == token_a {
+                pool.deposit(amount_a, amount_b);
+            } else {
+                pool.deposit(amount_b, amount_a);
+            }
+            let liquidity = pool.mint(to, now)?;
+            Ok((amount_a, amount_b, liquidity))
+        })
+    }
+
+    /// `Router::remove_liquidity` of `owner`'s shares. Returns the amounts paid out.
+    pub fn remove_liquidity(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        liquidity: Balance,
+        amount_a_min: Balance,
+        amount_b_min: Balance,
+        owner: AccountId,
+    ) -> Result<(Balance, Balance)> {
+        let now = self.now;
+        self.atomically(|network| {
+            let pool = network.pool_mut(token_a, token_b)?;
+            let (amount_0, amount_1) = pool.burn(owner, liquidity, now)?;
+            let (amount_a, amount_b) = if pool.tokens().0 == token_a {
+                (amount_0, amount_1)
+            } else {
+                (amount_1, amount_0)
+            };
+            if amount_a < amount_a_min {
+                return Err(SimError::InsufficientAAmount);
+            }
+            if amount_b < amount_b_min {
+                return Err(SimError::InsufficientBAmount);
+            }
+            Ok((amount_a, amount_b))
+        })
+    }
+
+    /// `Router::swap_exact_tokens_for_tokens`. Returns the amount at every hop.
+    pub fn swap_exact_tokens_for_tokens(
+        &mut self,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        path: &[AccountId],
+    ) -> Result<Vec<Balance>> {
+        let amounts = self.get_amounts_out(amount_in, path)?;
+        if amounts[amounts.len() - 1] < amount_out_min {
+            return Err(SimError::InsufficientOutputAmount);
+        }
+        self.atomically(|network| network.swap(&amounts, path))?;
+        Ok(amounts)
+    }
+
+    /// `Router::swap_tokens_for_exact_tokens`. Returns the amount at every hop.
+    pub fn swap_tokens_for_exact_tokens(
+        &mut self,
+        amount_out: Balance,
+        amount_in_max: Balance,
+        path: &[AccountId],
+    ) -> Result<Vec<Balance>> {
+        let amounts = self.get_amounts_in(amount_out, path)?;
+        if amounts[0] > amount_in_max {
+            return Err(SimError::ExcessiveInputAmount);
+        }
+        self.atomically(|network| network.swap(&amounts, path))?;
+        Ok(amounts)
+    }
+
+    /// Replays `trades` in order, advancing the clock to each trade's time. Trades the
+    /// router would have rejected are reported and skipped.
+    pub fn replay<'a>(&mut self, trades: impl IntoIterator<Item = &'a Trade>) -> Vec<Result<Fill>> {
+        trades
+            .into_iter()
+            .map(|trade| {
+                self.advance_to(trade.at);
+                let mid_price = self.mid_price(&trade.path)?;
+                let amounts = self.swap_exact_tokens_for_tokens(trade.amount_in, 0, &trade.path)?;
+                Ok(Fill {
+                    at: self.now,
+                    amounts,
+                    mid_price,
+                })
+            })
+            .collect()
+    }
+
+    /// Fee-free marginal price of `path[0]` in the last token of `path`.
+    pub fn mid_price(&self, path: &[AccountId]) -> Result<f64> {
+        if path.len() < 2 {
+            return Err(SimError::InvalidPath);
+        }
+        path.windows(2).try_fold(1.0, |price, hop| {
+            let (reserve_in, reserve_out) = self.get_reserves(hop[0], hop[1])?;
+            if reserve_in == 0 {
+                return Err(SimError::Math(
+                    uniswap_math::MathError::InsufficientLiquidity,
+                ));
+            }
+            Ok(price * reserve_out as f64 / reserve_in as f64)
+        })
+    }
+
+    /// Sends `amounts[0]` into the first pool and each hop's output into the next.
+    fn swap(&mut self, amounts: &[Balance], path: &[AccountId]) -> Result<()> {
+        let now = self.now;
+        for (i, hop) in path.windows(2).enumerate() {
+            let pool = self.pool_mut(hop[0], hop[1])?;
+            let (amount_in, amount_out) = (amounts[i], amounts[i + 1]);
+            if pool.tokens().0 == hop[0] {
+                pool.deposit(amount_in, 0);
+                pool.swap(0, amount_out, now)?;
+            } else {
+                pool.deposit(0, amount_in);
+                pool.swap(amount_out, 0, now)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `f`, restoring the pools if it fails.
+    fn atomically<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let snapshot = self.pools.clone();
+        let result = f(self);
+        if result.is_err() {
+            self.pools = snapshot;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: [u8; 32] = [0x1; 32];
+    const A: [u8; 32] = [0xa; 32];
+    const B: [u8; 32] = [0xb; 32];
+    const C: [u8; 32] = [0xc; 32];
+
+    fn network() -> Network {
+        let mut network = Network::new();
+        for (token_a, token_b) in [(A, B), (B, C)] {
+            network.create_pair(token_a.into(), token_b.into()).unwrap();
+            network
+                .add_liquidity(
+                    token_a.into(),
+                    token_b.into(),
+                    1_000_000,
+                    2_000_000,
+                    0,
+                    0,
+                    ALICE.into(),
+                )
+                .unwrap();
+        }
+        network
+    }
+
+    #[test]
+    fn multi_hop_swap_matches_the_quote() {
+        let mut network = network();
+        let path = [A.into(), B.into(), C.into()];
+        let quoted = network.get_amounts_out(10_000, &path).unwrap();
+
+        let amounts = network
+            .swap_exact_tokens_for_tokens(10_000, 0, &path)
+            .unwrap();
+
+        assert_eq!(amounts, quoted);
+        assert_eq!(
+            network.get_reserves(A.into(), B.into()).unwrap(),
+            (1_010_000, 2_000_000 - amounts[1])
+        );
+        assert_eq!(
+            network.get_reserves(B.into(), C.into()).unwrap(),
+            (1_000_000 + amounts[1], 2_000_000 - amounts[2])
+        );
+    }
+
+    #[test]
+    fn failed_operations_leave_the_network_untouched() {
+        let mut network = network();
+        let path = [A.into(), B.into(), C.into()];
+        let before = network.get_reserves(A.into(), B.into()).unwrap();
+
+        assert_eq!(
+            network.swap_exact_tokens_for_tokens(10_000, u128::MAX, &path),
+            Err(SimError::InsufficientOutputAmount)
+        );
+        assert_eq!(
+            network.remove_liquidity(A.into(), B.into(), 1_000, u128::MAX, 0, ALICE.into()),
+            Err(SimError::InsufficientAAmount)
+        );
+        assert_eq!(network.get_reserves(A.into(), B.into()).unwrap(), before);
+        assert_eq!(
+            network.create_pair(B.into(), A.into()),
+            Err(SimError::PairExists)
+        );
+    }
+
+    #[test]
+    fn replay_reports_fills_and_rejections() {
+        let mut network = network();
+        let trades = [
+            Trade {
+                at: 6_000,
+                path: vec![A.into(), B.into()],
+                amount_in: 10_000,
+            },
+            Trade {
+                at: 12_000,
+                path: vec![A.into(), C.into()],
+                amount_in: 10_000,
+            },
+        ];
+
+        let fills = network.replay(&trades);
+
+        let fill = fills[0].as_ref().unwrap();
+        assert_eq!(fill.at, 6_000);
+        assert_eq!(fill.mid_price, 2.0);
+        assert!(fill.slippage() > 0.003);
+        assert_eq!(fills[1], Err(SimError::PairNotFound));
+        assert_eq!(network.now(), 12_000);
+    }
+}
@@ -0,0 +1,493 @@
+//! A single pair, modelled message for message on `Pair`.
+
+use std::collections::BTreeMap;
+
+use ink::primitives::AccountId;
+use uniswap_math::dynamic_fee;
+use uniswap_math::pair::{self as math, MINIMUM_LIQUIDITY};
+
+use crate::{Balance, Result, SimError};
+
+/// `Pair`'s dynamic fee: the bounds the factory set and the volatility of the oracle,
+/// measured window by window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DynamicFee {
+    pub min_fee: u32,
+    pub max_fee: u32,
+    /// Smoothed change of the average price from window to window, in millionths.
+    pub volatility: u32,
+    /// Timestamp the current window opened at, 0 until the first one opens.
+    pub window_start: u128,
+    /// `price_0_cumulative_last` as of `window_start`.
+    pub price_0_cumulative: u128,
+    /// Average price of token 0 over the last closed window, 0 until one closes.
+    pub price_0_average: u128,
+}
+
+/// State of one `token_0`/`token_1` pair. Tokens sent to the pair are credited with
+/// `deposit` and picked up by the next `mint`, `swap` or `sync`, as on chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pool {
+    token_0: AccountId,
+    token_1: AccountId,
+    /// Tokens the pair holds, reserves plus anything deposited since the last update.
+    balance_0: Balance,
+    balance_1: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    lp_balances: BTreeMap<AccountId, Balance>,
+    total_supply: Balance,
+    /// `sqrt(reserve_0 * reserve_1)` as of the last liquidity event, while the protocol fee
+    /// is on.
+    k_last: Balance,
+    price_0_cumulative_last: u128,
+    price_1_cumulative_last: u128,
+    block_timestamp_last: u128,
+    /// The factory's `fee_to`.
+    fee_to: Option<AccountId>,
+    dynamic_fee: Option<DynamicFee>,
+}
+
+impl Pool {
+    /// An empty pair. The tokens must be sorted, see `uniswap_math::sort_tokens`.
+    pub fn new(token_0: AccountId, token_1: AccountId) -> Self {
+        assert!(token_0 < token_1, "tokens must be sorted");
+        Self {
+            token_0,
+            token_1,
+            balance_0: 0,
+            balance_1: 0,
+            reserve_0: 0,
+            reserve_1: 0,
+            lp_balances: BTreeMap::new(),
+            total_supply: 0,
+            k_last: 0,
+            price_0_cumulative_last: 0,
+            price_1_cumulative_last: 0,
+            block_timestamp_last: 0,
+            fee_to: None,
+            dynamic_fee: None,
+        }
+    }
+
+    pub fn tokens(&self) -> (AccountId, AccountId) {
+        (self.token_0, self.token_1)
+    }
+
+    /// Reserves and the timestamp of their last update, as `Pair::get_reserves` returns.
+    pub fn get_reserves(&self) -> (Balance, Balance, u128) {
+        (self.reserve_0, self.reserve_1, self.block_timestamp_last)
+    }
+
+    pub fn balances(&self) -> (Balance, Balance) {
+        (self.balance_0, self.balance_1)
+    }
+
+    pub fn total_supply(&self) -> Balance {
+        self.total_supply
+    }
+
+    pub fn lp_balance(&self, owner: AccountId) -> Balance {
+        self.lp_balances.get(&owner).copied().unwrap_or(0)
+    }
+
+    pub fn k_last(&self) -> Balance {
+        self.k_last
+    }
+
+    pub fn price_0_cumulative_last(&self) -> u128 {
+        self.price_0_cumulative_last
+    }
+
+    pub fn price_1_cumulative_last(&self) -> u128 {
+        self.price_1_cumulative_last
+    }
+
+    /// Turns the protocol fee on for `fee_to`, or off with `None`.
+    pub fn set_fee_to(&mut self, fee_to: Option<AccountId>) {
+        self.fee_to = fee_to;
+    }
+
+    /// `Pair::fee`: the swap fee in millionths, following the volatility while the dynamic
+    /// fee is on.
+    pub fn fee(&self) -> u32 {
+        match self.dynamic_fee {
+            Some(state) => dynamic_fee::fee(state.volatility, state.min_fee, state.max_fee),
+            None => {
+                (uniswap_math::FEE_NUMERATOR * 1_000_000 / uniswap_math::FEE_DENOMINATOR) as u32
+            }
+        }
+    }
+
+    pub fn dynamic_fee(&self) -> Option<DynamicFee> {
+        self.dynamic_fee
+    }
+
+    /// `Pair::set_dynamic_fee`: bounds the fee to `(min_fee, max_fee)`, or fixes it again
+    /// with `None`. The volatility measured so far is kept.
+    pub fn set_dynamic_fee(&mut self, bounds: Option<(u32, u32)>) -> Result<()> {
+        self.dynamic_fee = match bounds {
+            Some((min_fee, max_fee)) => {
+                if !dynamic_fee::is_valid_bounds(min_fee, max_fee) {
+                    return Err(SimError::InvalidFee);
+                }
+                Some(DynamicFee {
+                    min_fee,
+                    max_fee,
+                    ..self.dynamic_fee.unwrap_or_default()
+                })
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Tokens `liquidity` LP shares are currently worth, ignoring the protocol fee not yet
+    /// minted.
+    pub fn underlying(&self, liquidity: Balance) -> Result<(Balance, Balance)> {
+        Ok((
+            math::mul_div(liquidity, self.reserve_0, self.total_supply)?,
+            math::mul_div(liquidity, self.reserve_1, self.total_supply)?,
+        ))
+    }
+
+    /// Credits tokens transferred to the pair.
+    pub fn deposit(&mut self, amount_0: Balance, amount_1: Balance) {
+        self.balance_0 += amount_0;
+        self.balance_1 += amount_1;
+    }
+
+    /// `Pair::mint`: LP shares for everything deposited since the last update.
+    pub fn mint(&mut self, to: AccountId, now: u64) -> Result<Balance> {
+        let (reserve_0, reserve_1) = (self.reserve_0, self.reserve_1);
+        let amount_0 = self.balance_0.saturating_sub(reserve_0);
+        let amount_1 = self.balance_1.saturating_sub(reserve_1);
+
+        let fee = self.protocol_fee(reserve_0, reserve_1)?;
+        let total_supply = self.total_supply + fee;
+        let liquidity =
+            math::liquidity_minted(amount_0, amount_1, reserve_0, reserve_1, total_supply)?;
+
+        self.apply_fee(fee);
+        if total_supply == 0 {
+            self.lp_mint(AccountId::from([0x0; 32]), MINIMUM_LIQUIDITY);
+        }
+        self.lp_mint(to, liquidity);
+        self.update(reserve_0, reserve_1, now);
+        self.after_liquidity_event();
+        Ok(liquidity)
+    }
+
+    /// `Pair::burn` of `liquidity` shares `owner` sent to the pair. The tokens leave the
+    /// pool.
+    pub fn burn(
+        &mut self,
+        owner: AccountId,
+        liquidity: Balance,
+        now: u64,
+    ) -> Result<(Balance, Balance)> {
+        if self.lp_balance(owner) < liquidity {
+            return Err(SimError::InsufficientLpBalance);
+        }
+        let (reserve_0, reserve_1) = (self.reserve_0, self.reserve_1);
+
+        let fee = self.protocol_fee(reserve_0, reserve_1)?;
+        let (amount_0, amount_1) = math::amounts_burned(
+            liquidity,
+            self.balance_0,
+            self.balance_1,
+            self.total_supply + fee,
+        )?;
+
+        self.apply_fee(fee);
+        self.lp_burn(owner, liquidity);
+        self.balance_0 -= amount_0;
+        self.balance_1 -= amount_1;
+        self.update(reserve_0, reserve_1, now);
+        self.after_liquidity_event();
+        Ok((amount_0, amount_1))
+    }
+
+    /// `Pair::swap`: pays out the requested amounts against what was deposited beforehand.
+    /// Returns the amounts taken in.
+    pub fn swap(
+        &mut self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        now: u64,
+    ) -> Result<(Balance, Balance)> {
+        if amount_0_out == 0 && amount_1_out == 0 {
+            return Err(SimError::InsufficientOutputAmount);
+        }
+        let (reserve_0, reserve_1) = (self.reserve_0, self.reserve_1);
+        if amount_0_out >= reserve_0 || amount_1_out >= reserve_1 {
+            return Err(SimError::InsufficientLiquidity);
+        }
+
+        let balance_0 = self.balance_0 - amount_0_out;
+        let balance_1 = self.balance_1 - amount_1_out;
+        let amount_0_in = balance_0.saturating_sub(reserve_0 - amount_0_out);
+        let amount_1_in = balance_1.saturating_sub(reserve_1 - amount_1_out);
+        if amount_0_in == 0 && amount_1_in == 0 {
+            return Err(SimError::InsufficientInputAmount);
+        }
+        if !dynamic_fee::k_holds(
+            balance_0,
+            balance_1,
+            amount_0_in,
+            amount_1_in,
+            reserve_0,
+            reserve_1,
+            self.fee(),
+        ) {
+            return Err(SimError::K);
+        }
+
+        self.balance_0 = balance_0;
+        self.balance_1 = balance_1;
+        self.update(reserve_0, reserve_1, now);
+        Ok((amount_0_in, amount_1_in))
+    }
+
+    /// Deposits `amount_in` of one token and swaps it for as much of the other as K allows,
+    /// like a router hop. Returns the amount out.
+    pub fn swap_exact_in(
+        &mut self,
+        zero_for_one: bool,
+        amount_in: Balance,
+        now: u64,
+    ) -> Result<Balance> {
+        let (reserve_in, reserve_out) = if zero_for_one {
+            (self.reserve_0, self.reserve_1)
+        } else {
+            (self.reserve_1, self.reserve_0)
+        };
+        let amount_out =
+            dynamic_fee::get_amount_out(amount_in, reserve_in, reserve_out, self.fee())?;
+        let snapshot = self.clone();
+        let result = if zero_for_one {
+            self.deposit(amount_in, 0);
+            self.swap(0, amount_out, now)
+        } else {
+            self.deposit(0, amount_in);
+            self.swap(amount_out, 0, now)
+        };
+        if let Err(error) = result {
+            *self = snapshot;
+            return Err(error);
+        }
+        Ok(amount_out)
+    }
+
+    /// `Pair::skim`: removes and returns whatever the pair holds above its reserves.
+    pub fn skim(&mut self) -> (Balance, Balance) {
+        let excess_0 = self.balance_0.saturating_sub(self.reserve_0);
+        let excess_1 = self.balance_1.saturating_sub(self.reserve_1);
+        self.balance_0 -= excess_0;
+        self.balance_1 -= excess_1;
+        (excess_0, excess_1)
+    }
+
+    /// `Pair::sync`: sets the reserves to the balances.
+    pub fn sync(&mut self, now: u64) {
+        self.update(self.reserve_0, self.reserve_1, now);
+    }
+
+    /// Accumulates the prices of the reserves being replaced, then moves the balances into
+    /// the reserves.
+    fn update(&mut self, reserve_0: Balance, reserve_1: Balance, now: u64) {
+        let block_timestamp = now as u128;
+        let time_elapsed = block_timestamp.saturating_sub(self.block_timestamp_last);
+        if time_elapsed > 0 && reserve_0 != 0 && reserve_1 != 0 {
+            self.price_0_cumulative_last = self
+                .price_0_cumulative_last
+                .wrapping_add(math::encode_price(reserve_0, reserve_1).wrapping_mul(time_elapsed));
+            self.price_1_cumulative_last = self
+                .price_1_cumulative_last
+                .wrapping_add(math::encode_price(reserve_1, reserve_0).wrapping_mul(time_elapsed));
+        }
+        self.reserve_0 = self.balance_0;
+        self.reserve_1 = self.balance_1;
+        self.block_timestamp_last = block_timestamp;
+        self.observe(block_timestamp);
+    }
+
+    /// `Pair::observe`: closes the dynamic fee's window once it lasted
+    /// `dynamic_fee::WINDOW`, folding its average price into the volatility.
+    fn observe(&mut self, block_timestamp: u128) {
+        let Some(mut state) = self.dynamic_fee else {
+            return;
+        };
+        let elapsed = block_timestamp.saturating_sub(state.window_start);
+        if state.window_start != 0 {
+            if elapsed < dynamic_fee::WINDOW {
+                return;
+            }
+            let average = self
+                .price_0_cumulative_last
+                .wrapping_sub(state.price_0_cumulative)
+                / elapsed;
+            let change = dynamic_fee::price_change(state.price_0_average, average);
+            state.volatility = dynamic_fee::next_volatility(
+                state.volatility,
+                change,
+                elapsed / dynamic_fee::WINDOW,
+            );
+            state.price_0_average = average;
+        }
+        state.window_start = block_timestamp;
+        state.price_0_cumulative = self.price_0_cumulative_last;
+        self.dynamic_fee = Some(state);
+    }
+
+    /// LP shares `Pair::mint_fee` would mint to `fee_to` before a liquidity event.
+    fn protocol_fee(&self, reserve_0: Balance, reserve_1: Balance) -> Result<Balance> {
+        if self.fee_to.is_none() || self.k_last == 0 {
+            return Ok(0);
+        }
+        let root_k = math::sqrt_product(reserve_0, reserve_1);
+        Ok(math::protocol_fee_liquidity(
+            self.total_supply,
+            root_k,
+            self.k_last,
+        )?)
+    }
+
+    fn apply_fee(&mut self, fee: Balance) {
+        match self.fee_to {
+            Some(fee_to) if fee > 0 => self.lp_mint(fee_to, fee),
+            Some(_) => {}
+            None => self.k_last = 0,
+        }
+    }
+
+    fn after_liquidity_event(&mut self) {
+        if self.fee_to.is_some() {
+            self.k_last = math::sqrt_product(self.reserve_0, self.reserve_1);
+        }
+    }
+
+    fn lp_mint(&mut self, to: AccountId, value: Balance) {
+        *self.lp_balances.entry(to).or_default() += value;
+        self.total_supply += value;
+    }
+
+    fn lp_burn(&mut self, from: AccountId, value: Balance) {
+        let balance = self.lp_balances.entry(from).or_default();
+        *balance -= value;
+        if *balance == 0 {
+            self.lp_balances.remove(&from);
+        }
+        self.total_supply -= value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: [u8; 32] = [0x1; 32];
+    const FEE_TO: [u8; 32] = [0x2; 32];
+
+    fn pool(amount_0: Balance, amount_1: Balance) -> Pool {
+        let mut pool = Pool::new(AccountId::from([0x13; 32]), AccountId::from([0x14; 32]));
+        pool.deposit(amount_0, amount_1);
+        pool.mint(ALICE.into(), 0).unwrap();
+        pool
+    }
+
+    #[test]
+    fn first_mint_locks_minimum_liquidity() {
+        let pool = pool(1_000_000, 4_000_000);
+        assert_eq!(pool.lp_balance(ALICE.into()), 2_000_000 - MINIMUM_LIQUIDITY);
+        assert_eq!(
+            pool.lp_balance(AccountId::from([0x0; 32])),
+            MINIMUM_LIQUIDITY
+        );
+        assert_eq!(pool.get_reserves(), (1_000_000, 4_000_000, 0));
+    }
+
+    #[test]
+    fn swap_enforces_k_and_rolls_back() {
+        let mut pool = pool(1_000_000, 4_000_000);
+        let amount_out = uniswap_math::get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+
+        pool.deposit(10_000, 0);
+        assert_eq!(pool.swap(0, amount_out + 1, 1), Err(SimError::K));
+        assert_eq!(pool.swap(0, amount_out, 1), Ok((10_000, 0)));
+        assert_eq!(pool.get_reserves(), (1_010_000, 4_000_000 - amount_out, 1));
+
+        let before = pool.clone();
+        assert_eq!(
+            pool.swap_exact_in(true, 0, 2),
+            Err(SimError::Math(
+                uniswap_math::MathError::InsufficientInputAmount
+            ))
+        );
+        assert_eq!(pool, before);
+    }
+
+    #[test]
+    fn dynamic_fee_follows_volatility() {
+        let mut pool = pool(1_000_000, 1_000_000);
+        assert_eq!(
+            pool.set_dynamic_fee(Some((10_000, 1_000))),
+            Err(SimError::InvalidFee)
+        );
+        pool.set_dynamic_fee(Some((1_000, 50_000))).unwrap();
+        pool.sync(1_000);
+        assert_eq!(pool.fee(), 1_000);
+
+        // A steady window sets the baseline average, then the price drops 1/11.
+        pool.sync(1_000 + dynamic_fee::WINDOW as u64);
+        pool.deposit(100_000, 0);
+        pool.sync(1_001 + dynamic_fee::WINDOW as u64);
+        pool.sync(1_001 + 2 * dynamic_fee::WINDOW as u64);
+        let volatility = pool.dynamic_fee().unwrap().volatility;
+        assert!((45_000..45_500).contains(&volatility));
+        assert_eq!(pool.fee(), 1_000 + volatility / 2);
+
+        let fee = pool.fee();
+        let (reserve_0, reserve_1, now) = pool.get_reserves();
+        let amount_out = dynamic_fee::get_amount_out(10_000, reserve_0, reserve_1, fee).unwrap();
+        assert_eq!(pool.swap_exact_in(true, 10_000, now as u64), Ok(amount_out));
+
+        pool.set_dynamic_fee(None).unwrap();
+        assert_eq!(pool.fee(), 3_000);
+    }
+
+    #[test]
+    fn prices_accumulate_between_updates() {
+        let mut pool = pool(1_000_000, 4_000_000);
+        pool.sync(12_000);
+        assert_eq!(
+            pool.price_0_cumulative_last(),
+            math::encode_price(1_000_000, 4_000_000) * 12_000
+        );
+        assert_eq!(
+            pool.price_1_cumulative_last(),
+            math::encode_price(4_000_000, 1_000_000) * 12_000
+        );
+    }
+
+    #[test]
+    fn protocol_fee_is_minted_on_the_next_liquidity_event() {
+        let mut pool = Pool::new(AccountId::from([0x13; 32]), AccountId::from([0x14; 32]));
+        pool.set_fee_to(Some(FEE_TO.into()));
+        pool.deposit(1_000_000_000, 1_000_000_000);
+        pool.mint(ALICE.into(), 0).unwrap();
+        for _ in 0..10 {
+            pool.swap_exact_in(true, 10_000_000, 0).unwrap();
+            pool.swap_exact_in(false, 10_000_000, 0).unwrap();
+        }
+        assert_eq!(pool.lp_balance(FEE_TO.into()), 0);
+
+        let liquidity = pool.lp_balance(ALICE.into());
+        pool.burn(ALICE.into(), liquidity, 0).unwrap();
+        assert!(pool.lp_balance(FEE_TO.into()) > 0);
+        assert_eq!(
+            pool.k_last(),
+            math::sqrt_product(pool.reserve_0, pool.reserve_1)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Runs a manifest against a node and records what it deployed.
+
+use std::collections::BTreeMap;
+
+use ink::primitives::AccountId;
+use serde::Serialize;
+use uniswap_client::api::code_hash;
+use uniswap_client::{Balance, Client};
+
+use crate::manifest::Manifest;
+use crate::{to_hex, Result};
+
+/// The deployment JSON. Addresses and code hashes are `0x` hex.
+#[derive(Debug, Serialize)]
+pub struct Deployment {
+    pub url: String,
+    pub deployer: String,
+    pub code_hashes: CodeHashes,
+    pub factory: String,
+    pub router: String,
+    pub wnative: String,
+    /// Token addresses by symbol.
+    pub tokens: BTreeMap<String, String>,
+    pub pairs: Vec<PairDeployment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CodeHashes {
+    pub core: String,
+    pub pair: String,
+    pub lp_token: String,
+    pub psp22token: String,
+    pub router: String,
+    pub wnative: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairDeployment {
+    pub tokens: [String; 2],
+    pub address: String,
+    pub lp_token: String,
+    /// LP tokens minted to the deployer when the pair was seeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<Balance>,
+}
+
+/// Deploys the tokens, the factory and its router, and the pairs of `manifest`, in that
+/// order, reporting each step on stderr.
+pub async fn deploy(client: &Client, url: &str, manifest: &Manifest) -> Result<Deployment> {
+    let deployer = client.account_id();
+    let artifacts = client.artifacts();
+
+    let mut tokens = BTreeMap::<&str, AccountId>::new();
+    for token in &manifest.tokens {
+        let address = match token.address {
+            Some(address) => address,
+            None => {
+                client
+                    .deploy_token(
+                        token.supply,
+                        token.name.clone(),
+                        Some(token.symbol.clone()),
+                        token.decimals,
+                    )
+                    .await?
+            }
+        };
+        eprintln!("token {} at {}", token.symbol, to_hex(address.as_ref()));
+        tokens.insert(&token.symbol, address);
+    }
+
+    // The deployer administers fees until the factory is set up, then proposes the
+    // manifest's setter, who takes over by accepting.
+    let factory = client.factory(client.deploy_factory(deployer).await?);
+    eprintln!("factory at {}", to_hex(factory.address.as_ref()));
+    if let Some(fee_to) = manifest.factory.fee_to {
+        factory.set_fee_to(fee_to).await?;
+        eprintln!("fee to {}", to_hex(fee_to.as_ref()));
+    }
+    if let Some(fee_to_setter) = manifest.factory.fee_to_setter.filter(|s| *s != deployer) {
+        factory.propose_fee_to_setter(fee_to_setter).await?;
+        eprintln!(
+            "proposed {} as fee-to setter; it takes over with `accept_fee_to_setter`",
+            to_hex(fee_to_setter.as_ref())
+        );
+    }
+    let router = client.router(client.deploy_router(factory.address).await?);
+    let wnative = router.wnative().await?;
+    eprintln!("router at {}", to_hex(router.address.as_ref()));
+
+    let mut pairs = Vec::new();
+    for spec in &manifest.pairs {
+        let [a, b] = &spec.tokens;
+        let (token_a, token_b) = (tokens[a.as_str()], tokens[b.as_str()]);
+        let pair = client.pair(factory.create_pair(token_a, token_b).await?.value);
+        let lp_token = pair.lp_token().await?;
+        eprintln!("pair {a}/{b} at {}", to_hex(pair.address.as_ref()));
+
+        let liquidity = match spec.liquidity {
+            Some([amount_a, amount_b]) => {
+                client
+                    .token(token_a)
+                    .approve(router.address, amount_a)
+                    .await?;
+                client
+                    .token(token_b)
+                    .approve(router.address, amount_b)
+                    .await?;
+                // Both payments and the mint in one call, at exactly the manifest's amounts:
+                // if anything got into the pair first, seeding fails instead of taking its
+                // price.
+                let (_, _, liquidity) = router
+                    .add_liquidity(
+                        token_a,
+                        token_b,
+                        amount_a,
+                        amount_b,
+                        amount_a,
+                        amount_b,
+                        deployer,
+                        u64::MAX,
+                    )
+                    .await?
+                    .value;
+                eprintln!("seeded {a}/{b} with {amount_a} and {amount_b} for {liquidity} LP");
+                Some(liquidity)
+            }
+            None => None,
+        };
+        pairs.push(PairDeployment {
+            tokens: spec.tokens.clone(),
+            address: to_hex(pair.address.as_ref()),
+            lp_token: to_hex(lp_token.as_ref()),
+            liquidity,
+        });
+    }
+
+    Ok(Deployment {
+        url: url.to_owned(),
+        deployer: to_hex(deployer.as_ref()),
+        code_hashes: CodeHashes {
+            core: to_hex(code_hash(artifacts.core.wasm()?).as_ref()),
+            pair: to_hex(factory.pair_code_hash().await?.as_ref()),
+            lp_token: to_hex(code_hash(artifacts.lp_token.wasm()?).as_ref()),
+            psp22token: to_hex(code_hash(artifacts.psp22token.wasm()?).as_ref()),
+            router: to_hex(code_hash(artifacts.router.wasm()?).as_ref()),
+            wnative: to_hex(code_hash(artifacts.wnative.wasm()?).as_ref()),
+        },
+        factory: to_hex(factory.address.as_ref()),
+        router: to_hex(router.address.as_ref()),
+        wnative: to_hex(wnative.as_ref()),
+        tokens: tokens
+            .into_iter()
+            .map(|(symbol, address)| (symbol.to_owned(), to_hex(address.as_ref())))
+            .collect(),
+        pairs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deployment_json_layout() {
+        let deployment = Deployment {
+            url: "ws://127.0.0.1:9944".into(),
+            deployer: to_hex(&[1; 32]),
+            code_hashes: CodeHashes {
+                core: to_hex(&[2; 32]),
+                pair: to_hex(&[3; 32]),
+                lp_token: to_hex(&[4; 32]),
+                psp22token: to_hex(&[11; 32]),
+                router: to_hex(&[12; 32]),
+                wnative: to_hex(&[13; 32]),
+            },
+            factory: to_hex(&[5; 32]),
+            router: to_hex(&[14; 32]),
+            wnative: to_hex(&[15; 32]),
+            tokens: [("WETH".to_owned(), to_hex(&[6; 32]))].into(),
+            pairs: vec![
+                PairDeployment {
+                    tokens: ["USDT".into(), "WETH".into()],
+                    address: to_hex(&[7; 32]),
+                    lp_token: to_hex(&[8; 32]),
+                    liquidity: Some(u128::MAX),
+                },
+                PairDeployment {
+                    tokens: ["DAI".into(), "WETH".into()],
+                    address: to_hex(&[9; 32]),
+                    lp_token: to_hex(&[10; 32]),
+                    liquidity: None,
+                },
+            ],
+        };
+
+        let json = serde_json::to_string(&deployment).unwrap();
+
+        assert!(json.contains(&format!(r#""code_hashes":{{"core":"{}""#, to_hex(&[2; 32]))));
+        assert!(json.contains(&format!(r#""tokens":{{"WETH":"{}"}}"#, to_hex(&[6; 32]))));
+        // At full precision, which a JSON number parsed as a double would not keep.
+        assert!(json.contains(&format!(r#""liquidity":{}}}"#, u128::MAX)));
+        assert_eq!(json.matches("liquidity").count(), 1);
+    }
+}
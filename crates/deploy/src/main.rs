@@ -0,0 +1,145 @@
+//! `uniswap-deploy` uploads the AMM's code, deploys tokens, the factory and a router,
+//! creates and seeds pairs as a TOML manifest describes, and writes the addresses to a JSON
+//! file.
+
+mod deployment;
+mod manifest;
+
+use std::fmt;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::Parser;
+use ink::primitives::AccountId;
+use subxt_signer::sr25519::Keypair;
+use subxt_signer::SecretUri;
+use uniswap_client::{Artifacts, Client};
+
+use manifest::Manifest;
+
+#[derive(Parser)]
+#[command(about = "Deploys the AMM contracts described by a TOML manifest")]
+struct Cli {
+    manifest: PathBuf,
+    /// Node to deploy to; overrides the manifest's `url`.
+    #[arg(long)]
+    url: Option<String>,
+    /// Secret URI of the deployer, e.g. `//Alice`; overrides the manifest's `signer`.
+    #[arg(long)]
+    signer: Option<String>,
+    /// Directory of the `cargo contract build` bundles; overrides the manifest's `artifacts`.
+    #[arg(long)]
+    artifacts: Option<PathBuf>,
+    /// Where to write the deployment JSON.
+    #[arg(long, default_value = "deployment.json")]
+    out: PathBuf,
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(std::io::Error),
+    Manifest(String),
+    Signer(String),
+    Client(uniswap_client::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Manifest(reason) => write!(f, "invalid manifest: {reason}"),
+            Error::Signer(reason) => write!(f, "invalid signer: {reason}"),
+            Error::Client(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<uniswap_client::Error> for Error {
+    fn from(error: uniswap_client::Error) -> Self {
+        Error::Client(error)
+    }
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+fn to_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{digits}")
+}
+
+fn parse_account(hex: &str) -> Option<AccountId> {
+    let digits = hex.strip_prefix("0x")?;
+    if digits.len() != 64 || !digits.is_ascii() {
+        return None;
+    }
+    let mut account = [0u8; 32];
+    for (i, byte) in account.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(AccountId::from(account))
+}
+
+fn keypair(uri: &str) -> Result<Keypair> {
+    let uri = SecretUri::from_str(uri).map_err(|err| Error::Signer(err.to_string()))?;
+    Keypair::from_uri(&uri).map_err(|err| Error::Signer(err.to_string()))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let manifest = Manifest::parse(&std::fs::read_to_string(&cli.manifest)?)?;
+    let url = cli
+        .url
+        .or(manifest.url.clone())
+        .unwrap_or_else(|| "ws://127.0.0.1:9944".into());
+    let signer = keypair(
+        cli.signer
+            .as_deref()
+            .or(manifest.signer.as_deref())
+            .unwrap_or("//Alice"),
+    )?;
+    let artifacts = cli
+        .artifacts
+        .or(manifest.artifacts.clone())
+        .unwrap_or_else(|| "target/ink".into());
+
+    let client = Client::connect(&url, signer, Artifacts::load(artifacts)?).await?;
+    let deployment = deployment::deploy(&client, &url, &manifest).await?;
+
+    let json =
+        serde_json::to_string_pretty(&deployment).expect("the deployment serializes to JSON");
+    std::fs::write(&cli.out, json + "\n")?;
+    eprintln!("wrote {}", cli.out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dev_signers_resolve() {
+        let alice = keypair("//Alice").unwrap();
+        assert_eq!(
+            alice.public_key().0,
+            subxt_signer::sr25519::dev::alice().public_key().0
+        );
+        assert!(matches!(keypair("not a uri"), Err(Error::Signer(_))));
+    }
+}
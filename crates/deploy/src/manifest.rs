@@ -0,0 +1,228 @@
+//! The TOML manifest describing what to deploy. See `deploy.example.toml`.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use ink::primitives::AccountId;
+use serde::{Deserialize, Deserializer};
+use uniswap_client::Balance;
+
+use crate::{parse_account, Error, Result};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub url: Option<String>,
+    /// Secret URI of the deployer, e.g. `//Alice`.
+    pub signer: Option<String>,
+    pub artifacts: Option<PathBuf>,
+    #[serde(default)]
+    pub factory: FactorySpec,
+    #[serde(default)]
+    pub tokens: Vec<TokenSpec>,
+    #[serde(default)]
+    pub pairs: Vec<PairSpec>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FactorySpec {
+    /// Defaults to the deployer. Another account is proposed once `fee_to` is set, and
+    /// takes over by calling `accept_fee_to_setter`.
+    #[serde(default, deserialize_with = "account")]
+    pub fee_to_setter: Option<AccountId>,
+    /// Turns the protocol fee on.
+    #[serde(default, deserialize_with = "account")]
+    pub fee_to: Option<AccountId>,
+}
+
+/// A `psp22token` to deploy, or an existing token when `address` is set.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenSpec {
+    /// How pairs refer to the token, and its PSP22 symbol.
+    pub symbol: String,
+    pub name: Option<String>,
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    /// Minted to the deployer.
+    #[serde(default, deserialize_with = "amount")]
+    pub supply: Balance,
+    #[serde(default, deserialize_with = "account")]
+    pub address: Option<AccountId>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PairSpec {
+    /// Symbols of the two tokens.
+    pub tokens: [String; 2],
+    /// Amounts of each token, in `tokens` order, to seed the pair with from the deployer's
+    /// balance through the router. The LP tokens go to the deployer.
+    #[serde(default, deserialize_with = "liquidity")]
+    pub liquidity: Option<[Balance; 2]>,
+}
+
+fn default_decimals() -> u8 {
+    18
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Self> {
+        let manifest: Self =
+            toml::from_str(source).map_err(|err| Error::Manifest(err.to_string()))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut symbols = BTreeSet::new();
+        for token in &self.tokens {
+            if !symbols.insert(token.symbol.as_str()) {
+                return Err(Error::Manifest(format!(
+                    "token `{}` is listed twice",
+                    token.symbol
+                )));
+            }
+            if token.address.is_some() && (token.supply != 0 || token.name.is_some()) {
+                return Err(Error::Manifest(format!(
+                    "token `{}` has an address, so it cannot have a name or supply",
+                    token.symbol
+                )));
+            }
+        }
+
+        let mut pairs = BTreeSet::new();
+        for pair in &self.pairs {
+            let [a, b] = &pair.tokens;
+            if let Some(unknown) = [a, b]
+                .into_iter()
+                .find(|symbol| !symbols.contains(symbol.as_str()))
+            {
+                return Err(Error::Manifest(format!(
+                    "pair of unknown token `{unknown}`"
+                )));
+            }
+            if a == b {
+                return Err(Error::Manifest(format!("pair of `{a}` with itself")));
+            }
+            if !pairs.insert(if a < b { (a, b) } else { (b, a) }) {
+                return Err(Error::Manifest(format!("pair `{a}`/`{b}` is listed twice")));
+            }
+            if matches!(pair.liquidity, Some([0, _] | [_, 0])) {
+                return Err(Error::Manifest(format!(
+                    "pair `{a}`/`{b}` needs both amounts to be seeded"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Balances beyond the 64 bits TOML integers have are written as strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawAmount {
+    Number(u64),
+    Text(String),
+}
+
+impl RawAmount {
+    fn parse<E: serde::de::Error>(self) -> core::result::Result<Balance, E> {
+        match self {
+            RawAmount::Number(amount) => Ok(amount.into()),
+            RawAmount::Text(text) => text
+                .replace('_', "")
+                .parse()
+                .map_err(|_| E::custom(format!("`{text}` is not an amount"))),
+        }
+    }
+}
+
+fn amount<'de, D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Balance, D::Error> {
+    RawAmount::deserialize(deserializer)?.parse()
+}
+
+fn liquidity<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> core::result::Result<Option<[Balance; 2]>, D::Error> {
+    let [a, b] = <[RawAmount; 2]>::deserialize(deserializer)?;
+    Ok(Some([a.parse()?, b.parse()?]))
+}
+
+fn account<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> core::result::Result<Option<AccountId>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    parse_account(&hex)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("`{hex}` is not a 0x-prefixed address")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_manifest_parses() {
+        let manifest = Manifest::parse(include_str!("../deploy.example.toml")).unwrap();
+
+        assert_eq!(manifest.url.as_deref(), Some("ws://127.0.0.1:9944"));
+        assert_eq!(manifest.tokens.len(), 3);
+        assert_eq!(manifest.tokens[0].supply, 1_000_000_000_000_000_000_000_000);
+        assert_eq!(manifest.tokens[2].decimals, 18);
+        assert_eq!(manifest.pairs[0].tokens, ["USDT", "WETH"]);
+        assert_eq!(
+            manifest.pairs[0].liquidity,
+            Some([2_000_000_000, 1_000_000_000_000_000_000])
+        );
+        assert_eq!(manifest.pairs[1].liquidity, None);
+    }
+
+    #[test]
+    fn amounts_can_be_numbers_or_strings() {
+        let manifest = Manifest::parse(
+            r#"
+            [[tokens]]
+            symbol = "A"
+            supply = 1_000
+
+            [[tokens]]
+            symbol = "B"
+            supply = "340282366920938463463374607431768211455"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.tokens[0].supply, 1_000);
+        assert_eq!(manifest.tokens[1].supply, u128::MAX);
+    }
+
+    #[test]
+    fn inconsistent_manifests_are_rejected() {
+        let tokens = r#"
+            [[tokens]]
+            symbol = "A"
+            [[tokens]]
+            symbol = "B"
+        "#;
+        let rejected = |pairs: &str| {
+            matches!(
+                Manifest::parse(&format!("{tokens}{pairs}")),
+                Err(Error::Manifest(_))
+            )
+        };
+
+        assert!(rejected("[[pairs]]\ntokens = [\"A\", \"C\"]"));
+        assert!(rejected("[[pairs]]\ntokens = [\"A\", \"A\"]"));
+        assert!(rejected(
+            "[[pairs]]\ntokens = [\"A\", \"B\"]\n[[pairs]]\ntokens = [\"B\", \"A\"]"
+        ));
+        assert!(rejected(
+            "[[pairs]]\ntokens = [\"A\", \"B\"]\nliquidity = [1, 0]"
+        ));
+        assert!(rejected("[[tokens]]\nsymbol = \"A\""));
+        assert!(rejected("[[pairs]]\ntokens = [\"A\", \"B\"]\nfee = 3"));
+        assert!(!rejected("[[pairs]]\ntokens = [\"A\", \"B\"]"));
+    }
+}
@@ -0,0 +1,322 @@
+//! In-process tests for the AMM contracts on drink!'s pallet-contracts sandbox. No node is
+//! involved, so scenarios are deterministic and cheap enough to run by the hundred:
+//!
+//! ```sh
+//! for contract in core pair lp_token psp22token router wnative multicall weighted_pair; do
+//!     cargo contract build --release --manifest-path contracts/$contract/Cargo.toml
+//! done
+//! cargo test -p drink-tests --features drink-tests
+//! ```
+//!
+//! The compiled code is read from `target/ink/<name>/<name>.wasm` at the workspace root, or
+//! from `$AMM_ARTIFACTS/<name>/<name>.wasm`.
+#![cfg(feature = "drink-tests")]
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use drink::pallet_contracts::Determinism;
+use drink::runtime::MinimalRuntime;
+use drink::{AccountId32, Sandbox, Weight, DEFAULT_ACTOR};
+use ink::env::hash::{Blake2x256, CryptoHash};
+use ink::primitives::{AccountId, Hash};
+use scale::{Decode, Encode};
+
+const GAS_LIMIT: Weight = Weight::from_parts(100_000_000_000, 3 * 1024 * 1024);
+
+/// Native balance given to every account that signs calls.
+const ENDOWMENT: u128 = 1_000_000_000_000_000;
+
+/// The contracts the harness can deploy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contract {
+    Core,
+    Pair,
+    LpToken,
+    Psp22token,
+    Router,
+    Wnative,
+    Multicall,
+    WeightedPair,
+}
+
+impl Contract {
+    pub fn name(self) -> &'static str {
+        match self {
+            Contract::Core => "core",
+            Contract::Pair => "pair",
+            Contract::LpToken => "lp_token",
+            Contract::Psp22token => "psp22token",
+            Contract::Router => "router",
+            Contract::Wnative => "wnative",
+            Contract::Multicall => "multicall",
+            Contract::WeightedPair => "weighted_pair",
+        }
+    }
+
+    fn wasm(self) -> Vec<u8> {
+        let dir = std::env::var_os("AMM_ARTIFACTS")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/ink"));
+        let path = dir.join(self.name()).join(format!("{}.wasm", self.name()));
+        std::fs::read(&path).unwrap_or_else(|err| {
+            panic!(
+                "reading {}: {err}; build the contract with `cargo contract build` first",
+                path.display()
+            )
+        })
+    }
+}
+
+/// A message that trapped, reverted or failed to dispatch.
+#[derive(Debug)]
+pub struct Reverted {
+    pub message: &'static str,
+    /// The dispatch error and the contract's debug output.
+    pub reason: String,
+}
+
+/// Weight consumed by one message call.
+#[derive(Debug, Clone, Copy)]
+pub struct GasUsage {
+    pub message: &'static str,
+    pub gas_consumed: Weight,
+}
+
+/// A sandboxed chain with the AMM contracts' code at hand. Calls are signed by `caller`,
+/// which starts as drink!'s default actor.
+pub struct Harness {
+    sandbox: Sandbox<MinimalRuntime>,
+    caller: AccountId32,
+    gas: Vec<GasUsage>,
+    salt: u32,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Self {
+            sandbox: Sandbox::new().expect("sandbox setup failed"),
+            caller: DEFAULT_ACTOR,
+            gas: Vec::new(),
+            salt: 0,
+        }
+    }
+
+    pub fn caller(&self) -> AccountId {
+        to_ink(&self.caller)
+    }
+
+    /// Signs the following calls as `caller`, funding it on first use.
+    pub fn set_caller(&mut self, caller: AccountId) {
+        let caller = to_drink(caller);
+        if self.sandbox.free_balance(&caller) == 0 {
+            self.sandbox
+                .mint_into(caller.clone(), ENDOWMENT)
+                .expect("funding the caller failed");
+        }
+        self.caller = caller;
+    }
+
+    /// Milliseconds since the epoch, as `block_timestamp` reports it to contracts.
+    pub fn now(&mut self) -> u64 {
+        self.sandbox.get_timestamp()
+    }
+
+    /// Starts a new block `millis` after the current one.
+    pub fn advance_time(&mut self, millis: u64) {
+        let now = self.now();
+        self.sandbox.build_block();
+        self.sandbox.set_timestamp(now + millis);
+    }
+
+    pub fn upload(&mut self, contract: Contract) -> Hash {
+        let result = self
+            .sandbox
+            .upload_contract(contract.wasm(), self.caller.clone(), None)
+            .unwrap_or_else(|err| panic!("uploading {} failed: {err:?}", contract.name()));
+        Hash::from(result.code_hash.0)
+    }
+
+    /// Instantiates `contract` with the constructor `constructor` and SCALE encoded `args`.
+    pub fn instantiate(
+        &mut self,
+        contract: Contract,
+        constructor: &'static str,
+        args: impl Encode,
+    ) -> AccountId {
+        self.salt += 1;
+        let mut input = selector(constructor).to_vec();
+        args.encode_to(&mut input);
+        let result = self.sandbox.deploy_contract(
+            contract.wasm(),
+            0,
+            input,
+            self.salt.to_le_bytes().to_vec(),
+            self.caller.clone(),
+            GAS_LIMIT,
+            None,
+        );
+        let debug = String::from_utf8_lossy(&result.debug_message).into_owned();
+        match result.result {
+            Ok(instantiated) if !instantiated.result.did_revert() => {
+                to_ink(&instantiated.account_id)
+            }
+            other => panic!(
+                "instantiating {} failed: {other:?} {debug}",
+                contract.name()
+            ),
+        }
+    }
+
+    /// Calls `message` on `contract` with SCALE encoded `args` and decodes its return value.
+    /// Every call, including failed ones, is added to the gas report.
+    pub fn call<R: Decode>(
+        &mut self,
+        contract: AccountId,
+        message: &'static str,
+        args: impl Encode,
+    ) -> Result<R, Reverted> {
+        self.call_with_value(contract, message, args, 0)
+    }
+
+    /// Like `call`, sending `value` of native currency along to a payable message.
+    pub fn call_with_value<R: Decode>(
+        &mut self,
+        contract: AccountId,
+        message: &'static str,
+        args: impl Encode,
+        value: u128,
+    ) -> Result<R, Reverted> {
+        let mut input = selector(message).to_vec();
+        args.encode_to(&mut input);
+        let result = self.sandbox.call_contract(
+            to_drink(contract),
+            value,
+            input,
+            self.caller.clone(),
+            GAS_LIMIT,
+            None,
+            Determinism::Enforced,
+        );
+        self.gas.push(GasUsage {
+            message,
+            gas_consumed: result.gas_consumed,
+        });
+
+        let debug = String::from_utf8_lossy(&result.debug_message).into_owned();
+        let returned = match result.result {
+            Ok(returned) if !returned.did_revert() => returned,
+            other => {
+                return Err(Reverted {
+                    message,
+                    reason: format!("{other:?} {debug}"),
+                })
+            }
+        };
+        <Result<R, ink::LangError>>::decode(&mut &returned.data[..])
+            .expect("undecodable return value")
+            .map_err(|err| Reverted {
+                message,
+                reason: format!("{err:?}"),
+            })
+    }
+
+    pub fn gas_report(&self) -> &[GasUsage] {
+        &self.gas
+    }
+
+    /// Number of calls and mean `ref_time` per message.
+    pub fn gas_summary(&self) -> BTreeMap<&'static str, (usize, u64)> {
+        let mut totals = BTreeMap::<&'static str, (usize, u64)>::new();
+        for usage in &self.gas {
+            let total = totals.entry(usage.message).or_default();
+            total.0 += 1;
+            total.1 += usage.gas_consumed.ref_time();
+        }
+        totals
+            .into_iter()
+            .map(|(message, (calls, ref_time))| (message, (calls, ref_time / calls as u64)))
+            .collect()
+    }
+
+    /// A `psp22token` with `supply` minted to the caller.
+    pub fn deploy_token(&mut self, supply: u128) -> AccountId {
+        self.instantiate(
+            Contract::Psp22token,
+            "new",
+            (supply, None::<String>, None::<String>, 18u8),
+        )
+    }
+
+    /// A `UniswapCore` with the caller as fee setter.
+    pub fn deploy_factory(&mut self) -> AccountId {
+        let pair_code_hash = self.upload(Contract::Pair);
+        let lp_token_code_hash = self.upload(Contract::LpToken);
+        let fee_to_setter = self.caller();
+        self.instantiate(
+            Contract::Core,
+            "new",
+            (fee_to_setter, pair_code_hash, lp_token_code_hash),
+        )
+    }
+
+    /// A `Router` on `factory`, wrapping native currency through a new `wnative`. Returns
+    /// the router and the wrapped token.
+    pub fn deploy_router(&mut self, factory: AccountId) -> (AccountId, AccountId) {
+        let wnative = self.instantiate(
+            Contract::Wnative,
+            "new",
+            (
+                Some(String::from("Wrapped Native")),
+                Some(String::from("WNATIVE")),
+                18u8,
+            ),
+        );
+        let router = self.instantiate(Contract::Router, "new", (factory, wnative));
+        (router, wnative)
+    }
+
+    /// Free native balance of `account`.
+    pub fn native_balance(&mut self, account: AccountId) -> u128 {
+        self.sandbox.free_balance(&to_drink(account))
+    }
+
+    pub fn balance_of(&mut self, token: AccountId, owner: AccountId) -> u128 {
+        self.call(token, "PSP22::balance_of", owner)
+            .expect("balance_of failed")
+    }
+
+    pub fn transfer(&mut self, token: AccountId, to: AccountId, value: u128) {
+        // The `PSP22Error` is only told apart from success, so it is not decoded.
+        self.call::<Result<(), ()>>(token, "PSP22::transfer", (to, value, Vec::<u8>::new()))
+            .expect("transfer failed")
+            .expect("transfer rejected");
+    }
+
+    pub fn approve(&mut self, token: AccountId, spender: AccountId, value: u128) {
+        self.call::<Result<(), ()>>(token, "PSP22::approve", (spender, value))
+            .expect("approve failed")
+            .expect("approve rejected");
+    }
+}
+
+/// Selector of a message or constructor, as `ink::selector_bytes!` computes it.
+pub fn selector(name: &str) -> [u8; 4] {
+    let mut hash = [0u8; 32];
+    Blake2x256::hash(name.as_bytes(), &mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn to_ink(account: &AccountId32) -> AccountId {
+    AccountId::from(*<AccountId32 as AsRef<[u8; 32]>>::as_ref(account))
+}
+
+fn to_drink(account: AccountId) -> AccountId32 {
+    AccountId32::new(*account.as_ref())
+}
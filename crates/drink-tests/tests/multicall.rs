@@ -0,0 +1,144 @@
+#![cfg(feature = "drink-tests")]
+//! Batches through `multicall`, which succeed or revert as one transaction.
+
+use drink_tests::{selector, Contract, Harness};
+use ink::primitives::AccountId;
+use multicall::multicall::{Call, Error, Outcome};
+use scale::{Decode, Encode};
+use uniswap_math::sort_tokens;
+
+const SUPPLY: u128 = 1_000_000_000_000_000;
+
+type Batch = Result<Vec<Outcome>, Error>;
+
+struct Setup {
+    multicall: AccountId,
+    pair: AccountId,
+    token_0: AccountId,
+    token_1: AccountId,
+}
+
+/// An empty pair and a multicall owned by the caller, approved to spend both its tokens.
+fn setup(harness: &mut Harness) -> Setup {
+    let token_a = harness.deploy_token(SUPPLY);
+    let token_b = harness.deploy_token(SUPPLY);
+    let factory = harness.deploy_factory();
+    let (token_0, token_1) = sort_tokens(token_a, token_b).unwrap();
+    let pair: AccountId = harness
+        .call(factory, "create_pair", (token_a, token_b))
+        .expect("create_pair failed");
+    let multicall = harness.instantiate(Contract::Multicall, "new", ());
+    harness.approve(token_0, multicall, SUPPLY);
+    harness.approve(token_1, multicall, SUPPLY);
+
+    Setup {
+        multicall,
+        pair,
+        token_0,
+        token_1,
+    }
+}
+
+fn call(callee: AccountId, message: &str, input: impl Encode) -> Call {
+    Call {
+        callee,
+        selector: selector(message),
+        input: input.encode(),
+        value: 0,
+        allow_failure: false,
+    }
+}
+
+/// `transfer_from` on both tokens into the pair, then `mint` to the caller.
+fn add_liquidity(harness: &Harness, setup: &Setup, amount_0: u128, amount_1: u128) -> Vec<Call> {
+    let owner = harness.caller();
+    vec![
+        call(
+            setup.token_0,
+            "PSP22::transfer_from",
+            (owner, setup.pair, amount_0, Vec::<u8>::new()),
+        ),
+        call(
+            setup.token_1,
+            "PSP22::transfer_from",
+            (owner, setup.pair, amount_1, Vec::<u8>::new()),
+        ),
+        call(setup.pair, "mint", owner),
+    ]
+}
+
+fn reserves(harness: &mut Harness, pair: AccountId) -> (u128, u128) {
+    let (reserve_0, reserve_1, _): (u128, u128, u128) = harness
+        .call(pair, "get_reserves", ())
+        .expect("get_reserves failed");
+    (reserve_0, reserve_1)
+}
+
+#[test]
+fn batch_adds_liquidity_in_one_transaction() {
+    let mut harness = Harness::new();
+    let setup = setup(&mut harness);
+    let calls = add_liquidity(&harness, &setup, 1_000_000, 4_000_000);
+
+    let outcomes = harness
+        .call::<Batch>(setup.multicall, "aggregate", calls)
+        .expect("aggregate reverted")
+        .expect("aggregate rejected");
+
+    assert!(outcomes.iter().all(|outcome| outcome.success));
+    // sqrt(1_000_000 * 4_000_000) less the locked minimum.
+    let liquidity = u128::decode(&mut &outcomes[2].output[..]).unwrap();
+    assert_eq!(liquidity, 2_000_000 - 1_000);
+    assert_eq!(reserves(&mut harness, setup.pair), (1_000_000, 4_000_000));
+    let owner = harness.caller();
+    assert_eq!(harness.balance_of(setup.token_0, owner), SUPPLY - 1_000_000);
+    assert_eq!(harness.balance_of(setup.token_1, owner), SUPPLY - 4_000_000);
+}
+
+#[test]
+fn failing_mint_reverts_the_transfers_before_it() {
+    let mut harness = Harness::new();
+    let setup = setup(&mut harness);
+    // Too little to mint past the locked minimum, so the third call panics.
+    let calls = add_liquidity(&harness, &setup, 1_000, 1_000);
+
+    let result = harness
+        .call::<Batch>(setup.multicall, "aggregate", calls)
+        .expect("aggregate reverted");
+
+    assert_eq!(result, Err(Error::CallFailed(2)));
+    assert_eq!(reserves(&mut harness, setup.pair), (0, 0));
+    let owner = harness.caller();
+    for token in [setup.token_0, setup.token_1] {
+        assert_eq!(harness.balance_of(token, setup.pair), 0);
+        assert_eq!(harness.balance_of(token, owner), SUPPLY);
+    }
+}
+
+#[test]
+fn failed_calls_allowed_to_fail_refund_their_value() {
+    let mut harness = Harness::new();
+    let setup = setup(&mut harness);
+    let owner = harness.caller();
+    let owner_before = harness.native_balance(owner);
+    let multicall_before = harness.native_balance(setup.multicall);
+
+    // `get_reserves` is not payable, so the call traps and keeps nothing.
+    let mut unpayable = call(setup.pair, "get_reserves", ());
+    unpayable.value = 1_000;
+    unpayable.allow_failure = true;
+    let outcomes = harness
+        .call_with_value::<Batch>(setup.multicall, "aggregate", vec![unpayable], 1_000)
+        .expect("aggregate reverted")
+        .expect("aggregate rejected");
+
+    assert_eq!(
+        outcomes,
+        vec![Outcome {
+            success: false,
+            output: Vec::new(),
+        }]
+    );
+    assert_eq!(harness.native_balance(setup.multicall), multicall_before);
+    assert_eq!(harness.native_balance(owner), owner_before);
+}
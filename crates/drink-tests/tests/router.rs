@@ -0,0 +1,304 @@
+#![cfg(feature = "drink-tests")]
+//! Router scenarios through the native entry points, which wrap and unwrap WNATIVE.
+
+use drink_tests::Harness;
+use ink::primitives::AccountId;
+use router::router::Error;
+use uniswap_math::{get_amount_in, get_amount_out, sort_tokens};
+
+const SUPPLY: u128 = 1_000_000_000_000_000;
+const DEADLINE: u64 = u64::MAX;
+
+/// Seeded on every pool: 1_000 tokens for 2_000 native, at 9 decimals.
+const TOKEN_SEED: u128 = 1_000_000_000_000;
+const NATIVE_SEED: u128 = 2_000_000_000_000;
+
+/// Recipient of payouts, holding no native currency until paid.
+const BOB: [u8; 32] = [0xb0; 32];
+
+type Swap = Result<Vec<u128>, Error>;
+
+struct Amm {
+    router: AccountId,
+    wnative: AccountId,
+    token: AccountId,
+    pair: AccountId,
+}
+
+/// A token/WNATIVE pair seeded through `add_liquidity_native`, with the router approved
+/// to spend the caller's tokens.
+fn amm(harness: &mut Harness) -> Amm {
+    let token = harness.deploy_token(SUPPLY);
+    let factory = harness.deploy_factory();
+    let (router, wnative) = harness.deploy_router(factory);
+    let pair: AccountId = harness
+        .call(factory, "create_pair", (token, wnative))
+        .expect("create_pair failed");
+    harness.approve(token, router, SUPPLY);
+
+    let to = harness.caller();
+    let added: (u128, u128, u128) = harness
+        .call_with_value::<Result<_, Error>>(
+            router,
+            "add_liquidity_native",
+            (token, TOKEN_SEED, 0u128, 0u128, to, DEADLINE),
+            NATIVE_SEED,
+        )
+        .expect("add_liquidity_native reverted")
+        .expect("add_liquidity_native rejected");
+    assert_eq!((added.0, added.1), (TOKEN_SEED, NATIVE_SEED));
+
+    Amm {
+        router,
+        wnative,
+        token,
+        pair,
+    }
+}
+
+/// Reserves of the pair as `(token, wnative)`.
+fn reserves(harness: &mut Harness, amm: &Amm) -> (u128, u128) {
+    let (reserve_0, reserve_1, _): (u128, u128, u128) = harness
+        .call(amm.pair, "get_reserves", ())
+        .expect("get_reserves failed");
+    if sort_tokens(amm.token, amm.wnative).unwrap().0 == amm.token {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    }
+}
+
+#[test]
+fn add_liquidity_native_refunds_unused_value() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let caller = harness.caller();
+    let native_before = harness.native_balance(caller);
+    let router_before = harness.native_balance(amm.router);
+
+    // 100 tokens need 200 native at the pool price; 500 are sent.
+    let (amount_token, amount_native, liquidity): (u128, u128, u128) = harness
+        .call_with_value::<Result<_, Error>>(
+            amm.router,
+            "add_liquidity_native",
+            (amm.token, TOKEN_SEED / 10, 0u128, 0u128, caller, DEADLINE),
+            NATIVE_SEED / 4,
+        )
+        .expect("add_liquidity_native reverted")
+        .expect("add_liquidity_native rejected");
+
+    assert_eq!(
+        (amount_token, amount_native),
+        (TOKEN_SEED / 10, NATIVE_SEED / 10)
+    );
+    assert!(liquidity > 0);
+    assert_eq!(
+        reserves(&mut harness, &amm),
+        (TOKEN_SEED * 11 / 10, NATIVE_SEED * 11 / 10)
+    );
+    // The caller paid for the native side and storage, not for the excess sent.
+    let spent = native_before - harness.native_balance(caller);
+    assert!(spent >= amount_native && spent < NATIVE_SEED / 4);
+    assert_eq!(harness.native_balance(amm.router), router_before);
+    assert_eq!(harness.balance_of(amm.wnative, amm.router), 0);
+}
+
+#[test]
+fn remove_liquidity_native_unwraps_for_the_recipient() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let caller = harness.caller();
+    let lp_token: AccountId = harness.call(amm.pair, "lp_token", ()).unwrap();
+    let liquidity = harness.balance_of(lp_token, caller) / 2;
+    harness.approve(lp_token, amm.router, liquidity);
+    let router_before = harness.native_balance(amm.router);
+
+    let (amount_token, amount_native): (u128, u128) = harness
+        .call::<Result<_, Error>>(
+            amm.router,
+            "remove_liquidity_native",
+            (
+                amm.token,
+                liquidity,
+                0u128,
+                0u128,
+                AccountId::from(BOB),
+                DEADLINE,
+            ),
+        )
+        .expect("remove_liquidity_native reverted")
+        .expect("remove_liquidity_native rejected");
+
+    assert!(amount_token > 0 && amount_native > 0);
+    assert_eq!(harness.balance_of(amm.token, BOB.into()), amount_token);
+    assert_eq!(harness.native_balance(BOB.into()), amount_native);
+    assert_eq!(harness.balance_of(amm.wnative, BOB.into()), 0);
+    assert_eq!(harness.native_balance(amm.router), router_before);
+    assert_eq!(harness.balance_of(amm.wnative, amm.router), 0);
+}
+
+#[test]
+fn swap_exact_native_for_tokens_wraps_the_value() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let amount_in = NATIVE_SEED / 100;
+    let expected = get_amount_out(amount_in, NATIVE_SEED, TOKEN_SEED).unwrap();
+
+    let amounts = harness
+        .call_with_value::<Swap>(
+            amm.router,
+            "swap_exact_native_for_tokens",
+            (
+                expected,
+                vec![amm.wnative, amm.token],
+                AccountId::from(BOB),
+                DEADLINE,
+            ),
+            amount_in,
+        )
+        .expect("swap_exact_native_for_tokens reverted")
+        .expect("swap_exact_native_for_tokens rejected");
+
+    assert_eq!(amounts, vec![amount_in, expected]);
+    assert_eq!(harness.balance_of(amm.token, BOB.into()), expected);
+    assert_eq!(
+        reserves(&mut harness, &amm),
+        (TOKEN_SEED - expected, NATIVE_SEED + amount_in)
+    );
+}
+
+#[test]
+fn swap_native_for_exact_tokens_refunds_the_rest() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let caller = harness.caller();
+    let amount_out = TOKEN_SEED / 200;
+    let amount_in = get_amount_in(amount_out, NATIVE_SEED, TOKEN_SEED).unwrap();
+    let native_before = harness.native_balance(caller);
+    let router_before = harness.native_balance(amm.router);
+
+    let amounts = harness
+        .call_with_value::<Swap>(
+            amm.router,
+            "swap_native_for_exact_tokens",
+            (
+                amount_out,
+                vec![amm.wnative, amm.token],
+                AccountId::from(BOB),
+                DEADLINE,
+            ),
+            2 * amount_in,
+        )
+        .expect("swap_native_for_exact_tokens reverted")
+        .expect("swap_native_for_exact_tokens rejected");
+
+    assert_eq!(amounts, vec![amount_in, amount_out]);
+    assert_eq!(harness.balance_of(amm.token, BOB.into()), amount_out);
+    let spent = native_before - harness.native_balance(caller);
+    assert!(spent >= amount_in && spent < amount_in + amount_in / 2);
+    assert_eq!(harness.native_balance(amm.router), router_before);
+    assert_eq!(harness.balance_of(amm.wnative, amm.router), 0);
+}
+
+#[test]
+fn swaps_for_native_unwrap_for_the_recipient() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let path = vec![amm.token, amm.wnative];
+    let router_before = harness.native_balance(amm.router);
+
+    let amount_in = TOKEN_SEED / 100;
+    let expected = get_amount_out(amount_in, TOKEN_SEED, NATIVE_SEED).unwrap();
+    let amounts = harness
+        .call::<Swap>(
+            amm.router,
+            "swap_exact_tokens_for_native",
+            (
+                amount_in,
+                expected,
+                path.clone(),
+                AccountId::from(BOB),
+                DEADLINE,
+            ),
+        )
+        .expect("swap_exact_tokens_for_native reverted")
+        .expect("swap_exact_tokens_for_native rejected");
+    assert_eq!(amounts, vec![amount_in, expected]);
+    assert_eq!(harness.native_balance(BOB.into()), expected);
+
+    let amount_out = NATIVE_SEED / 200;
+    let amounts = harness
+        .call::<Swap>(
+            amm.router,
+            "swap_tokens_for_exact_native",
+            (amount_out, u128::MAX, path, AccountId::from(BOB), DEADLINE),
+        )
+        .expect("swap_tokens_for_exact_native reverted")
+        .expect("swap_tokens_for_exact_native rejected");
+    assert_eq!(amounts[1], amount_out);
+    assert_eq!(harness.native_balance(BOB.into()), expected + amount_out);
+
+    assert_eq!(harness.balance_of(amm.wnative, BOB.into()), 0);
+    assert_eq!(harness.balance_of(amm.wnative, amm.router), 0);
+    assert_eq!(harness.native_balance(amm.router), router_before);
+}
+
+#[test]
+fn native_entry_points_reject_paths_without_wnative_at_the_native_end() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let to = AccountId::from(BOB);
+    let (into_native, from_native) = (vec![amm.token, amm.wnative], vec![amm.wnative, amm.token]);
+    let caller = harness.caller();
+    let native_before = harness.native_balance(caller);
+    let reserves_before = reserves(&mut harness, &amm);
+
+    // Native in, so the path must start with WNATIVE.
+    let value = NATIVE_SEED / 100;
+    let result = harness.call_with_value::<Swap>(
+        amm.router,
+        "swap_exact_native_for_tokens",
+        (0u128, into_native.clone(), to, DEADLINE),
+        value,
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+    let result = harness.call_with_value::<Swap>(
+        amm.router,
+        "swap_native_for_exact_tokens",
+        (1u128, into_native.clone(), to, DEADLINE),
+        value,
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+    let result = harness.call_with_value::<Result<(), Error>>(
+        amm.router,
+        "swap_exact_native_for_tokens_supporting_fee_on_transfer",
+        (0u128, into_native, to, DEADLINE),
+        value,
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+
+    // Native out, so the path must end with WNATIVE.
+    let result = harness.call::<Swap>(
+        amm.router,
+        "swap_exact_tokens_for_native",
+        (1_000u128, 0u128, from_native.clone(), to, DEADLINE),
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+    let result = harness.call::<Swap>(
+        amm.router,
+        "swap_tokens_for_exact_native",
+        (1_000u128, u128::MAX, from_native.clone(), to, DEADLINE),
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+    let result = harness.call::<Result<(), Error>>(
+        amm.router,
+        "swap_exact_tokens_for_native_supporting_fee_on_transfer",
+        (1_000u128, 0u128, from_native, to, DEADLINE),
+    );
+    assert_eq!(result.unwrap(), Err(Error::InvalidPath));
+
+    // Rejected calls revert, value included.
+    assert_eq!(reserves(&mut harness, &amm), reserves_before);
+    assert!(native_before - harness.native_balance(caller) < value);
+    assert_eq!(harness.native_balance(to), 0);
+}
@@ -0,0 +1,186 @@
+#![cfg(feature = "drink-tests")]
+//! Routes whose hops go through weighted pairs as well as classic ones.
+
+use drink_tests::{Contract, Harness};
+use ink::primitives::AccountId;
+use router::router::{Error, Hop, PoolKind};
+use uniswap_math::{sort_tokens, weighted};
+
+const SUPPLY: u128 = 1_000_000_000_000_000;
+const DEADLINE: u64 = u64::MAX;
+
+/// 4_000 A against 1_000 B in the 80/20 pair, which prices them alike.
+const WEIGHTED_SEED: (u128, u128) = (4_000_000_000_000, 1_000_000_000_000);
+/// 1_000 B against 1_000 C in the classic pair.
+const CLASSIC_SEED: u128 = 1_000_000_000_000;
+
+const BOB: [u8; 32] = [0xb0; 32];
+
+type Swap = Result<Vec<u128>, Error>;
+
+struct Amm {
+    router: AccountId,
+    token_a: AccountId,
+    token_b: AccountId,
+    token_c: AccountId,
+    weighted_pair: AccountId,
+}
+
+/// An 80/20 A/B weighted pair and a classic B/C pair, seeded by the caller, who approves
+/// the router to spend its A.
+fn amm(harness: &mut Harness) -> Amm {
+    let token_a = harness.deploy_token(SUPPLY);
+    let token_b = harness.deploy_token(SUPPLY);
+    let token_c = harness.deploy_token(SUPPLY);
+    let factory = harness.deploy_factory();
+    let (router, _) = harness.deploy_router(factory);
+
+    let code_hash = harness.upload(Contract::WeightedPair);
+    harness
+        .call::<()>(factory, "set_weighted_pair_code_hash", code_hash)
+        .expect("set_weighted_pair_code_hash failed");
+    let weighted_pair: AccountId = harness
+        .call(factory, "create_weighted_pair", (token_a, token_b, 80u32))
+        .expect("create_weighted_pair failed");
+    seed(harness, weighted_pair, (token_a, token_b), WEIGHTED_SEED);
+
+    let classic_pair: AccountId = harness
+        .call(factory, "create_pair", (token_b, token_c))
+        .expect("create_pair failed");
+    seed(
+        harness,
+        classic_pair,
+        (token_b, token_c),
+        (CLASSIC_SEED, CLASSIC_SEED),
+    );
+
+    harness.approve(token_a, router, SUPPLY);
+    Amm {
+        router,
+        token_a,
+        token_b,
+        token_c,
+        weighted_pair,
+    }
+}
+
+fn seed(
+    harness: &mut Harness,
+    pair: AccountId,
+    tokens: (AccountId, AccountId),
+    amounts: (u128, u128),
+) {
+    harness.transfer(tokens.0, pair, amounts.0);
+    harness.transfer(tokens.1, pair, amounts.1);
+    let to = harness.caller();
+    let liquidity: u128 = harness.call(pair, "mint", to).expect("mint failed");
+    assert!(liquidity > 0);
+}
+
+/// Reserves of the weighted pair as `(a, b)`.
+fn weighted_reserves(harness: &mut Harness, amm: &Amm) -> (u128, u128) {
+    let (reserve_0, reserve_1, _): (u128, u128, u128) = harness
+        .call(amm.weighted_pair, "get_reserves", ())
+        .expect("get_reserves failed");
+    if sort_tokens(amm.token_a, amm.token_b).unwrap().0 == amm.token_a {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    }
+}
+
+fn route(amm: &Amm) -> Vec<Hop> {
+    vec![
+        Hop {
+            token_in: amm.token_a,
+            token_out: amm.token_b,
+            kind: PoolKind::Weighted(80),
+        },
+        Hop {
+            token_in: amm.token_b,
+            token_out: amm.token_c,
+            kind: PoolKind::Classic,
+        },
+    ]
+}
+
+#[test]
+fn routes_through_an_80_20_pair_at_its_own_price() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let amount_in = 10_000_000_000;
+
+    let quoted = harness
+        .call::<Swap>(
+            amm.router,
+            "get_amounts_out_on_route",
+            (amount_in, route(&amm)),
+        )
+        .expect("get_amounts_out_on_route reverted")
+        .expect("get_amounts_out_on_route rejected");
+    let over_weighted_pair =
+        weighted::get_amount_out(amount_in, WEIGHTED_SEED.0, WEIGHTED_SEED.1, 80, 20).unwrap();
+    let over_classic_pair = harness
+        .call::<Swap>(
+            amm.router,
+            "get_amounts_out",
+            (over_weighted_pair, vec![amm.token_b, amm.token_c]),
+        )
+        .expect("get_amounts_out reverted")
+        .expect("get_amounts_out rejected")[1];
+    assert_eq!(
+        quoted,
+        vec![amount_in, over_weighted_pair, over_classic_pair]
+    );
+
+    let bob = AccountId::from(BOB);
+    let amounts = harness
+        .call::<Swap>(
+            amm.router,
+            "swap_exact_tokens_for_tokens_on_route",
+            (amount_in, quoted[2], route(&amm), bob, DEADLINE),
+        )
+        .expect("swap reverted")
+        .expect("swap rejected");
+
+    assert_eq!(amounts, quoted);
+    assert_eq!(harness.balance_of(amm.token_c, bob), quoted[2]);
+    assert_eq!(
+        weighted_reserves(&mut harness, &amm),
+        (
+            WEIGHTED_SEED.0 + amount_in,
+            WEIGHTED_SEED.1 - over_weighted_pair
+        )
+    );
+    assert_eq!(harness.balance_of(amm.token_b, amm.router), 0);
+}
+
+#[test]
+fn routes_need_existing_connected_pools() {
+    let mut harness = Harness::new();
+    let amm = amm(&mut harness);
+    let bob = AccountId::from(BOB);
+
+    // There is no 20/80 A/B pair.
+    let mut hops = route(&amm);
+    hops[0].kind = PoolKind::Weighted(20);
+    let swap = harness
+        .call::<Swap>(
+            amm.router,
+            "swap_exact_tokens_for_tokens_on_route",
+            (1_000_000u128, 0u128, hops, bob, DEADLINE),
+        )
+        .expect("swap reverted");
+    assert_eq!(swap, Err(Error::PairNotFound));
+
+    let mut hops = route(&amm);
+    hops.swap(0, 1);
+    let swap = harness
+        .call::<Swap>(
+            amm.router,
+            "swap_exact_tokens_for_tokens_on_route",
+            (1_000_000u128, 0u128, hops, bob, DEADLINE),
+        )
+        .expect("swap reverted");
+    assert_eq!(swap, Err(Error::InvalidPath));
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "drink-tests")]
+//! Pair scenarios on the in-process sandbox.
+
+use drink_tests::Harness;
+use ink::primitives::AccountId;
+use lp_token::PSP22Error;
+use uniswap_math::{get_amount_out, pair::encode_price, sort_tokens};
+
+const SUPPLY: u128 = 1_000_000_000_000_000;
+
+struct Pool {
+    pair: AccountId,
+    token_0: AccountId,
+    token_1: AccountId,
+}
+
+/// Creates a pair through the factory and seeds it with `amount_0`/`amount_1`.
+fn pool(harness: &mut Harness, amount_0: u128, amount_1: u128) -> Pool {
+    let token_a = harness.deploy_token(SUPPLY);
+    let token_b = harness.deploy_token(SUPPLY);
+    let factory = harness.deploy_factory();
+    let (token_0, token_1) = sort_tokens(token_a, token_b).unwrap();
+    let pair: AccountId = harness
+        .call(factory, "create_pair", (token_a, token_b))
+        .expect("create_pair failed");
+
+    harness.transfer(token_0, pair, amount_0);
+    harness.transfer(token_1, pair, amount_1);
+    let to = harness.caller();
+    harness.call::<u128>(pair, "mint", to).expect("mint failed");
+
+    Pool {
+        pair,
+        token_0,
+        token_1,
+    }
+}
+
+fn reserves(harness: &mut Harness, pair: AccountId) -> (u128, u128, u128) {
+    harness
+        .call(pair, "get_reserves", ())
+        .expect("get_reserves failed")
+}
+
+#[test]
+fn oracle_accumulates_prices_over_time() {
+    let mut harness = Harness::new();
+    let pool = pool(&mut harness, 1_000_000, 4_000_000);
+    let (_, _, last) = reserves(&mut harness, pool.pair);
+
+    harness.advance_time(12_000);
+    harness
+        .call::<()>(pool.pair, "sync", ())
+        .expect("sync failed");
+
+    let price_0: u128 = harness
+        .call(pool.pair, "price_0_cumulative_last", ())
+        .unwrap();
+    let price_1: u128 = harness
+        .call(pool.pair, "price_1_cumulative_last", ())
+        .unwrap();
+    let elapsed = reserves(&mut harness, pool.pair).2 - last;
+    assert_eq!(elapsed, 12_000);
+    assert_eq!(price_0, encode_price(1_000_000, 4_000_000) * elapsed);
+    assert_eq!(price_1, encode_price(4_000_000, 1_000_000) * elapsed);
+
+    // Prices only accumulate once per block.
+    harness
+        .call::<()>(pool.pair, "sync", ())
+        .expect("sync failed");
+    let unchanged: u128 = harness
+        .call(pool.pair, "price_0_cumulative_last", ())
+        .unwrap();
+    assert_eq!(unchanged, price_0);
+}
+
+#[test]
+fn swap_breaking_k_reverts() {
+    let mut harness = Harness::new();
+    let pool = pool(&mut harness, 1_000_000, 4_000_000);
+    let to = harness.caller();
+    let amount_out = get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+
+    harness.transfer(pool.token_0, pool.pair, 10_000);
+    let result = harness.call::<()>(
+        pool.pair,
+        "swap",
+        (0u128, amount_out + 1, to, Vec::<u8>::new()),
+    );
+
+    assert!(result.is_err());
+    assert_eq!(reserves(&mut harness, pool.pair).0, 1_000_000);
+}
+
+#[test]
+fn lp_token_mints_and_burns_only_for_its_pair() {
+    let mut harness = Harness::new();
+    let pool = pool(&mut harness, 1_000_000, 4_000_000);
+    let alice = harness.caller();
+    let lp_token: AccountId = harness.call(pool.pair, "lp_token", ()).unwrap();
+    let supply: u128 = harness.call(lp_token, "PSP22::total_supply", ()).unwrap();
+    let forbidden = Err(PSP22Error::Custom("UniswapV2: FORBIDDEN".into()));
+
+    let minted = harness.call::<Result<(), PSP22Error>>(
+        lp_token,
+        "PSP22Mintable::mint",
+        (alice, 1_000_000u128),
+    );
+    assert_eq!(minted.unwrap(), forbidden);
+    let burnt =
+        harness.call::<Result<(), PSP22Error>>(lp_token, "PSP22Burnable::burn", (alice, 1_000u128));
+    assert_eq!(burnt.unwrap(), forbidden);
+
+    let unchanged: u128 = harness.call(lp_token, "PSP22::total_supply", ()).unwrap();
+    assert_eq!(unchanged, supply);
+}
+
+/// A few hundred swaps in alternating directions with pseudo-random sizes: reserves track
+/// balances and K never shrinks.
+#[test]
+fn repeated_swaps_keep_invariants() {
+    let mut harness = Harness::new();
+    let pool = pool(&mut harness, 1_000_000_000, 2_000_000_000);
+    let to = harness.caller();
+    let mut seed: u64 = 0x5eed;
+
+    for round in 0..300 {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let (reserve_0, reserve_1, _) = reserves(&mut harness, pool.pair);
+        let amount_in = 1 + (seed >> 33) as u128 % (reserve_0.min(reserve_1) / 20);
+        let zero_for_one = round % 2 == 0;
+        let (token_in, reserve_in, reserve_out) = if zero_for_one {
+            (pool.token_0, reserve_0, reserve_1)
+        } else {
+            (pool.token_1, reserve_1, reserve_0)
+        };
+        let amount_out = get_amount_out(amount_in, reserve_in, reserve_out).unwrap();
+        let (amount_0_out, amount_1_out) = if zero_for_one {
+            (0, amount_out)
+        } else {
+            (amount_out, 0)
+        };
+
+        harness.transfer(token_in, pool.pair, amount_in);
+        harness
+            .call::<()>(
+                pool.pair,
+                "swap",
+                (amount_0_out, amount_1_out, to, Vec::<u8>::new()),
+            )
+            .unwrap_or_else(|err| panic!("swap {round} failed: {err:?}"));
+
+        let (new_reserve_0, new_reserve_1, _) = reserves(&mut harness, pool.pair);
+        assert_eq!(new_reserve_0, harness.balance_of(pool.token_0, pool.pair));
+        assert_eq!(new_reserve_1, harness.balance_of(pool.token_1, pool.pair));
+        assert!(new_reserve_0 * new_reserve_1 >= reserve_0 * reserve_1);
+        harness.advance_time(6_000);
+    }
+
+    for (message, (calls, ref_time)) in harness.gas_summary() {
+        println!("{message:<24} {calls:>5} calls {ref_time:>14} ref_time");
+    }
+    assert!(harness
+        .gas_report()
+        .iter()
+        .all(|usage| usage.gas_consumed.ref_time() > 0));
+}
@@ -0,0 +1,147 @@
+//! End-to-end tests for the AMM contracts, run against a local `substrate-contracts-node`:
+//!
+//! ```sh
+//! cargo test -p e2e-tests --features e2e-tests
+//! ```
+//!
+//! Event types are private to the contracts, so the ones the tests assert on are mirrored
+//! here. ink! encodes an event as its index among the contract's events, then its fields.
+
+use ink::primitives::AccountId;
+use scale::Decode;
+
+/// Raw ink! event data emitted by the contract at `emitter`.
+#[derive(Debug, Clone)]
+pub struct ContractEvent {
+    pub emitter: AccountId,
+    pub data: Vec<u8>,
+}
+
+impl ContractEvent {
+    /// Decodes the event if it is an `E`.
+    pub fn decode<E: Event>(&self) -> Option<E> {
+        let (index, mut fields) = self.data.split_first()?;
+        if *index != E::INDEX {
+            return None;
+        }
+        E::decode(&mut fields).ok()
+    }
+}
+
+/// A mirrored contract event, with its position among the events of its contract.
+pub trait Event: Decode {
+    const INDEX: u8;
+}
+
+/// Events of `UniswapCore`.
+pub mod factory {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub enum PairKind {
+        Classic,
+        Stable,
+        Weighted(u32),
+        Concentrated(u32),
+    }
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub struct PairCreated {
+        pub token_0: Option<AccountId>,
+        pub token_1: Option<AccountId>,
+        pub pair: Option<AccountId>,
+        pub pair_index: u32,
+        pub kind: PairKind,
+    }
+
+    impl Event for PairCreated {
+        const INDEX: u8 = 0;
+    }
+}
+
+/// Events of `Pair`.
+pub mod pair {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub struct Sync {
+        pub reserve_0: u128,
+        pub reserve_1: u128,
+        pub pair: AccountId,
+    }
+
+    impl Event for Sync {
+        const INDEX: u8 = 0;
+    }
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub struct Mint {
+        pub owner: Option<AccountId>,
+        pub amount_0: u128,
+        pub amount_1: u128,
+        pub to: AccountId,
+        pub liquidity: u128,
+    }
+
+    impl Event for Mint {
+        const INDEX: u8 = 1;
+    }
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub struct Burn {
+        pub sender: Option<AccountId>,
+        pub amount_0: u128,
+        pub amount_1: u128,
+        pub to: AccountId,
+        pub liquidity: u128,
+    }
+
+    impl Event for Burn {
+        const INDEX: u8 = 2;
+    }
+
+    #[derive(Debug, PartialEq, Eq, Decode)]
+    pub struct Swap {
+        pub sender: Option<AccountId>,
+        pub amount_0_in: u128,
+        pub amount_1_in: u128,
+        pub amount_0_out: u128,
+        pub amount_1_out: u128,
+        pub to: AccountId,
+        pub reserve_0: u128,
+        pub reserve_1: u128,
+    }
+
+    impl Event for Swap {
+        const INDEX: u8 = 3;
+    }
+}
+
+/// The `Contracts::ContractEmitted` events of an extrinsic, in order.
+#[cfg(feature = "e2e-tests")]
+pub fn contract_events<C: ink_e2e::subxt::Config>(
+    events: &ink_e2e::subxt::blocks::ExtrinsicEvents<C>,
+) -> Vec<ContractEvent> {
+    events
+        .iter()
+        .map(|event| event.expect("undecodable runtime event"))
+        .filter(|event| {
+            event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted"
+        })
+        .map(|event| {
+            // `ContractEmitted { contract, data }`
+            let (emitter, data) = <(AccountId, Vec<u8>)>::decode(&mut event.field_bytes())
+                .expect("undecodable ContractEmitted");
+            ContractEvent { emitter, data }
+        })
+        .collect()
+}
+
+/// The events `emitter` emitted that decode as `E`.
+pub fn find<E: Event>(events: &[ContractEvent], emitter: AccountId) -> Vec<E> {
+    events
+        .iter()
+        .filter(|event| event.emitter == emitter)
+        .filter_map(ContractEvent::decode)
+        .collect()
+}
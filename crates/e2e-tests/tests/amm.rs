@@ -0,0 +1,331 @@
+#![cfg(feature = "e2e-tests")]
+//! Factory → pair → token flows against a freshly spawned node.
+
+use deflationary_token::deflationary_token::DeflationaryTokenRef;
+use e2e_tests::{
+    contract_events,
+    factory::{PairCreated, PairKind},
+    find,
+    pair::{Burn, Mint, Swap, Sync},
+};
+use ink::env::DefaultEnvironment;
+use ink::primitives::AccountId;
+use ink_e2e::{build_message, AccountKeyring, PolkadotConfig};
+use openbrush::contracts::psp22::PSP22;
+use pair::pair::PairRef;
+use psp22token::psp22token::Psp22tokenRef;
+use router::router::RouterRef;
+use uniswap_core::core::UniswapCoreRef;
+use uniswap_math::{get_amount_out, pair::MINIMUM_LIQUIDITY, sort_tokens};
+
+type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Client = ink_e2e::Client<PolkadotConfig, DefaultEnvironment>;
+
+const SUPPLY: u128 = 1_000_000_000_000;
+const BURN_BPS: u16 = 100;
+
+async fn deploy_token(client: &mut Client, symbol: &str) -> AccountId {
+    let constructor = Psp22tokenRef::new(SUPPLY, Some(symbol.into()), Some(symbol.into()), 18);
+    client
+        .instantiate("psp22token", &ink_e2e::alice(), constructor, 0, None)
+        .await
+        .expect("token instantiation failed")
+        .account_id
+}
+
+/// Deploys `UniswapCore` with Alice as fee setter, uploading the pair and LP token code.
+async fn deploy_factory(client: &mut Client) -> AccountId {
+    let pair_code_hash = client
+        .upload("pair", &ink_e2e::alice(), None)
+        .await
+        .expect("pair upload failed")
+        .code_hash;
+    let lp_token_code_hash = client
+        .upload("lp_token", &ink_e2e::alice(), None)
+        .await
+        .expect("LP token upload failed")
+        .code_hash;
+
+    let constructor = UniswapCoreRef::new(
+        ink_e2e::account_id(AccountKeyring::Alice),
+        pair_code_hash,
+        lp_token_code_hash,
+    );
+    client
+        .instantiate("core", &ink_e2e::alice(), constructor, 0, None)
+        .await
+        .expect("factory instantiation failed")
+        .account_id
+}
+
+async fn balance_of(client: &mut Client, token: AccountId, owner: AccountId) -> u128 {
+    let message = build_message::<Psp22tokenRef>(token).call(|token| token.balance_of(owner));
+    client
+        .call_dry_run(&ink_e2e::alice(), &message, 0, None)
+        .await
+        .return_value()
+}
+
+async fn transfer(client: &mut Client, token: AccountId, to: AccountId, value: u128) {
+    let message =
+        build_message::<Psp22tokenRef>(token).call(|token| token.transfer(to, value, Vec::new()));
+    client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("transfer failed")
+        .return_value()
+        .expect("transfer rejected");
+}
+
+async fn approve(client: &mut Client, token: AccountId, spender: AccountId, value: u128) {
+    let message = build_message::<Psp22tokenRef>(token).call(|token| token.approve(spender, value));
+    client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("approve failed");
+}
+
+async fn get_reserves(client: &mut Client, pair: AccountId) -> (u128, u128) {
+    let message = build_message::<PairRef>(pair).call(|pair| pair.get_reserves());
+    let (reserve_0, reserve_1, _) = client
+        .call_dry_run(&ink_e2e::alice(), &message, 0, None)
+        .await
+        .return_value();
+    (reserve_0, reserve_1)
+}
+
+#[ink_e2e::test]
+async fn create_pair_add_liquidity_swap_and_remove(mut client: Client) -> E2EResult<()> {
+    let alice = ink_e2e::account_id(AccountKeyring::Alice);
+    let bob = ink_e2e::account_id(AccountKeyring::Bob);
+    let token_a = deploy_token(&mut client, "TKA").await;
+    let token_b = deploy_token(&mut client, "TKB").await;
+    let factory = deploy_factory(&mut client).await;
+    let (token_0, token_1) = sort_tokens(token_a, token_b).unwrap();
+
+    // Create the pair where the factory says it will be.
+    let message = build_message::<UniswapCoreRef>(factory)
+        .call(|factory| factory.compute_pair_address(token_a, token_b));
+    let computed = client
+        .call_dry_run(&ink_e2e::alice(), &message, 0, None)
+        .await
+        .return_value();
+
+    let message = build_message::<UniswapCoreRef>(factory)
+        .call(|factory| factory.create_pair(token_a, token_b));
+    let result = client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("create_pair failed");
+    let events = contract_events(&result.events);
+    let pair = result.return_value();
+
+    assert_eq!(pair, computed);
+    assert_eq!(
+        find::<PairCreated>(&events, factory),
+        vec![PairCreated {
+            token_0: Some(token_0),
+            token_1: Some(token_1),
+            pair: Some(pair),
+            pair_index: 1,
+            kind: PairKind::Classic,
+        }]
+    );
+    let message = build_message::<PairRef>(pair).call(|pair| pair.lp_token());
+    let lp_token = client
+        .call_dry_run(&ink_e2e::alice(), &message, 0, None)
+        .await
+        .return_value();
+
+    // Add liquidity.
+    transfer(&mut client, token_0, pair, 1_000_000).await;
+    transfer(&mut client, token_1, pair, 4_000_000).await;
+    let message = build_message::<PairRef>(pair).call(|pair| pair.mint(alice));
+    let result = client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("mint failed");
+    let events = contract_events(&result.events);
+    let liquidity = result.return_value();
+
+    assert_eq!(liquidity, 2_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(balance_of(&mut client, lp_token, alice).await, liquidity);
+    assert_eq!(
+        get_reserves(&mut client, pair).await,
+        (1_000_000, 4_000_000)
+    );
+    assert_eq!(
+        find::<Mint>(&events, pair),
+        vec![Mint {
+            owner: Some(alice),
+            amount_0: 1_000_000,
+            amount_1: 4_000_000,
+            to: alice,
+            liquidity,
+        }]
+    );
+
+    // Swap token 0 for token 1.
+    let amount_out = get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+    transfer(&mut client, token_0, pair, 10_000).await;
+    let message =
+        build_message::<PairRef>(pair).call(|pair| pair.swap(0, amount_out, bob, Vec::new()));
+    let result = client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("swap failed");
+    let events = contract_events(&result.events);
+
+    assert_eq!(balance_of(&mut client, token_1, bob).await, amount_out);
+    assert_eq!(
+        get_reserves(&mut client, pair).await,
+        (1_010_000, 4_000_000 - amount_out)
+    );
+    assert_eq!(
+        find::<Swap>(&events, pair),
+        vec![Swap {
+            sender: Some(alice),
+            amount_0_in: 10_000,
+            amount_1_in: 0,
+            amount_0_out: 0,
+            amount_1_out: amount_out,
+            to: bob,
+            reserve_0: 1_010_000,
+            reserve_1: 4_000_000 - amount_out,
+        }]
+    );
+    assert_eq!(
+        find::<Sync>(&events, pair),
+        vec![Sync {
+            reserve_0: 1_010_000,
+            reserve_1: 4_000_000 - amount_out,
+            pair,
+        }]
+    );
+
+    // Remove all of Alice's liquidity, paid out to Bob.
+    let balance_1_before = balance_of(&mut client, token_1, bob).await;
+    transfer(&mut client, lp_token, pair, liquidity).await;
+    let message = build_message::<PairRef>(pair).call(|pair| pair.burn(bob));
+    let result = client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("burn failed");
+    let events = contract_events(&result.events);
+    let (amount_0, amount_1) = result.return_value();
+
+    assert_eq!(amount_0, liquidity * 1_010_000 / 2_000_000);
+    assert_eq!(amount_1, liquidity * (4_000_000 - amount_out) / 2_000_000);
+    assert_eq!(balance_of(&mut client, token_0, bob).await, amount_0);
+    assert_eq!(
+        balance_of(&mut client, token_1, bob).await,
+        balance_1_before + amount_1
+    );
+    assert_eq!(balance_of(&mut client, lp_token, alice).await, 0);
+    assert_eq!(
+        find::<Burn>(&events, pair),
+        vec![Burn {
+            sender: Some(alice),
+            amount_0,
+            amount_1,
+            to: bob,
+            liquidity,
+        }]
+    );
+
+    Ok(())
+}
+
+#[ink_e2e::test]
+async fn router_swaps_fee_on_transfer_tokens(mut client: Client) -> E2EResult<()> {
+    let bob = ink_e2e::account_id(AccountKeyring::Bob);
+    let constructor =
+        DeflationaryTokenRef::new(SUPPLY, Some("FEE".into()), Some("FEE".into()), 18, BURN_BPS);
+    let fee_token = client
+        .instantiate(
+            "deflationary_token",
+            &ink_e2e::alice(),
+            constructor,
+            0,
+            None,
+        )
+        .await
+        .expect("fee token instantiation failed")
+        .account_id;
+    let token = deploy_token(&mut client, "TKN").await;
+    let factory = deploy_factory(&mut client).await;
+    // Token-only routes never touch the wrapped native token.
+    let constructor = RouterRef::new(factory, AccountId::from([0x0; 32]));
+    let router = client
+        .instantiate("router", &ink_e2e::alice(), constructor, 0, None)
+        .await
+        .expect("router instantiation failed")
+        .account_id;
+    let after_burn = |amount: u128| amount - amount * BURN_BPS as u128 / 10_000;
+
+    let message = build_message::<UniswapCoreRef>(factory)
+        .call(|factory| factory.create_pair(fee_token, token));
+    let pair = client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("create_pair failed")
+        .return_value();
+    approve(&mut client, fee_token, router, SUPPLY).await;
+    approve(&mut client, token, router, SUPPLY).await;
+    let message = build_message::<RouterRef>(router).call(|router| {
+        router.add_liquidity(
+            fee_token,
+            token,
+            1_000_000,
+            1_000_000,
+            0,
+            0,
+            ink_e2e::account_id(AccountKeyring::Alice),
+            u64::MAX,
+        )
+    });
+    client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("add_liquidity failed")
+        .return_value()
+        .expect("add_liquidity rejected");
+
+    let (reserve_0, reserve_1) = get_reserves(&mut client, pair).await;
+    let (reserve_in, reserve_out) = if sort_tokens(fee_token, token).unwrap().0 == fee_token {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    };
+    assert_eq!(reserve_in, after_burn(1_000_000));
+
+    // The plain route quotes on the nominal amount and trips K.
+    let path = vec![fee_token, token];
+    let message = build_message::<RouterRef>(router)
+        .call(|router| router.swap_exact_tokens_for_tokens(10_000, 0, path.clone(), bob, u64::MAX));
+    assert!(client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .is_err());
+
+    let expected = get_amount_out(after_burn(10_000), reserve_in, reserve_out).unwrap();
+    let message = build_message::<RouterRef>(router).call(|router| {
+        router.swap_exact_tokens_for_tokens_supporting_fee_on_transfer(
+            10_000,
+            expected,
+            path.clone(),
+            bob,
+            u64::MAX,
+        )
+    });
+    client
+        .call(&ink_e2e::alice(), message, 0, None)
+        .await
+        .expect("swap failed")
+        .return_value()
+        .expect("swap rejected");
+
+    assert_eq!(balance_of(&mut client, token, bob).await, expected);
+
+    Ok(())
+}
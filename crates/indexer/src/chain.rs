@@ -0,0 +1,65 @@
+//! Where blocks come from: a node in production, a stand-in in tests.
+
+use std::future::Future;
+
+use ink::primitives::AccountId;
+use uniswap_client::events::contract_event;
+use uniswap_client::{Client, ContractEvent};
+
+use crate::{Error, Result};
+
+pub trait Chain {
+    /// Number of the latest finalized block.
+    fn finalized_head(&self) -> impl Future<Output = Result<u32>> + Send;
+
+    /// The contract events emitted in block `number`, in order.
+    fn contract_events(
+        &self,
+        number: u32,
+    ) -> impl Future<Output = Result<Vec<ContractEvent>>> + Send;
+
+    /// The LP token of `pair`, which no event carries.
+    fn lp_token(&self, pair: AccountId) -> impl Future<Output = Result<AccountId>> + Send;
+}
+
+/// A contracts node, read through the client.
+pub struct NodeChain {
+    client: Client,
+}
+
+impl NodeChain {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Chain for NodeChain {
+    async fn finalized_head(&self) -> Result<u32> {
+        let rpc = self.client.api().rpc();
+        let hash = rpc.finalized_head().await?;
+        let header = rpc.header(Some(hash)).await?.ok_or_else(|| {
+            Error::Inconsistent(format!("no header for finalized block {hash:?}"))
+        })?;
+        Ok(header.number)
+    }
+
+    async fn contract_events(&self, number: u32) -> Result<Vec<ContractEvent>> {
+        let hash = self
+            .client
+            .api()
+            .rpc()
+            .block_hash(Some(number.into()))
+            .await?
+            .ok_or(Error::MissingBlock(number))?;
+        let events = self.client.api().events().at(hash).await?;
+        let mut contract_events = Vec::new();
+        for event in events.iter() {
+            contract_events.extend(contract_event(&event?));
+        }
+        Ok(contract_events)
+    }
+
+    async fn lp_token(&self, pair: AccountId) -> Result<AccountId> {
+        Ok(self.client.pair(pair).lp_token().await?)
+    }
+}
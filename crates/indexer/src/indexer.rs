@@ -0,0 +1,466 @@
+//! Turns the events of each block into store writes.
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use ink::primitives::AccountId;
+use uniswap_client::events::PairCreated;
+use uniswap_client::{Artifacts, ContractEvent, FactoryEvent, PairEvent, TokenEvent};
+
+use crate::store::Batch;
+use crate::{Chain, Result, Store};
+
+pub struct Indexer<C> {
+    chain: C,
+    store: Store,
+    factory: AccountId,
+    /// Resolves event indices to labels.
+    artifacts: Artifacts,
+}
+
+impl<C: Chain> Indexer<C> {
+    pub fn new(chain: C, store: Store, factory: AccountId, artifacts: Artifacts) -> Self {
+        Self {
+            chain,
+            store,
+            factory,
+            artifacts,
+        }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Indexes `blocks` in order, skipping those already indexed.
+    pub async fn replay(&mut self, blocks: RangeInclusive<u32>) -> Result<()> {
+        let start = match self.store.cursor()? {
+            Some(cursor) => (*blocks.start()).max(cursor + 1),
+            None => *blocks.start(),
+        };
+        for number in start..=*blocks.end() {
+            self.index_block(number).await?;
+        }
+        Ok(())
+    }
+
+    /// Indexes finalized blocks from `from` on as they come, checking for new ones every
+    /// `interval`. Returns only on error.
+    pub async fn follow(&mut self, from: u32, interval: Duration) -> Result<()> {
+        loop {
+            let head = self.chain.finalized_head().await?;
+            if head >= from {
+                self.replay(from..=head).await?;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn index_block(&mut self, number: u32) -> Result<()> {
+        let events = self.chain.contract_events(number).await?;
+
+        // Looked up before the batch opens, as the batch cannot be held across awaits.
+        let mut lp_tokens = BTreeMap::new();
+        for event in events.iter().filter(|event| event.emitter == self.factory) {
+            if let Some(pair) =
+                created_pair(event, &self.artifacts).and_then(|created| created.pair)
+            {
+                lp_tokens.insert(pair, self.chain.lp_token(pair).await?);
+            }
+        }
+
+        let batch = self.store.begin(number)?;
+        for (index, event) in (0..).zip(&events) {
+            apply(
+                &batch,
+                &self.artifacts,
+                self.factory,
+                &lp_tokens,
+                index,
+                event,
+            )?;
+        }
+        batch.commit()
+    }
+}
+
+fn created_pair(event: &ContractEvent, artifacts: &Artifacts) -> Option<PairCreated> {
+    match FactoryEvent::decode(event, &artifacts.core)? {
+        FactoryEvent::PairCreated(created) => Some(created),
+        FactoryEvent::PairCodeHashChanged(_) | FactoryEvent::Other(_) => None,
+    }
+}
+
+/// Records `event` if it comes from the factory, a known pair or a known LP token.
+fn apply(
+    batch: &Batch<'_>,
+    artifacts: &Artifacts,
+    factory: AccountId,
+    lp_tokens: &BTreeMap<AccountId, AccountId>,
+    index: u32,
+    event: &ContractEvent,
+) -> Result<()> {
+    let emitter = event.emitter;
+    if emitter == factory {
+        if let Some(PairCreated {
+            token_0: Some(token_0),
+            token_1: Some(token_1),
+            pair: Some(pair),
+            pair_index,
+            ..
+        }) = created_pair(event, artifacts)
+        {
+            batch.insert_pair(pair, (token_0, token_1), lp_tokens[&pair], pair_index)?;
+        }
+    } else if batch.is_pair(emitter)? {
+        match PairEvent::decode(event, &artifacts.pair) {
+            Some(PairEvent::Sync(sync)) => batch.record_sync(emitter, index, &sync)?,
+            Some(PairEvent::Swap(swap)) => batch.record_swap(emitter, index, &swap)?,
+            Some(PairEvent::Mint(mint)) => batch.record_mint(emitter, index, &mint)?,
+            Some(PairEvent::Burn(burn)) => batch.record_burn(emitter, index, &burn)?,
+            Some(PairEvent::Other(_)) | None => {}
+        }
+    } else if let Some(pair) = batch.pair_of_lp_token(emitter)? {
+        if let Some(TokenEvent::Transfer(transfer)) = TokenEvent::decode(event, &artifacts.lp_token)
+        {
+            batch.transfer_lp(pair, &transfer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use scale::Encode;
+    use uniswap_client::Artifact;
+
+    use super::*;
+    use crate::{Error, LiquidityEvent, Position, ReservesRecord, Volume};
+
+    const FACTORY: [u8; 32] = [0xfa; 32];
+    const TOKEN_A: [u8; 32] = [1; 32];
+    const TOKEN_B: [u8; 32] = [2; 32];
+    const PAIR: [u8; 32] = [3; 32];
+    const LP_TOKEN: [u8; 32] = [4; 32];
+    const ALICE: [u8; 32] = [5; 32];
+    const BOB: [u8; 32] = [6; 32];
+
+    /// A node stand-in: blocks of events pushed by the test, finalized as soon as pushed.
+    #[derive(Default)]
+    struct LocalChain {
+        blocks: Vec<Vec<ContractEvent>>,
+    }
+
+    impl LocalChain {
+        fn push_block(&mut self, events: Vec<ContractEvent>) {
+            self.blocks.push(events);
+        }
+    }
+
+    impl Chain for LocalChain {
+        async fn finalized_head(&self) -> Result<u32> {
+            Ok(self.blocks.len() as u32)
+        }
+
+        /// Block 0 is genesis; pushed blocks are numbered from 1.
+        async fn contract_events(&self, number: u32) -> Result<Vec<ContractEvent>> {
+            match number {
+                0 => Ok(Vec::new()),
+                _ => self
+                    .blocks
+                    .get(number as usize - 1)
+                    .cloned()
+                    .ok_or(Error::MissingBlock(number)),
+            }
+        }
+
+        async fn lp_token(&self, pair: AccountId) -> Result<AccountId> {
+            assert_eq!(pair, AccountId::from(PAIR));
+            Ok(LP_TOKEN.into())
+        }
+    }
+
+    fn artifact(name: &str, events: &[&str]) -> Artifact {
+        let events: Vec<_> = events
+            .iter()
+            .map(|label| serde_json::json!({ "label": label }))
+            .collect();
+        let metadata = serde_json::from_value(serde_json::json!({
+            "source": {
+                "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "language": "ink! 4.2.1",
+                "compiler": "rustc 1.70.0"
+            },
+            "contract": { "name": name, "version": "0.1.0", "authors": ["Bolaji Ahmad"] },
+            "spec": { "constructors": [], "messages": [], "events": events },
+            "version": "4"
+        }))
+        .unwrap();
+        Artifact::from_metadata(metadata).unwrap()
+    }
+
+    fn artifacts() -> Artifacts {
+        Artifacts {
+            core: artifact(
+                "core",
+                &[
+                    "PairCreated",
+                    "FeeToChanged",
+                    "FeeToSetterChanged",
+                    "PairCodeHashChanged",
+                    "Paused",
+                    "Unpaused",
+                ],
+            ),
+            pair: artifact("pair", &["Sync", "Mint", "Burn", "Swap"]),
+            lp_token: artifact("lp_token", &["TransferEvent", "ApprovalEvent"]),
+            psp22token: artifact("psp22token", &["TransferEvent", "ApprovalEvent"]),
+            router: artifact("router", &[]),
+            wnative: artifact("wnative", &["TransferEvent", "ApprovalEvent"]),
+        }
+    }
+
+    fn event(emitter: [u8; 32], index: u8, fields: impl Encode) -> ContractEvent {
+        let mut data = vec![index];
+        fields.encode_to(&mut data);
+        ContractEvent {
+            emitter: emitter.into(),
+            data,
+        }
+    }
+
+    fn pair_created() -> ContractEvent {
+        let fields = (
+            Some(AccountId::from(TOKEN_A)),
+            Some(AccountId::from(TOKEN_B)),
+            Some(AccountId::from(PAIR)),
+            0u32,
+            false,
+            50u32,
+            false,
+        );
+        event(FACTORY, 0, fields)
+    }
+
+    fn sync(reserve_0: u128, reserve_1: u128) -> ContractEvent {
+        event(PAIR, 0, (reserve_0, reserve_1, AccountId::from(PAIR)))
+    }
+
+    fn lp_transfer(from: Option<[u8; 32]>, to: Option<[u8; 32]>, value: u128) -> ContractEvent {
+        let transfer = (from.map(AccountId::from), to.map(AccountId::from), value);
+        event(LP_TOKEN, 0, transfer)
+    }
+
+    /// Alice adds liquidity, Bob swaps token A for B, then Alice removes half.
+    fn trade(chain: &mut LocalChain) {
+        chain.push_block(vec![pair_created()]);
+        chain.push_block(vec![
+            lp_transfer(None, Some([0; 32]), 1_000),
+            lp_transfer(None, Some(ALICE), 9_000),
+            sync(10_000, 10_000),
+            event(
+                PAIR,
+                1,
+                (
+                    Some(AccountId::from(ALICE)),
+                    10_000u128,
+                    10_000u128,
+                    AccountId::from(ALICE),
+                    9_000u128,
+                ),
+            ),
+        ]);
+        chain.push_block(vec![
+            sync(11_000, 9_094),
+            event(
+                PAIR,
+                3,
+                (
+                    Some(AccountId::from(BOB)),
+                    1_000u128,
+                    0u128,
+                    0u128,
+                    906u128,
+                    AccountId::from(BOB),
+                    11_000u128,
+                    9_094u128,
+                ),
+            ),
+        ]);
+        chain.push_block(vec![
+            lp_transfer(Some(ALICE), Some(PAIR), 4_500),
+            lp_transfer(Some(PAIR), None, 4_500),
+            sync(6_050, 5_002),
+            event(
+                PAIR,
+                2,
+                (
+                    Some(AccountId::from(ALICE)),
+                    4_950u128,
+                    4_092u128,
+                    AccountId::from(ALICE),
+                    4_500u128,
+                ),
+            ),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn builds_pool_state_from_events() {
+        let mut chain = LocalChain::default();
+        trade(&mut chain);
+        let store = Store::open_in_memory().unwrap();
+        let mut indexer = Indexer::new(chain, store, FACTORY.into(), artifacts());
+
+        indexer.replay(0..=4).await.unwrap();
+        let store = indexer.store();
+
+        assert_eq!(store.cursor().unwrap(), Some(4));
+        let pairs = store.pairs().unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].address, PAIR.into());
+        assert_eq!(pairs[0].lp_token, LP_TOKEN.into());
+        assert_eq!(
+            (pairs[0].token_0, pairs[0].token_1),
+            (TOKEN_A.into(), TOKEN_B.into())
+        );
+        assert_eq!(pairs[0].created_at, 1);
+        assert_eq!((pairs[0].reserve_0, pairs[0].reserve_1), (6_050, 5_002));
+
+        assert_eq!(
+            store.reserves_history(PAIR.into(), 3..=4).unwrap(),
+            [
+                ReservesRecord {
+                    block: 3,
+                    reserve_0: 11_000,
+                    reserve_1: 9_094,
+                },
+                ReservesRecord {
+                    block: 4,
+                    reserve_0: 6_050,
+                    reserve_1: 5_002,
+                },
+            ]
+        );
+        assert_eq!(
+            store.volume(PAIR.into(), 0..=4).unwrap(),
+            Volume {
+                swaps: 1,
+                amount_0_in: 1_000,
+                amount_1_in: 0,
+                amount_0_out: 0,
+                amount_1_out: 906,
+            }
+        );
+        assert_eq!(store.volume(PAIR.into(), 4..=4).unwrap(), Volume::default());
+        assert_eq!(
+            store.liquidity_events(PAIR.into()).unwrap(),
+            [
+                LiquidityEvent::Mint {
+                    block: 2,
+                    owner: Some(ALICE.into()),
+                    to: ALICE.into(),
+                    amount_0: 10_000,
+                    amount_1: 10_000,
+                    liquidity: 9_000,
+                },
+                LiquidityEvent::Burn {
+                    block: 4,
+                    sender: Some(ALICE.into()),
+                    to: ALICE.into(),
+                    amount_0: 4_950,
+                    amount_1: 4_092,
+                    liquidity: 4_500,
+                },
+            ]
+        );
+        assert_eq!(
+            store.holders(PAIR.into()).unwrap(),
+            [
+                Position {
+                    pair: PAIR.into(),
+                    owner: ALICE.into(),
+                    liquidity: 4_500,
+                },
+                Position {
+                    pair: PAIR.into(),
+                    owner: [0; 32].into(),
+                    liquidity: 1_000,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_contracts_it_does_not_know() {
+        let mut chain = LocalChain::default();
+        // A pair-shaped event before the factory created the pair, and a token transfer.
+        chain.push_block(vec![
+            sync(1, 1),
+            event(
+                TOKEN_A,
+                0,
+                (None::<AccountId>, Some(AccountId::from(ALICE)), 5u128),
+            ),
+        ]);
+        let mut indexer = Indexer::new(
+            chain,
+            Store::open_in_memory().unwrap(),
+            FACTORY.into(),
+            artifacts(),
+        );
+
+        indexer.replay(1..=1).await.unwrap();
+
+        assert!(indexer.store().pairs().unwrap().is_empty());
+        assert!(indexer.store().positions(ALICE.into()).unwrap().is_empty());
+        assert_eq!(indexer.store().cursor().unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_cursor() {
+        let mut chain = LocalChain::default();
+        trade(&mut chain);
+        let mut indexer = Indexer::new(
+            chain,
+            Store::open_in_memory().unwrap(),
+            FACTORY.into(),
+            artifacts(),
+        );
+
+        indexer.replay(0..=2).await.unwrap();
+        // Replaying indexed blocks would insert the pair twice.
+        indexer.replay(0..=4).await.unwrap();
+
+        assert_eq!(indexer.store().cursor().unwrap(), Some(4));
+        assert_eq!(indexer.store().volume(PAIR.into(), 0..=4).unwrap().swaps, 1);
+    }
+
+    #[tokio::test]
+    async fn a_failing_block_is_not_half_applied() {
+        let mut chain = LocalChain::default();
+        chain.push_block(vec![pair_created()]);
+        chain.push_block(vec![
+            sync(5, 5),
+            // Alice never received any LP tokens.
+            lp_transfer(Some(ALICE), Some(BOB), 1),
+        ]);
+        let mut indexer = Indexer::new(
+            chain,
+            Store::open_in_memory().unwrap(),
+            FACTORY.into(),
+            artifacts(),
+        );
+
+        let result = indexer.replay(1..=2).await;
+
+        assert!(matches!(result, Err(Error::Inconsistent(_))));
+        assert_eq!(indexer.store().cursor().unwrap(), Some(1));
+        assert!(indexer
+            .store()
+            .reserves_history(PAIR.into(), 0..=2)
+            .unwrap()
+            .is_empty());
+    }
+}
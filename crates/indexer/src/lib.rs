@@ -0,0 +1,95 @@
+//! Indexes the AMM from its events: pairs from the factory's `PairCreated`, reserves from
+//! `Sync`, volume from `Swap`, liquidity from `Mint`/`Burn` and LP positions from the LP
+//! tokens' transfers, kept in a SQLite database that can be queried while indexing runs.
+
+pub mod chain;
+pub mod indexer;
+pub mod store;
+
+use std::fmt;
+
+use ink::primitives::AccountId;
+
+pub use chain::{Chain, NodeChain};
+pub use indexer::Indexer;
+pub use store::{LiquidityEvent, PairRecord, Position, ReservesRecord, Store, SwapRecord, Volume};
+
+#[derive(Debug)]
+pub enum Error {
+    Sqlite(rusqlite::Error),
+    Client(uniswap_client::Error),
+    /// The node has no block with this number yet.
+    MissingBlock(u32),
+    /// The events disagree with what is stored, e.g. an LP transfer exceeding a balance.
+    Inconsistent(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Sqlite(err) => write!(f, "database error: {err}"),
+            Error::Client(err) => write!(f, "{err}"),
+            Error::MissingBlock(number) => write!(f, "no block #{number}"),
+            Error::Inconsistent(reason) => write!(f, "inconsistent events: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Sqlite(error)
+    }
+}
+
+impl From<uniswap_client::Error> for Error {
+    fn from(error: uniswap_client::Error) -> Self {
+        Error::Client(error)
+    }
+}
+
+impl From<subxt::Error> for Error {
+    fn from(error: subxt::Error) -> Self {
+        Error::Client(error.into())
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Formats `account` as `0x` and 64 hex digits, the form addresses are stored in.
+pub fn to_hex(account: &AccountId) -> String {
+    let bytes: &[u8] = account.as_ref();
+    let digits: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{digits}")
+}
+
+/// Parses an address written as by [`to_hex`].
+pub fn parse_account(hex: &str) -> Option<AccountId> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    if digits.len() != 64 || !digits.is_ascii() {
+        return None;
+    }
+    let mut account = [0u8; 32];
+    for (i, byte) in account.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(AccountId::from(account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_round_trip_through_hex() {
+        let account = AccountId::from([0xab; 32]);
+        let hex = to_hex(&account);
+
+        assert_eq!(hex.len(), 66);
+        assert_eq!(parse_account(&hex), Some(account));
+        assert_eq!(parse_account(&hex[2..]), Some(account));
+        assert_eq!(parse_account("0xab"), None);
+        assert_eq!(parse_account(&hex.replace('a', "g")), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! `uniswap-indexer run` indexes a node into a SQLite database; the other subcommands query
+//! it, and can run while `run` does.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use ink::primitives::AccountId;
+use uniswap_client::{Artifacts, Client};
+use uniswap_indexer::{parse_account, to_hex, Indexer, NodeChain, Store};
+
+#[derive(Parser)]
+#[command(about = "Indexes the AMM's pairs, reserves, volume and LP positions")]
+struct Cli {
+    /// SQLite database, created if missing.
+    #[arg(long, default_value = "amm.sqlite")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Indexes blocks, resuming after the last one indexed.
+    Run {
+        #[arg(long, default_value = "ws://127.0.0.1:9944")]
+        url: String,
+        /// Address of `UniswapCore`, as 0x-prefixed hex.
+        #[arg(long, value_parser = account)]
+        factory: AccountId,
+        /// Directory holding a `<name>/<name>.contract` bundle for each contract, such as
+        /// `core/core.contract`.
+        #[arg(long, default_value = "target/ink")]
+        artifacts: PathBuf,
+        /// First block to index; use the factory's deployment block to skip history.
+        #[arg(long, default_value_t = 0)]
+        from: u32,
+        /// Last block to index. Without it, follows the finalized head.
+        #[arg(long)]
+        to: Option<u32>,
+        /// Seconds between checks for new finalized blocks.
+        #[arg(long, default_value_t = 6)]
+        poll: u64,
+    },
+    /// Lists the indexed pairs with their current reserves.
+    Pairs,
+    /// Prints the reserves of a pair after each `Sync` in a block range.
+    Reserves {
+        #[arg(value_parser = account)]
+        pair: AccountId,
+        #[arg(long, default_value_t = 0)]
+        from: u32,
+        #[arg(long, default_value_t = u32::MAX)]
+        to: u32,
+    },
+    /// Sums the swaps of a pair over a block range.
+    Volume {
+        #[arg(value_parser = account)]
+        pair: AccountId,
+        #[arg(long, default_value_t = 0)]
+        from: u32,
+        #[arg(long, default_value_t = u32::MAX)]
+        to: u32,
+    },
+    /// Lists the LP positions of an account.
+    Positions {
+        #[arg(value_parser = account)]
+        owner: AccountId,
+    },
+}
+
+fn account(hex: &str) -> Result<AccountId, String> {
+    parse_account(hex).ok_or_else(|| format!("`{hex}` is not a 0x-prefixed 32 byte address"))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let store = Store::open(&cli.db)?;
+    match cli.command {
+        Command::Run {
+            url,
+            factory,
+            artifacts,
+            from,
+            to,
+            poll,
+        } => {
+            let artifacts = Artifacts::load(artifacts)?;
+            // Only queries are made, so the signer is irrelevant.
+            let client =
+                Client::connect(&url, subxt_signer::sr25519::dev::alice(), artifacts.clone())
+                    .await?;
+            let mut indexer = Indexer::new(NodeChain::new(client), store, factory, artifacts);
+            match to {
+                Some(to) => indexer.replay(from..=to).await?,
+                None => indexer.follow(from, Duration::from_secs(poll)).await?,
+            }
+        }
+        Command::Pairs => {
+            for pair in store.pairs()? {
+                println!(
+                    "#{} {} {}/{} reserves {} {} lp {}",
+                    pair.pair_index,
+                    to_hex(&pair.address),
+                    to_hex(&pair.token_0),
+                    to_hex(&pair.token_1),
+                    pair.reserve_0,
+                    pair.reserve_1,
+                    to_hex(&pair.lp_token)
+                );
+            }
+        }
+        Command::Reserves { pair, from, to } => {
+            for reserves in store.reserves_history(pair, from..=to)? {
+                println!(
+                    "{} {} {}",
+                    reserves.block, reserves.reserve_0, reserves.reserve_1
+                );
+            }
+        }
+        Command::Volume { pair, from, to } => {
+            let volume = store.volume(pair, from..=to)?;
+            println!("swaps {}", volume.swaps);
+            println!("in {} {}", volume.amount_0_in, volume.amount_1_in);
+            println!("out {} {}", volume.amount_0_out, volume.amount_1_out);
+        }
+        Command::Positions { owner } => {
+            for position in store.positions(owner)? {
+                println!("{} {}", to_hex(&position.pair), position.liquidity);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,723 @@
+//! The SQLite store. Addresses are kept as `0x` hex and balances as decimal text, since
+//! SQLite integers stop at 64 bits; sums over balances are therefore taken in Rust.
+
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use ink::primitives::AccountId;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use uniswap_client::events::{Burn, Mint, Swap, Sync, Transfer};
+use uniswap_client::Balance;
+
+use crate::{parse_account, to_hex, Error, Result};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    block INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pairs (
+    address TEXT PRIMARY KEY,
+    token_0 TEXT NOT NULL,
+    token_1 TEXT NOT NULL,
+    lp_token TEXT NOT NULL UNIQUE,
+    pair_index INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    reserve_0 TEXT NOT NULL DEFAULT '0',
+    reserve_1 TEXT NOT NULL DEFAULT '0'
+);
+CREATE TABLE IF NOT EXISTS reserves (
+    pair TEXT NOT NULL REFERENCES pairs (address),
+    block INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    reserve_0 TEXT NOT NULL,
+    reserve_1 TEXT NOT NULL,
+    PRIMARY KEY (pair, block, event_index)
+);
+CREATE TABLE IF NOT EXISTS swaps (
+    pair TEXT NOT NULL REFERENCES pairs (address),
+    block INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    sender TEXT,
+    recipient TEXT NOT NULL,
+    amount_0_in TEXT NOT NULL,
+    amount_1_in TEXT NOT NULL,
+    amount_0_out TEXT NOT NULL,
+    amount_1_out TEXT NOT NULL,
+    PRIMARY KEY (pair, block, event_index)
+);
+CREATE TABLE IF NOT EXISTS liquidity (
+    pair TEXT NOT NULL REFERENCES pairs (address),
+    block INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('mint', 'burn')),
+    sender TEXT,
+    recipient TEXT NOT NULL,
+    amount_0 TEXT NOT NULL,
+    amount_1 TEXT NOT NULL,
+    liquidity TEXT NOT NULL,
+    PRIMARY KEY (pair, block, event_index)
+);
+CREATE TABLE IF NOT EXISTS positions (
+    pair TEXT NOT NULL REFERENCES pairs (address),
+    owner TEXT NOT NULL,
+    liquidity TEXT NOT NULL,
+    PRIMARY KEY (pair, owner)
+);
+";
+
+/// An address column.
+struct Address(AccountId);
+
+impl ToSql for Address {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(to_hex(&self.0)))
+    }
+}
+
+impl FromSql for Address {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        parse_account(value.as_str()?)
+            .map(Address)
+            .ok_or_else(|| FromSqlError::Other("not an address".into()))
+    }
+}
+
+/// A balance column.
+struct Amount(Balance);
+
+impl ToSql for Amount {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.to_string()))
+    }
+}
+
+impl FromSql for Amount {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map(Amount)
+            .map_err(|err| FromSqlError::Other(Box::new(err)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairRecord {
+    pub address: AccountId,
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    pub lp_token: AccountId,
+    pub pair_index: u32,
+    /// Block the pair was created in.
+    pub created_at: u32,
+    /// Reserves as of the last indexed `Sync`.
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+}
+
+impl PairRecord {
+    const COLUMNS: &'static str =
+        "address, token_0, token_1, lp_token, pair_index, created_at, reserve_0, reserve_1";
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            address: row.get::<_, Address>(0)?.0,
+            token_0: row.get::<_, Address>(1)?.0,
+            token_1: row.get::<_, Address>(2)?.0,
+            lp_token: row.get::<_, Address>(3)?.0,
+            pair_index: row.get(4)?,
+            created_at: row.get(5)?,
+            reserve_0: row.get::<_, Amount>(6)?.0,
+            reserve_1: row.get::<_, Amount>(7)?.0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservesRecord {
+    pub block: u32,
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapRecord {
+    pub block: u32,
+    pub sender: Option<AccountId>,
+    pub to: AccountId,
+    pub amount_0_in: Balance,
+    pub amount_1_in: Balance,
+    pub amount_0_out: Balance,
+    pub amount_1_out: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiquidityEvent {
+    Mint {
+        block: u32,
+        owner: Option<AccountId>,
+        to: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        /// LP tokens minted to `to`.
+        liquidity: Balance,
+    },
+    Burn {
+        block: u32,
+        sender: Option<AccountId>,
+        to: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        /// LP tokens burned.
+        liquidity: Balance,
+    },
+}
+
+/// Swapped amounts over a block range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Volume {
+    pub swaps: u64,
+    pub amount_0_in: Balance,
+    pub amount_1_in: Balance,
+    pub amount_0_out: Balance,
+    pub amount_1_out: Balance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub pair: AccountId,
+    pub owner: AccountId,
+    pub liquidity: Balance,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// The last block indexed, if any.
+    pub fn cursor(&self) -> Result<Option<u32>> {
+        Ok(self
+            .conn
+            .query_row("SELECT block FROM cursor WHERE id = 0", [], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Starts recording `block`. Nothing is visible until [`Batch::commit`], which also
+    /// moves the cursor to `block`.
+    pub(crate) fn begin(&mut self, block: u32) -> Result<Batch<'_>> {
+        Ok(Batch {
+            tx: self.conn.transaction()?,
+            block,
+        })
+    }
+
+    /// Pairs in creation order.
+    pub fn pairs(&self) -> Result<Vec<PairRecord>> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {} FROM pairs ORDER BY pair_index",
+            PairRecord::COLUMNS
+        ))?;
+        let pairs = statement
+            .query_map([], PairRecord::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(pairs)
+    }
+
+    pub fn pair(&self, address: AccountId) -> Result<Option<PairRecord>> {
+        pair(&self.conn, address)
+    }
+
+    /// Reserves after each `Sync` of `pair` in `blocks`, oldest first.
+    pub fn reserves_history(
+        &self,
+        pair: AccountId,
+        blocks: RangeInclusive<u32>,
+    ) -> Result<Vec<ReservesRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT block, reserve_0, reserve_1 FROM reserves
+             WHERE pair = ?1 AND block BETWEEN ?2 AND ?3
+             ORDER BY block, event_index",
+        )?;
+        let history = statement
+            .query_map(
+                params![Address(pair), blocks.start(), blocks.end()],
+                |row| {
+                    Ok(ReservesRecord {
+                        block: row.get(0)?,
+                        reserve_0: row.get::<_, Amount>(1)?.0,
+                        reserve_1: row.get::<_, Amount>(2)?.0,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(history)
+    }
+
+    /// Swaps on `pair` in `blocks`, oldest first.
+    pub fn swaps(&self, pair: AccountId, blocks: RangeInclusive<u32>) -> Result<Vec<SwapRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT block, sender, recipient, amount_0_in, amount_1_in, amount_0_out, amount_1_out
+             FROM swaps WHERE pair = ?1 AND block BETWEEN ?2 AND ?3
+             ORDER BY block, event_index",
+        )?;
+        let swaps = statement
+            .query_map(
+                params![Address(pair), blocks.start(), blocks.end()],
+                |row| {
+                    Ok(SwapRecord {
+                        block: row.get(0)?,
+                        sender: row.get::<_, Option<Address>>(1)?.map(|sender| sender.0),
+                        to: row.get::<_, Address>(2)?.0,
+                        amount_0_in: row.get::<_, Amount>(3)?.0,
+                        amount_1_in: row.get::<_, Amount>(4)?.0,
+                        amount_0_out: row.get::<_, Amount>(5)?.0,
+                        amount_1_out: row.get::<_, Amount>(6)?.0,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(swaps)
+    }
+
+    /// Totals of the swaps on `pair` in `blocks`.
+    pub fn volume(&self, pair: AccountId, blocks: RangeInclusive<u32>) -> Result<Volume> {
+        let overflow = || Error::Inconsistent("volume overflows a balance".into());
+        self.swaps(pair, blocks)?
+            .iter()
+            .try_fold(Volume::default(), |volume, swap| {
+                Ok(Volume {
+                    swaps: volume.swaps + 1,
+                    amount_0_in: volume
+                        .amount_0_in
+                        .checked_add(swap.amount_0_in)
+                        .ok_or_else(overflow)?,
+                    amount_1_in: volume
+                        .amount_1_in
+                        .checked_add(swap.amount_1_in)
+                        .ok_or_else(overflow)?,
+                    amount_0_out: volume
+                        .amount_0_out
+                        .checked_add(swap.amount_0_out)
+                        .ok_or_else(overflow)?,
+                    amount_1_out: volume
+                        .amount_1_out
+                        .checked_add(swap.amount_1_out)
+                        .ok_or_else(overflow)?,
+                })
+            })
+    }
+
+    /// Mints and burns on `pair`, oldest first.
+    pub fn liquidity_events(&self, pair: AccountId) -> Result<Vec<LiquidityEvent>> {
+        let mut statement = self.conn.prepare(
+            "SELECT kind, block, sender, recipient, amount_0, amount_1, liquidity FROM liquidity
+             WHERE pair = ?1 ORDER BY block, event_index",
+        )?;
+        let events = statement
+            .query_map(params![Address(pair)], |row| {
+                let kind: String = row.get(0)?;
+                let block = row.get(1)?;
+                let sender = row.get::<_, Option<Address>>(2)?.map(|sender| sender.0);
+                let to = row.get::<_, Address>(3)?.0;
+                let amount_0 = row.get::<_, Amount>(4)?.0;
+                let amount_1 = row.get::<_, Amount>(5)?.0;
+                let liquidity = row.get::<_, Amount>(6)?.0;
+                Ok(match kind.as_str() {
+                    "mint" => LiquidityEvent::Mint {
+                        block,
+                        owner: sender,
+                        to,
+                        amount_0,
+                        amount_1,
+                        liquidity,
+                    },
+                    _ => LiquidityEvent::Burn {
+                        block,
+                        sender,
+                        to,
+                        amount_0,
+                        amount_1,
+                        liquidity,
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(events)
+    }
+
+    /// LP balances of `owner`, across pairs.
+    pub fn positions(&self, owner: AccountId) -> Result<Vec<Position>> {
+        self.query_positions("owner = ?1 ORDER BY pair", owner)
+    }
+
+    /// LP balances in `pair`, largest first.
+    pub fn holders(&self, pair: AccountId) -> Result<Vec<Position>> {
+        let mut holders = self.query_positions("pair = ?1", pair)?;
+        holders.sort_by(|a, b| b.liquidity.cmp(&a.liquidity).then(a.owner.cmp(&b.owner)));
+        Ok(holders)
+    }
+
+    fn query_positions(&self, filter: &str, account: AccountId) -> Result<Vec<Position>> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT pair, owner, liquidity FROM positions WHERE {filter}"
+        ))?;
+        let positions = statement
+            .query_map(params![Address(account)], |row| {
+                Ok(Position {
+                    pair: row.get::<_, Address>(0)?.0,
+                    owner: row.get::<_, Address>(1)?.0,
+                    liquidity: row.get::<_, Amount>(2)?.0,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(positions)
+    }
+}
+
+fn pair(conn: &Connection, address: AccountId) -> Result<Option<PairRecord>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM pairs WHERE address = ?1",
+                PairRecord::COLUMNS
+            ),
+            params![Address(address)],
+            PairRecord::from_row,
+        )
+        .optional()?)
+}
+
+/// The writes of one block, applied atomically.
+pub(crate) struct Batch<'a> {
+    tx: Transaction<'a>,
+    block: u32,
+}
+
+impl Batch<'_> {
+    pub(crate) fn is_pair(&self, address: AccountId) -> Result<bool> {
+        Ok(pair(&self.tx, address)?.is_some())
+    }
+
+    /// The pair whose LP token is `lp_token`, if any.
+    pub(crate) fn pair_of_lp_token(&self, lp_token: AccountId) -> Result<Option<AccountId>> {
+        Ok(self
+            .tx
+            .query_row(
+                "SELECT address FROM pairs WHERE lp_token = ?1",
+                params![Address(lp_token)],
+                |row| row.get::<_, Address>(0),
+            )
+            .optional()?
+            .map(|address| address.0))
+    }
+
+    pub(crate) fn insert_pair(
+        &self,
+        address: AccountId,
+        tokens: (AccountId, AccountId),
+        lp_token: AccountId,
+        pair_index: u32,
+    ) -> Result<()> {
+        self.tx.execute(
+            "INSERT INTO pairs (address, token_0, token_1, lp_token, pair_index, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Address(address),
+                Address(tokens.0),
+                Address(tokens.1),
+                Address(lp_token),
+                pair_index,
+                self.block
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn record_sync(&self, pair: AccountId, event_index: u32, sync: &Sync) -> Result<()> {
+        self.tx.execute(
+            "INSERT INTO reserves (pair, block, event_index, reserve_0, reserve_1)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                Address(pair),
+                self.block,
+                event_index,
+                Amount(sync.reserve_0),
+                Amount(sync.reserve_1)
+            ],
+        )?;
+        self.tx.execute(
+            "UPDATE pairs SET reserve_0 = ?2, reserve_1 = ?3 WHERE address = ?1",
+            params![
+                Address(pair),
+                Amount(sync.reserve_0),
+                Amount(sync.reserve_1)
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn record_swap(&self, pair: AccountId, event_index: u32, swap: &Swap) -> Result<()> {
+        self.tx.execute(
+            "INSERT INTO swaps (pair, block, event_index, sender, recipient,
+                                amount_0_in, amount_1_in, amount_0_out, amount_1_out)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                Address(pair),
+                self.block,
+                event_index,
+                swap.sender.map(Address),
+                Address(swap.to),
+                Amount(swap.amount_0_in),
+                Amount(swap.amount_1_in),
+                Amount(swap.amount_0_out),
+                Amount(swap.amount_1_out)
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn record_mint(&self, pair: AccountId, event_index: u32, mint: &Mint) -> Result<()> {
+        let event = LiquidityEvent::Mint {
+            block: self.block,
+            owner: mint.owner,
+            to: mint.to,
+            amount_0: mint.amount_0,
+            amount_1: mint.amount_1,
+            liquidity: mint.liquidity,
+        };
+        self.record_liquidity(pair, event_index, &event)
+    }
+
+    pub(crate) fn record_burn(&self, pair: AccountId, event_index: u32, burn: &Burn) -> Result<()> {
+        let event = LiquidityEvent::Burn {
+            block: self.block,
+            sender: burn.sender,
+            to: burn.to,
+            amount_0: burn.amount_0,
+            amount_1: burn.amount_1,
+            liquidity: burn.liquidity,
+        };
+        self.record_liquidity(pair, event_index, &event)
+    }
+
+    fn record_liquidity(
+        &self,
+        pair: AccountId,
+        event_index: u32,
+        event: &LiquidityEvent,
+    ) -> Result<()> {
+        let (kind, sender, to, amount_0, amount_1, liquidity) = match *event {
+            LiquidityEvent::Mint {
+                owner,
+                to,
+                amount_0,
+                amount_1,
+                liquidity,
+                ..
+            } => ("mint", owner, to, amount_0, amount_1, liquidity),
+            LiquidityEvent::Burn {
+                sender,
+                to,
+                amount_0,
+                amount_1,
+                liquidity,
+                ..
+            } => ("burn", sender, to, amount_0, amount_1, liquidity),
+        };
+        self.tx.execute(
+            "INSERT INTO liquidity
+                 (pair, block, event_index, kind, sender, recipient, amount_0, amount_1, liquidity)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                Address(pair),
+                self.block,
+                event_index,
+                kind,
+                sender.map(Address),
+                Address(to),
+                Amount(amount_0),
+                Amount(amount_1),
+                Amount(liquidity)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Moves LP tokens of `pair` between positions. Mints come from `None` and burns go to
+    /// it; empty positions are dropped.
+    pub(crate) fn transfer_lp(&self, pair: AccountId, transfer: &Transfer) -> Result<()> {
+        if let Some(from) = transfer.from {
+            let balance = self.liquidity(pair, from)?;
+            let balance = balance.checked_sub(transfer.value).ok_or_else(|| {
+                Error::Inconsistent(format!(
+                    "{} moves {} LP tokens of {} but holds {balance}",
+                    to_hex(&from),
+                    transfer.value,
+                    to_hex(&pair)
+                ))
+            })?;
+            self.set_liquidity(pair, from, balance)?;
+        }
+        if let Some(to) = transfer.to {
+            let balance = self.liquidity(pair, to)?;
+            let balance = balance
+                .checked_add(transfer.value)
+                .ok_or_else(|| Error::Inconsistent("LP balance overflows".into()))?;
+            self.set_liquidity(pair, to, balance)?;
+        }
+        Ok(())
+    }
+
+    fn liquidity(&self, pair: AccountId, owner: AccountId) -> Result<Balance> {
+        Ok(self
+            .tx
+            .query_row(
+                "SELECT liquidity FROM positions WHERE pair = ?1 AND owner = ?2",
+                params![Address(pair), Address(owner)],
+                |row| row.get::<_, Amount>(0),
+            )
+            .optional()?
+            .map_or(0, |amount| amount.0))
+    }
+
+    fn set_liquidity(&self, pair: AccountId, owner: AccountId, liquidity: Balance) -> Result<()> {
+        if liquidity == 0 {
+            self.tx.execute(
+                "DELETE FROM positions WHERE pair = ?1 AND owner = ?2",
+                params![Address(pair), Address(owner)],
+            )?;
+        } else {
+            self.tx.execute(
+                "INSERT INTO positions (pair, owner, liquidity) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (pair, owner) DO UPDATE SET liquidity = excluded.liquidity",
+                params![Address(pair), Address(owner), Amount(liquidity)],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn commit(self) -> Result<()> {
+        self.tx.execute(
+            "INSERT INTO cursor (id, block) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET block = excluded.block",
+            params![self.block],
+        )?;
+        self.tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::from([byte; 32])
+    }
+
+    fn store_with_pair() -> Store {
+        let mut store = Store::open_in_memory().unwrap();
+        let batch = store.begin(1).unwrap();
+        batch
+            .insert_pair(account(10), (account(1), account(2)), account(11), 0)
+            .unwrap();
+        batch.commit().unwrap();
+        store
+    }
+
+    #[test]
+    fn balances_beyond_64_bits_survive() {
+        let mut store = store_with_pair();
+        let batch = store.begin(2).unwrap();
+        let sync = Sync {
+            reserve_0: u128::MAX,
+            reserve_1: 1 << 100,
+            pair: account(10),
+        };
+        batch.record_sync(account(10), 0, &sync).unwrap();
+        batch.commit().unwrap();
+
+        let pair = store.pair(account(10)).unwrap().unwrap();
+        assert_eq!((pair.reserve_0, pair.reserve_1), (u128::MAX, 1 << 100));
+        assert_eq!(
+            store.reserves_history(account(10), 0..=u32::MAX).unwrap(),
+            [ReservesRecord {
+                block: 2,
+                reserve_0: u128::MAX,
+                reserve_1: 1 << 100,
+            }]
+        );
+    }
+
+    #[test]
+    fn dropped_batches_leave_nothing_behind() {
+        let mut store = store_with_pair();
+        let batch = store.begin(2).unwrap();
+        let sync = Sync {
+            reserve_0: 1,
+            reserve_1: 1,
+            pair: account(10),
+        };
+        batch.record_sync(account(10), 0, &sync).unwrap();
+        drop(batch);
+
+        assert_eq!(store.cursor().unwrap(), Some(1));
+        assert!(store
+            .reserves_history(account(10), 0..=2)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn positions_follow_lp_transfers() {
+        let mut store = store_with_pair();
+        let transfer = |from: Option<u8>, to: Option<u8>, value| Transfer {
+            from: from.map(account),
+            to: to.map(account),
+            value,
+        };
+        let batch = store.begin(2).unwrap();
+        batch
+            .transfer_lp(account(10), &transfer(None, Some(20), 100))
+            .unwrap();
+        batch
+            .transfer_lp(account(10), &transfer(Some(20), Some(21), 40))
+            .unwrap();
+        batch
+            .transfer_lp(account(10), &transfer(Some(21), None, 40))
+            .unwrap();
+        assert!(matches!(
+            batch.transfer_lp(account(10), &transfer(Some(21), None, 1)),
+            Err(Error::Inconsistent(_))
+        ));
+        batch.commit().unwrap();
+
+        assert_eq!(
+            store.positions(account(20)).unwrap(),
+            [Position {
+                pair: account(10),
+                owner: account(20),
+                liquidity: 60,
+            }]
+        );
+        assert!(store.positions(account(21)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+//! Raw `pallet-contracts` extrinsics and `ContractsApi` dry-runs, mirroring what `ink_e2e`
+//! submits.
+
+use ink::primitives::{AccountId, Hash};
+use pallet_contracts_primitives::{ContractExecResult, ContractInstantiateResult};
+use scale::Encode;
+use subxt::blocks::ExtrinsicEvents;
+use subxt::utils::{AccountId32, MultiAddress};
+use subxt::PolkadotConfig;
+
+use crate::{Balance, Client, Error, Result};
+
+pub use payload::Weight;
+use payload::{Call, Determinism, InstantiateWithCode, UploadCode};
+
+/// Extrinsic arguments. Kept apart since the `EncodeAsType` derive expands to unqualified
+/// `Result` and `Error`.
+mod payload {
+    use scale::{Decode, Encode};
+    use subxt::ext::scale_encode;
+    use subxt::utils::{AccountId32, MultiAddress};
+
+    use crate::Balance;
+
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode, scale_encode::EncodeAsType,
+    )]
+    #[encode_as_type(crate_path = "subxt::ext::scale_encode")]
+    pub struct Weight {
+        #[codec(compact)]
+        pub ref_time: u64,
+        #[codec(compact)]
+        pub proof_size: u64,
+    }
+
+    impl From<sp_weights::Weight> for Weight {
+        fn from(weight: sp_weights::Weight) -> Self {
+            Self {
+                ref_time: weight.ref_time(),
+                proof_size: weight.proof_size(),
+            }
+        }
+    }
+
+    #[derive(Debug, Encode, scale_encode::EncodeAsType)]
+    #[encode_as_type(crate_path = "subxt::ext::scale_encode")]
+    pub(super) struct Call {
+        pub(super) dest: MultiAddress<AccountId32, ()>,
+        #[codec(compact)]
+        pub(super) value: Balance,
+        pub(super) gas_limit: Weight,
+        pub(super) storage_deposit_limit: Option<Balance>,
+        pub(super) data: Vec<u8>,
+    }
+
+    #[derive(Debug, Encode, scale_encode::EncodeAsType)]
+    #[encode_as_type(crate_path = "subxt::ext::scale_encode")]
+    pub(super) struct InstantiateWithCode {
+        #[codec(compact)]
+        pub(super) value: Balance,
+        pub(super) gas_limit: Weight,
+        pub(super) storage_deposit_limit: Option<Balance>,
+        pub(super) code: Vec<u8>,
+        pub(super) data: Vec<u8>,
+        pub(super) salt: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, Copy, Encode, scale_encode::EncodeAsType)]
+    #[encode_as_type(crate_path = "subxt::ext::scale_encode")]
+    pub(super) enum Determinism {
+        Enforced,
+    }
+
+    #[derive(Debug, Encode, scale_encode::EncodeAsType)]
+    #[encode_as_type(crate_path = "subxt::ext::scale_encode")]
+    pub(super) struct UploadCode {
+        pub(super) code: Vec<u8>,
+        pub(super) storage_deposit_limit: Option<Balance>,
+        pub(super) determinism: Determinism,
+    }
+}
+
+/// Arguments of `ContractsApi_call`.
+#[derive(Encode)]
+struct CallRequest {
+    origin: AccountId32,
+    dest: AccountId32,
+    value: Balance,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<Balance>,
+    input_data: Vec<u8>,
+}
+
+/// Arguments of `ContractsApi_instantiate`.
+#[derive(Encode)]
+struct InstantiateRequest {
+    origin: AccountId32,
+    value: Balance,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<Balance>,
+    code: Code,
+    data: Vec<u8>,
+    salt: Vec<u8>,
+}
+
+#[derive(Encode)]
+enum Code {
+    Upload(Vec<u8>),
+}
+
+/// What a dry-run returned.
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    pub gas_required: Weight,
+    pub data: Vec<u8>,
+}
+
+impl Client {
+    /// Runs `input` against `contract` without submitting anything.
+    pub async fn dry_run(&self, contract: AccountId, input: Vec<u8>) -> Result<DryRun> {
+        let request = CallRequest {
+            origin: self.origin(),
+            dest: to_subxt(contract),
+            value: 0,
+            gas_limit: None,
+            storage_deposit_limit: None,
+            input_data: input,
+        };
+        let result: ContractExecResult<Balance, ()> = self
+            .api
+            .rpc()
+            .state_call("ContractsApi_call", Some(&request.encode()), None)
+            .await?;
+
+        let debug = String::from_utf8_lossy(&result.debug_message).into_owned();
+        match result.result {
+            Ok(returned) if !returned.did_revert() => Ok(DryRun {
+                gas_required: result.gas_required.into(),
+                data: returned.data,
+            }),
+            Ok(returned) => Err(Error::Reverted(format!(
+                "{debug} (output 0x{})",
+                hex(&returned.data)
+            ))),
+            Err(err) => Err(Error::Dispatch(format!("{err:?} {debug}"))),
+        }
+    }
+
+    /// Submits `input` to `contract`, waiting until it is in a block.
+    pub async fn submit_call(
+        &self,
+        contract: AccountId,
+        input: Vec<u8>,
+        gas_limit: Weight,
+    ) -> Result<ExtrinsicEvents<PolkadotConfig>> {
+        let call = Call {
+            dest: MultiAddress::Id(to_subxt(contract)),
+            value: 0,
+            gas_limit,
+            storage_deposit_limit: None,
+            data: input,
+        };
+        self.submit(subxt::tx::Payload::new("Contracts", "call", call).unvalidated())
+            .await
+    }
+
+    /// Uploads `code` and returns its hash. Uploading code already on chain is a no-op.
+    pub async fn upload_code(&self, code: &[u8]) -> Result<Hash> {
+        let upload = UploadCode {
+            code: code.to_vec(),
+            storage_deposit_limit: None,
+            determinism: Determinism::Enforced,
+        };
+        self.submit(subxt::tx::Payload::new("Contracts", "upload_code", upload).unvalidated())
+            .await?;
+        Ok(code_hash(code))
+    }
+
+    /// Instantiates `code` with the SCALE encoded constructor call `input`.
+    pub async fn instantiate_with_code(
+        &self,
+        code: &[u8],
+        input: Vec<u8>,
+        salt: Vec<u8>,
+    ) -> Result<(AccountId, ExtrinsicEvents<PolkadotConfig>)> {
+        let request = InstantiateRequest {
+            origin: self.origin(),
+            value: 0,
+            gas_limit: None,
+            storage_deposit_limit: None,
+            code: Code::Upload(code.to_vec()),
+            data: input.clone(),
+            salt: salt.clone(),
+        };
+        let result: ContractInstantiateResult<AccountId, Balance, ()> = self
+            .api
+            .rpc()
+            .state_call("ContractsApi_instantiate", Some(&request.encode()), None)
+            .await?;
+        let debug = String::from_utf8_lossy(&result.debug_message).into_owned();
+        let account_id = match result.result {
+            Ok(instantiated) if !instantiated.result.did_revert() => instantiated.account_id,
+            Ok(_) => return Err(Error::Reverted(debug)),
+            Err(err) => return Err(Error::Dispatch(format!("{err:?} {debug}"))),
+        };
+
+        let instantiate = InstantiateWithCode {
+            value: 0,
+            gas_limit: result.gas_required.into(),
+            storage_deposit_limit: None,
+            code: code.to_vec(),
+            data: input,
+            salt,
+        };
+        let events = self
+            .submit(
+                subxt::tx::Payload::new("Contracts", "instantiate_with_code", instantiate)
+                    .unvalidated(),
+            )
+            .await?;
+        Ok((account_id, events))
+    }
+
+    async fn submit<P: subxt::tx::TxPayload>(
+        &self,
+        payload: P,
+    ) -> Result<ExtrinsicEvents<PolkadotConfig>> {
+        Ok(self
+            .api
+            .tx()
+            .sign_and_submit_then_watch_default(&payload, &self.signer)
+            .await?
+            .wait_for_in_block()
+            .await?
+            .wait_for_success()
+            .await?)
+    }
+
+    fn origin(&self) -> AccountId32 {
+        AccountId32(self.signer.public_key().0)
+    }
+}
+
+/// Hash pallet-contracts stores `code` under.
+pub fn code_hash(code: &[u8]) -> Hash {
+    use ink::env::hash::{Blake2x256, CryptoHash};
+
+    let mut hash = [0u8; 32];
+    Blake2x256::hash(code, &mut hash);
+    Hash::from(hash)
+}
+
+fn to_subxt(account: AccountId) -> AccountId32 {
+    AccountId32(*account.as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,228 @@
+//! Selectors and event labels read from the metadata `cargo contract build` produces.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use contract_metadata::ContractMetadata;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// A message as the metadata describes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message {
+    pub selector: [u8; 4],
+    /// Whether the message takes `&mut self`, i.e. needs a transaction to take effect.
+    pub mutates: bool,
+    pub payable: bool,
+}
+
+/// One contract's code and ABI, loaded from its `.contract` bundle or `.json` metadata.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    name: String,
+    wasm: Option<Vec<u8>>,
+    constructors: BTreeMap<String, [u8; 4]>,
+    messages: BTreeMap<String, Message>,
+    /// Event labels, in the order ink! indexes them.
+    events: Vec<String>,
+}
+
+impl Artifact {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let metadata = ContractMetadata::load(path)
+            .map_err(|err| Error::Metadata(format!("{}: {err}", path.display())))?;
+        Self::from_metadata(metadata)
+    }
+
+    pub fn from_metadata(metadata: ContractMetadata) -> Result<Self> {
+        let spec = metadata
+            .abi
+            .get("spec")
+            .ok_or_else(|| Error::Metadata("no `spec` in the ABI".into()))?;
+
+        let constructors = entries(spec, "constructors")?
+            .iter()
+            .map(|constructor| Ok((label(constructor)?, selector(constructor)?)))
+            .collect::<Result<_>>()?;
+        let messages = entries(spec, "messages")?
+            .iter()
+            .map(|message| {
+                let entry = Message {
+                    selector: selector(message)?,
+                    mutates: flag(message, "mutates"),
+                    payable: flag(message, "payable"),
+                };
+                Ok((label(message)?, entry))
+            })
+            .collect::<Result<_>>()?;
+        let events = entries(spec, "events")?
+            .iter()
+            .map(label)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name: metadata.contract.name,
+            wasm: metadata.source.wasm.map(|wasm| wasm.0),
+            constructors,
+            messages,
+            events,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The contract's code. Only `.contract` bundles carry it.
+    pub fn wasm(&self) -> Result<&[u8]> {
+        self.wasm
+            .as_deref()
+            .ok_or_else(|| Error::MissingWasm(self.name.clone()))
+    }
+
+    pub fn constructor(&self, label: &str) -> Result<[u8; 4]> {
+        self.constructors
+            .get(label)
+            .copied()
+            .ok_or_else(|| Error::UnknownConstructor(format!("{}::{label}", self.name)))
+    }
+
+    /// Looks up a message by label, e.g. `get_reserves` or `PSP22::transfer`.
+    pub fn message(&self, label: &str) -> Result<Message> {
+        self.messages
+            .get(label)
+            .copied()
+            .ok_or_else(|| Error::UnknownMessage(format!("{}::{label}", self.name)))
+    }
+
+    /// Label of the event ink! encodes with `index`.
+    pub fn event_label(&self, index: u8) -> Option<&str> {
+        self.events.get(index as usize).map(String::as_str)
+    }
+}
+
+/// The artifacts of every contract the client deploys or calls.
+#[derive(Debug, Clone)]
+pub struct Artifacts {
+    pub core: Artifact,
+    pub pair: Artifact,
+    pub lp_token: Artifact,
+    pub psp22token: Artifact,
+    pub router: Artifact,
+    pub wnative: Artifact,
+}
+
+impl Artifacts {
+    /// Loads `<dir>/<name>/<name>.contract` for each contract, the layout of `target/ink`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let load = |name: &str| Artifact::load(dir.join(name).join(format!("{name}.contract")));
+        Ok(Self {
+            core: load("core")?,
+            pair: load("pair")?,
+            lp_token: load("lp_token")?,
+            psp22token: load("psp22token")?,
+            router: load("router")?,
+            wnative: load("wnative")?,
+        })
+    }
+}
+
+fn entries<'a>(spec: &'a Value, key: &str) -> Result<&'a Vec<Value>> {
+    spec.get(key)
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::Metadata(format!("no `spec.{key}` in the ABI")))
+}
+
+fn label(entry: &Value) -> Result<String> {
+    entry
+        .get("label")
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| Error::Metadata(format!("entry without a label: {entry}")))
+}
+
+fn selector(entry: &Value) -> Result<[u8; 4]> {
+    let invalid = || Error::Metadata(format!("invalid selector: {entry}"));
+    let hex = entry
+        .get("selector")
+        .and_then(Value::as_str)
+        .and_then(|selector| selector.strip_prefix("0x"))
+        .filter(|hex| hex.len() == 8)
+        .ok_or_else(invalid)?;
+    let mut selector = [0u8; 4];
+    for (i, byte) in selector.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(selector)
+}
+
+fn flag(entry: &Value, key: &str) -> bool {
+    entry.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The parts of a `pair.json` the client reads.
+    fn metadata() -> ContractMetadata {
+        serde_json::from_value(serde_json::json!({
+            "source": {
+                "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "language": "ink! 4.2.1",
+                "compiler": "rustc 1.70.0",
+                "wasm": "0x0061736d"
+            },
+            "contract": { "name": "pair", "version": "0.1.0", "authors": ["Bolaji Ahmad"] },
+            "spec": {
+                "constructors": [{ "label": "create", "selector": "0x2fa3ab7a", "payable": false }],
+                "messages": [
+                    { "label": "get_reserves", "selector": "0x9e3a4a8b", "mutates": false, "payable": false },
+                    { "label": "swap", "selector": "0x2b2e8f9d", "mutates": true, "payable": false }
+                ],
+                "events": [{ "label": "Sync" }, { "label": "Mint" }, { "label": "Burn" }, { "label": "Swap" }]
+            },
+            "version": "4"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_selectors_and_event_order() {
+        let artifact = Artifact::from_metadata(metadata()).unwrap();
+
+        assert_eq!(artifact.name(), "pair");
+        assert_eq!(artifact.wasm().unwrap(), [0x00, 0x61, 0x73, 0x6d]);
+        assert_eq!(
+            artifact.constructor("create").unwrap(),
+            [0x2f, 0xa3, 0xab, 0x7a]
+        );
+        assert_eq!(
+            artifact.message("swap").unwrap(),
+            Message {
+                selector: [0x2b, 0x2e, 0x8f, 0x9d],
+                mutates: true,
+                payable: false,
+            }
+        );
+        assert_eq!(artifact.event_label(3), Some("Swap"));
+        assert_eq!(artifact.event_label(4), None);
+    }
+
+    #[test]
+    fn unknown_entries_are_errors() {
+        let artifact = Artifact::from_metadata(metadata()).unwrap();
+
+        assert!(matches!(
+            artifact.message("flip"),
+            Err(Error::UnknownMessage(label)) if label == "pair::flip"
+        ));
+        assert!(matches!(
+            artifact.constructor("new"),
+            Err(Error::UnknownConstructor(_))
+        ));
+    }
+}
@@ -0,0 +1,445 @@
+//! Typed handles on deployed contracts. Queries are dry-runs; messages that mutate are
+//! submitted and return a `Receipt`.
+
+use ink::primitives::{AccountId, Hash};
+
+use crate::{Balance, Client, Error, FactoryEvent, PairEvent, Receipt, Result};
+
+/// A pair's state as `UniswapCore::pools` reports it.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode)]
+pub struct PoolState {
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    pub lp_token: AccountId,
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+    pub block_timestamp_last: u128,
+    pub total_supply: Balance,
+    /// Swap fee in millionths.
+    pub fee: u32,
+    pub stable: bool,
+    /// Share of the pool's value in token 0, in percent.
+    pub weight_0: u32,
+    /// Whether the pool is a `ClPool`, of which `lp_token` is the pool itself and
+    /// `total_supply` the liquidity in range.
+    pub concentrated: bool,
+}
+
+/// A deployed `UniswapCore`.
+#[derive(Clone, Copy)]
+pub struct Factory<'a> {
+    client: &'a Client,
+    pub address: AccountId,
+}
+
+impl<'a> Factory<'a> {
+    pub fn new(client: &'a Client, address: AccountId) -> Self {
+        Self { client, address }
+    }
+
+    async fn query<R: scale::Decode>(&self, label: &str, args: impl scale::Encode) -> Result<R> {
+        let artifact = &self.client.artifacts().core;
+        self.client.query(artifact, self.address, label, args).await
+    }
+
+    async fn call<R: scale::Decode>(
+        &self,
+        label: &str,
+        args: impl scale::Encode,
+    ) -> Result<Receipt<R>> {
+        let artifact = &self.client.artifacts().core;
+        self.client.call(artifact, self.address, label, args).await
+    }
+
+    /// Returns the new pair's address.
+    pub async fn create_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Receipt<AccountId>> {
+        self.call("create_pair", (token_a, token_b)).await
+    }
+
+    /// Returns the new stable pair's address.
+    pub async fn create_stable_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Receipt<AccountId>> {
+        self.call("create_stable_pair", (token_a, token_b)).await
+    }
+
+    /// Returns the new weighted pair's address. `token_a` makes up `weight_a` percent of
+    /// its value.
+    pub async fn create_weighted_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        weight_a: u32,
+    ) -> Result<Receipt<AccountId>> {
+        self.call("create_weighted_pair", (token_a, token_b, weight_a))
+            .await
+    }
+
+    /// Returns the new concentrated liquidity pool's address. `fee` is a tier in
+    /// millionths: 500, 3000 or 10000.
+    pub async fn create_cl_pool(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        fee: u32,
+    ) -> Result<Receipt<AccountId>> {
+        self.call("create_cl_pool", (token_a, token_b, fee)).await
+    }
+
+    pub async fn get_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Option<AccountId>> {
+        self.query("get_pair", (token_a, token_b)).await
+    }
+
+    pub async fn get_stable_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Option<AccountId>> {
+        self.query("get_stable_pair", (token_a, token_b)).await
+    }
+
+    pub async fn get_weighted_pair(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        weight_a: u32,
+    ) -> Result<Option<AccountId>> {
+        self.query("get_weighted_pair", (token_a, token_b, weight_a))
+            .await
+    }
+
+    pub async fn get_cl_pool(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        fee: u32,
+    ) -> Result<Option<AccountId>> {
+        self.query("get_cl_pool", (token_a, token_b, fee)).await
+    }
+
+    pub async fn compute_pair_address(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<AccountId> {
+        self.query("compute_pair_address", (token_a, token_b)).await
+    }
+
+    pub async fn all_pairs_length(&self) -> Result<u32> {
+        self.query("all_pairs_length", ()).await
+    }
+
+    /// Up to `count` pairs from index `start`, with their state, or `None` where a pair
+    /// could not be read.
+    pub async fn pools(
+        &self,
+        start: u32,
+        count: u32,
+    ) -> Result<Vec<(AccountId, Option<PoolState>)>> {
+        self.query("pools", (start, count)).await
+    }
+
+    pub async fn pair_code_hash(&self) -> Result<Hash> {
+        self.query("pair_code_hash", ()).await
+    }
+
+    /// `None` until the fee-to setter enables stable pairs.
+    pub async fn stable_pair_code_hash(&self) -> Result<Option<Hash>> {
+        self.query("stable_pair_code_hash", ()).await
+    }
+
+    /// `None` until the fee-to setter enables weighted pairs.
+    pub async fn weighted_pair_code_hash(&self) -> Result<Option<Hash>> {
+        self.query("weighted_pair_code_hash", ()).await
+    }
+
+    /// `None` until the fee-to setter enables concentrated liquidity pools.
+    pub async fn cl_pool_code_hash(&self) -> Result<Option<Hash>> {
+        self.query("cl_pool_code_hash", ()).await
+    }
+
+    pub async fn get_fee_to(&self) -> Result<AccountId> {
+        self.query("get_fee_to", ()).await
+    }
+
+    pub async fn set_fee_to(&self, fee_to: AccountId) -> Result<Receipt<()>> {
+        self.call("set_fee_to", fee_to).await
+    }
+
+    /// Hands over fee administration once `fee_to_setter` calls `accept_fee_to_setter`.
+    pub async fn propose_fee_to_setter(&self, fee_to_setter: AccountId) -> Result<Receipt<()>> {
+        self.call("propose_fee_to_setter", fee_to_setter).await
+    }
+
+    pub async fn is_paused(&self, pair: AccountId) -> Result<bool> {
+        self.query("is_paused", pair).await
+    }
+
+    /// Makes `pair`'s fee follow its volatility within `(min_fee, max_fee)`, or fixes it
+    /// again with `None`.
+    pub async fn set_dynamic_fee(
+        &self,
+        pair: AccountId,
+        bounds: Option<(u32, u32)>,
+    ) -> Result<Receipt<()>> {
+        self.call("set_dynamic_fee", (pair, bounds)).await
+    }
+
+    /// The events this factory emitted in `receipt`.
+    pub fn events<T>(&self, receipt: &Receipt<T>) -> Vec<FactoryEvent> {
+        let artifact = &self.client.artifacts().core;
+        receipt
+            .events
+            .iter()
+            .filter(|event| event.emitter == self.address)
+            .filter_map(|event| FactoryEvent::decode(event, artifact))
+            .collect()
+    }
+}
+
+/// A deployed `Pair`.
+#[derive(Clone, Copy)]
+pub struct Pair<'a> {
+    client: &'a Client,
+    pub address: AccountId,
+}
+
+impl<'a> Pair<'a> {
+    pub fn new(client: &'a Client, address: AccountId) -> Self {
+        Self { client, address }
+    }
+
+    async fn query<R: scale::Decode>(&self, label: &str, args: impl scale::Encode) -> Result<R> {
+        let artifact = &self.client.artifacts().pair;
+        self.client.query(artifact, self.address, label, args).await
+    }
+
+    async fn call<R: scale::Decode>(
+        &self,
+        label: &str,
+        args: impl scale::Encode,
+    ) -> Result<Receipt<R>> {
+        let artifact = &self.client.artifacts().pair;
+        self.client.call(artifact, self.address, label, args).await
+    }
+
+    /// Reserves and the millisecond timestamp of their last update.
+    pub async fn get_reserves(&self) -> Result<(Balance, Balance, u128)> {
+        self.query("get_reserves", ()).await
+    }
+
+    pub async fn lp_token(&self) -> Result<AccountId> {
+        self.query("lp_token", ()).await
+    }
+
+    pub async fn token_0(&self) -> Result<AccountId> {
+        self.query("token_0", ()).await
+    }
+
+    pub async fn token_1(&self) -> Result<AccountId> {
+        self.query("token_1", ()).await
+    }
+
+    /// Swap fee in millionths, e.g. 3000 for 0.3%, current one for a dynamic fee.
+    pub async fn fee(&self) -> Result<u32> {
+        self.query("fee", ()).await
+    }
+
+    pub async fn stable(&self) -> Result<bool> {
+        self.query("stable", ()).await
+    }
+
+    /// Share of the pair's value in token 0, in percent; 50 but for weighted pairs.
+    pub async fn weight_0(&self) -> Result<u32> {
+        self.query("weight_0", ()).await
+    }
+
+    pub async fn price_0_cumulative_last(&self) -> Result<u128> {
+        self.query("price_0_cumulative_last", ()).await
+    }
+
+    pub async fn price_1_cumulative_last(&self) -> Result<u128> {
+        self.query("price_1_cumulative_last", ()).await
+    }
+
+    /// Mints LP tokens to `to` for the tokens sent to the pair beforehand.
+    pub async fn mint(&self, to: AccountId) -> Result<Receipt<Balance>> {
+        self.call("mint", to).await
+    }
+
+    /// Burns the LP tokens sent to the pair beforehand, paying out to `to`.
+    pub async fn burn(&self, to: AccountId) -> Result<Receipt<(Balance, Balance)>> {
+        self.call("burn", to).await
+    }
+
+    pub async fn swap(
+        &self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+    ) -> Result<Receipt<()>> {
+        self.call("swap", (amount_0_out, amount_1_out, to, Vec::<u8>::new()))
+            .await
+    }
+
+    pub async fn skim(&self, to: AccountId) -> Result<Receipt<()>> {
+        self.call("skim", to).await
+    }
+
+    pub async fn sync(&self) -> Result<Receipt<()>> {
+        self.call("sync", ()).await
+    }
+
+    /// The events this pair emitted in `receipt`.
+    pub fn events<T>(&self, receipt: &Receipt<T>) -> Vec<PairEvent> {
+        let artifact = &self.client.artifacts().pair;
+        receipt
+            .events
+            .iter()
+            .filter(|event| event.emitter == self.address)
+            .filter_map(|event| PairEvent::decode(event, artifact))
+            .collect()
+    }
+}
+
+/// A deployed `Router`.
+#[derive(Clone, Copy)]
+pub struct Router<'a> {
+    client: &'a Client,
+    pub address: AccountId,
+}
+
+impl<'a> Router<'a> {
+    pub fn new(client: &'a Client, address: AccountId) -> Self {
+        Self { client, address }
+    }
+
+    async fn query<R: scale::Decode>(&self, label: &str, args: impl scale::Encode) -> Result<R> {
+        let artifact = &self.client.artifacts().router;
+        self.client.query(artifact, self.address, label, args).await
+    }
+
+    /// Submits a router message. Its `Error` is only told apart from success, so it is not
+    /// decoded.
+    async fn call<R: scale::Decode>(
+        &self,
+        label: &'static str,
+        args: impl scale::Encode,
+    ) -> Result<Receipt<R>> {
+        let artifact = &self.client.artifacts().router;
+        let receipt = self
+            .client
+            .call::<core::result::Result<R, ()>>(artifact, self.address, label, args)
+            .await?;
+        let value = receipt.value.map_err(|()| Error::Rejected(label))?;
+        Ok(Receipt {
+            value,
+            events: receipt.events,
+            gas_required: receipt.gas_required,
+        })
+    }
+
+    pub async fn factory(&self) -> Result<AccountId> {
+        self.query("factory", ()).await
+    }
+
+    pub async fn wnative(&self) -> Result<AccountId> {
+        self.query("wnative", ()).await
+    }
+
+    /// Pays both tokens into their pair and mints in one transaction, so nothing can come
+    /// in between. Returns the amounts paid and the liquidity minted.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_liquidity(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        amount_a_desired: Balance,
+        amount_b_desired: Balance,
+        amount_a_min: Balance,
+        amount_b_min: Balance,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Receipt<(Balance, Balance, Balance)>> {
+        self.call(
+            "add_liquidity",
+            (
+                token_a,
+                token_b,
+                amount_a_desired,
+                amount_b_desired,
+                amount_a_min,
+                amount_b_min,
+                to,
+                deadline,
+            ),
+        )
+        .await
+    }
+}
+
+/// A deployed PSP22 token, such as a `psp22token` or a pair's `lp_token`, which share their
+/// message labels.
+#[derive(Clone, Copy)]
+pub struct Token<'a> {
+    client: &'a Client,
+    pub address: AccountId,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(client: &'a Client, address: AccountId) -> Self {
+        Self { client, address }
+    }
+
+    async fn query<R: scale::Decode>(&self, label: &str, args: impl scale::Encode) -> Result<R> {
+        let artifact = &self.client.artifacts().psp22token;
+        self.client.query(artifact, self.address, label, args).await
+    }
+
+    /// Submits a PSP22 message. The `PSP22Error` is only told apart from success, so it is
+    /// not decoded.
+    async fn call(&self, label: &'static str, args: impl scale::Encode) -> Result<Receipt<()>> {
+        let artifact = &self.client.artifacts().psp22token;
+        let receipt = self
+            .client
+            .call::<core::result::Result<(), ()>>(artifact, self.address, label, args)
+            .await?;
+        receipt.value.map_err(|()| Error::Rejected(label))?;
+        Ok(Receipt {
+            value: (),
+            events: receipt.events,
+            gas_required: receipt.gas_required,
+        })
+    }
+
+    pub async fn balance_of(&self, owner: AccountId) -> Result<Balance> {
+        self.query("PSP22::balance_of", owner).await
+    }
+
+    pub async fn total_supply(&self) -> Result<Balance> {
+        self.query("PSP22::total_supply", ()).await
+    }
+
+    pub async fn allowance(&self, owner: AccountId, spender: AccountId) -> Result<Balance> {
+        self.query("PSP22::allowance", (owner, spender)).await
+    }
+
+    pub async fn transfer(&self, to: AccountId, value: Balance) -> Result<Receipt<()>> {
+        self.call("PSP22::transfer", (to, value, Vec::<u8>::new()))
+            .await
+    }
+
+    pub async fn approve(&self, spender: AccountId, value: Balance) -> Result<Receipt<()>> {
+        self.call("PSP22::approve", (spender, value)).await
+    }
+}
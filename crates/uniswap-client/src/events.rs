@@ -0,0 +1,176 @@
+//! The factory and pair events, mirrored since the contracts keep theirs private. ink!
+//! encodes an event as its index among the contract's events, then its fields; the index is
+//! resolved through the contract's metadata.
+
+use ink::primitives::{AccountId, Hash};
+use scale::Decode;
+use subxt::blocks::ExtrinsicEvents;
+use subxt::events::EventDetails;
+
+use crate::{Artifact, Balance};
+
+/// Raw ink! event data emitted by the contract at `emitter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub emitter: AccountId,
+    pub data: Vec<u8>,
+}
+
+impl ContractEvent {
+    /// Label of the event, if `artifact` describes the emitter.
+    pub fn label<'a>(&self, artifact: &'a Artifact) -> Option<&'a str> {
+        artifact.event_label(*self.data.first()?)
+    }
+
+    /// Decodes the fields of the event, whatever its label.
+    pub fn fields<E: Decode>(&self) -> Option<E> {
+        E::decode(&mut self.data.get(1..)?).ok()
+    }
+}
+
+/// The `Contracts::ContractEmitted` events of an extrinsic, in order.
+pub fn contract_events<C: subxt::Config>(events: &ExtrinsicEvents<C>) -> Vec<ContractEvent> {
+    events
+        .iter()
+        .filter_map(|event| contract_event(&event.ok()?))
+        .collect()
+}
+
+/// The contract event carried by `event`, if it is a `Contracts::ContractEmitted`.
+pub fn contract_event<C: subxt::Config>(event: &EventDetails<C>) -> Option<ContractEvent> {
+    if event.pallet_name() != "Contracts" || event.variant_name() != "ContractEmitted" {
+        return None;
+    }
+    // `ContractEmitted { contract, data }`
+    let (emitter, data) = <(AccountId, Vec<u8>)>::decode(&mut event.field_bytes()).ok()?;
+    Some(ContractEvent { emitter, data })
+}
+
+/// The kind of pair a factory event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Decode)]
+pub enum PairKind {
+    Classic,
+    Stable,
+    /// A weighted pair in which token 0 has this weight, in percent.
+    Weighted(u32),
+    /// A concentrated liquidity pool charging this fee, in millionths.
+    Concentrated(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct PairCreated {
+    pub token_0: Option<AccountId>,
+    pub token_1: Option<AccountId>,
+    pub pair: Option<AccountId>,
+    pub pair_index: u32,
+    pub kind: PairKind,
+}
+
+/// A change of the code the factory creates pairs of `kind` from. `Weighted` and
+/// `Concentrated` carry 0, the code serving every weight and fee tier.
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct PairCodeHashChanged {
+    pub previous: Hash,
+    pub new: Hash,
+    pub kind: PairKind,
+}
+
+/// Events of `UniswapCore`. Only pair creation and code changes are decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FactoryEvent {
+    PairCreated(PairCreated),
+    PairCodeHashChanged(PairCodeHashChanged),
+    Other(String),
+}
+
+impl FactoryEvent {
+    pub fn decode(event: &ContractEvent, artifact: &Artifact) -> Option<Self> {
+        match event.label(artifact)? {
+            "PairCreated" => event.fields().map(FactoryEvent::PairCreated),
+            "PairCodeHashChanged" => event.fields().map(FactoryEvent::PairCodeHashChanged),
+            other => Some(FactoryEvent::Other(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct Sync {
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+    pub pair: AccountId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct Mint {
+    pub owner: Option<AccountId>,
+    pub amount_0: Balance,
+    pub amount_1: Balance,
+    pub to: AccountId,
+    pub liquidity: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct Burn {
+    pub sender: Option<AccountId>,
+    pub amount_0: Balance,
+    pub amount_1: Balance,
+    pub to: AccountId,
+    pub liquidity: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct Swap {
+    pub sender: Option<AccountId>,
+    pub amount_0_in: Balance,
+    pub amount_1_in: Balance,
+    pub amount_0_out: Balance,
+    pub amount_1_out: Balance,
+    pub to: AccountId,
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+}
+
+/// Events of `Pair`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairEvent {
+    Sync(Sync),
+    Mint(Mint),
+    Burn(Burn),
+    Swap(Swap),
+    Other(String),
+}
+
+impl PairEvent {
+    pub fn decode(event: &ContractEvent, artifact: &Artifact) -> Option<Self> {
+        match event.label(artifact)? {
+            "Sync" => event.fields().map(PairEvent::Sync),
+            "Mint" => event.fields().map(PairEvent::Mint),
+            "Burn" => event.fields().map(PairEvent::Burn),
+            "Swap" => event.fields().map(PairEvent::Swap),
+            other => Some(PairEvent::Other(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct Transfer {
+    pub from: Option<AccountId>,
+    pub to: Option<AccountId>,
+    pub value: Balance,
+}
+
+/// Events of a `psp22token` or of a pair's `lp_token`, which emit the same events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenEvent {
+    Transfer(Transfer),
+    Other(String),
+}
+
+impl TokenEvent {
+    pub fn decode(event: &ContractEvent, artifact: &Artifact) -> Option<Self> {
+        match event.label(artifact)? {
+            "TransferEvent" => event.fields().map(TokenEvent::Transfer),
+            other => Some(TokenEvent::Other(other.to_owned())),
+        }
+    }
+}
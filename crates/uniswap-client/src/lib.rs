@@ -0,0 +1,271 @@
+//! Rust client for the AMM contracts: deploys and calls the factory, pairs and PSP22 tokens
+//! on a node through subxt, with selectors and event layouts taken from the `.contract`
+//! bundles `cargo contract build` writes.
+//!
+//! ```no_run
+//! # async fn example() -> uniswap_client::Result<()> {
+//! use uniswap_client::{Artifacts, Client};
+//!
+//! let artifacts = Artifacts::load("target/ink")?;
+//! let client = Client::connect("ws://127.0.0.1:9944", subxt_signer::sr25519::dev::alice(), artifacts).await?;
+//! # let (factory, token_a, token_b) = ([0; 32].into(), [1; 32].into(), [2; 32].into());
+//! let pair = client.factory(factory).create_pair(token_a, token_b).await?.value;
+//! let (reserve_0, reserve_1, _) = client.pair(pair).get_reserves().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod api;
+pub mod artifact;
+pub mod contracts;
+pub mod events;
+
+use std::fmt;
+use std::sync::Arc;
+
+use ink::primitives::AccountId;
+use scale::{Decode, Encode};
+use subxt::{OnlineClient, PolkadotConfig};
+use subxt_signer::sr25519::Keypair;
+
+pub use api::Weight;
+pub use artifact::{Artifact, Artifacts};
+pub use contracts::{Factory, Pair, PoolState, Router, Token};
+pub use events::{ContractEvent, FactoryEvent, PairEvent, TokenEvent};
+
+pub type Balance = u128;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Boxed, being several times the size of every other variant.
+    Subxt(Box<subxt::Error>),
+    Io(std::io::Error),
+    /// A `.contract` or metadata file that could not be read.
+    Metadata(String),
+    MissingWasm(String),
+    UnknownConstructor(String),
+    UnknownMessage(String),
+    /// The contract trapped or reverted; carries its debug output.
+    Reverted(String),
+    /// The call failed to dispatch, e.g. ran out of gas or hit a missing contract.
+    Dispatch(String),
+    /// ink! could not decode the call, usually a selector or argument mismatch.
+    Lang(ink::LangError),
+    /// The return value did not decode as the expected type.
+    Decode(scale::Error),
+    /// A PSP22 or router message returned an error.
+    Rejected(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Subxt(err) => write!(f, "node error: {err}"),
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Metadata(reason) => write!(f, "invalid contract metadata: {reason}"),
+            Error::MissingWasm(name) => write!(f, "no wasm in the `{name}` artifact"),
+            Error::UnknownConstructor(label) => write!(f, "unknown constructor `{label}`"),
+            Error::UnknownMessage(label) => write!(f, "unknown message `{label}`"),
+            Error::Reverted(debug) => write!(f, "contract reverted: {debug}"),
+            Error::Dispatch(reason) => write!(f, "dispatch failed: {reason}"),
+            Error::Lang(err) => write!(f, "ink! rejected the call: {err:?}"),
+            Error::Decode(err) => write!(f, "undecodable return value: {err}"),
+            Error::Rejected(message) => write!(f, "`{message}` returned an error"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<subxt::Error> for Error {
+    fn from(error: subxt::Error) -> Self {
+        Error::Subxt(Box::new(error))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<scale::Error> for Error {
+    fn from(error: scale::Error) -> Self {
+        Error::Decode(error)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The outcome of a submitted message.
+#[derive(Debug, Clone)]
+pub struct Receipt<T> {
+    /// What the message returned in the dry-run the transaction was sized from.
+    pub value: T,
+    pub events: Vec<ContractEvent>,
+    pub gas_required: Weight,
+}
+
+/// A connection to a node, signing with one account.
+#[derive(Clone)]
+pub struct Client {
+    api: OnlineClient<PolkadotConfig>,
+    signer: Keypair,
+    artifacts: Arc<Artifacts>,
+}
+
+impl Client {
+    pub async fn connect(url: &str, signer: Keypair, artifacts: Artifacts) -> Result<Self> {
+        Ok(Self {
+            api: OnlineClient::from_url(url).await?,
+            signer,
+            artifacts: Arc::new(artifacts),
+        })
+    }
+
+    /// The same connection, signing as `signer`.
+    pub fn with_signer(&self, signer: Keypair) -> Self {
+        Self {
+            signer,
+            ..self.clone()
+        }
+    }
+
+    pub fn account_id(&self) -> AccountId {
+        AccountId::from(self.signer.public_key().0)
+    }
+
+    pub fn artifacts(&self) -> &Artifacts {
+        &self.artifacts
+    }
+
+    pub fn api(&self) -> &OnlineClient<PolkadotConfig> {
+        &self.api
+    }
+
+    pub fn factory(&self, address: AccountId) -> Factory<'_> {
+        Factory::new(self, address)
+    }
+
+    pub fn pair(&self, address: AccountId) -> Pair<'_> {
+        Pair::new(self, address)
+    }
+
+    pub fn token(&self, address: AccountId) -> Token<'_> {
+        Token::new(self, address)
+    }
+
+    pub fn router(&self, address: AccountId) -> Router<'_> {
+        Router::new(self, address)
+    }
+
+    /// Dry-runs `label` on `contract` and decodes what it returns.
+    pub async fn query<R: Decode>(
+        &self,
+        artifact: &Artifact,
+        contract: AccountId,
+        label: &str,
+        args: impl Encode,
+    ) -> Result<R> {
+        let dry_run = self
+            .dry_run(contract, input(artifact, label, args)?)
+            .await?;
+        decode_return(&dry_run.data)
+    }
+
+    /// Submits `label` on `contract`, sized by a dry-run, and collects the contract events
+    /// of the transaction.
+    pub async fn call<R: Decode>(
+        &self,
+        artifact: &Artifact,
+        contract: AccountId,
+        label: &str,
+        args: impl Encode,
+    ) -> Result<Receipt<R>> {
+        let input = input(artifact, label, args)?;
+        let dry_run = self.dry_run(contract, input.clone()).await?;
+        let value = decode_return(&dry_run.data)?;
+        let events = self
+            .submit_call(contract, input, dry_run.gas_required)
+            .await?;
+        Ok(Receipt {
+            value,
+            events: events::contract_events(&events),
+            gas_required: dry_run.gas_required,
+        })
+    }
+
+    /// Instantiates `artifact` through `constructor`, salted so repeated deployments of the
+    /// same code get distinct addresses.
+    pub async fn instantiate(
+        &self,
+        artifact: &Artifact,
+        constructor: &str,
+        args: impl Encode,
+    ) -> Result<AccountId> {
+        let mut input = artifact.constructor(constructor)?.to_vec();
+        args.encode_to(&mut input);
+        let salt = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            .to_le_bytes()
+            .to_vec();
+        let (address, _) = self
+            .instantiate_with_code(artifact.wasm()?, input, salt)
+            .await?;
+        Ok(address)
+    }
+
+    /// A `psp22token` with `supply` minted to the signer.
+    pub async fn deploy_token(
+        &self,
+        supply: Balance,
+        name: Option<String>,
+        symbol: Option<String>,
+        decimals: u8,
+    ) -> Result<AccountId> {
+        self.instantiate(
+            &self.artifacts.psp22token,
+            "new",
+            (supply, name, symbol, decimals),
+        )
+        .await
+    }
+
+    /// Uploads the pair and LP token code and instantiates `UniswapCore` with it.
+    pub async fn deploy_factory(&self, fee_to_setter: AccountId) -> Result<AccountId> {
+        let pair_code_hash = self.upload_code(self.artifacts.pair.wasm()?).await?;
+        let lp_token_code_hash = self.upload_code(self.artifacts.lp_token.wasm()?).await?;
+        self.instantiate(
+            &self.artifacts.core,
+            "new",
+            (fee_to_setter, pair_code_hash, lp_token_code_hash),
+        )
+        .await
+    }
+
+    /// A `Router` on `factory`, wrapping native currency through a new `wnative`.
+    pub async fn deploy_router(&self, factory: AccountId) -> Result<AccountId> {
+        let wnative = self
+            .instantiate(
+                &self.artifacts.wnative,
+                "new",
+                (Some("Wrapped Native"), Some("WNATIVE"), 18u8),
+            )
+            .await?;
+        self.instantiate(&self.artifacts.router, "new", (factory, wnative))
+            .await
+    }
+}
+
+fn input(artifact: &Artifact, label: &str, args: impl Encode) -> Result<Vec<u8>> {
+    let mut input = artifact.message(label)?.selector.to_vec();
+    args.encode_to(&mut input);
+    Ok(input)
+}
+
+/// Messages return `Result<R, LangError>`.
+fn decode_return<R: Decode>(data: &[u8]) -> Result<R> {
+    <core::result::Result<R, ink::LangError>>::decode(&mut &data[..])?.map_err(Error::Lang)
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "node-tests")]
+//! The client against a running `substrate-contracts-node`.
+
+use ink::primitives::AccountId;
+use subxt_signer::sr25519::dev;
+use uniswap_client::events::{PairCreated, PairKind, Swap, Sync};
+use uniswap_client::{Artifacts, Client, FactoryEvent, PairEvent, PoolState};
+use uniswap_math::{get_amount_out, pair::MINIMUM_LIQUIDITY, sort_tokens};
+
+const SUPPLY: u128 = 1_000_000_000_000;
+
+async fn client() -> Client {
+    let url = std::env::var("CONTRACTS_NODE_URL").unwrap_or("ws://127.0.0.1:9944".into());
+    let artifacts = std::env::var("AMM_ARTIFACTS")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/ink").into());
+    let artifacts = Artifacts::load(artifacts).expect("contracts not built");
+    Client::connect(&url, dev::alice(), artifacts)
+        .await
+        .expect("no node to connect to")
+}
+
+#[tokio::test]
+async fn create_pair_add_liquidity_and_swap() {
+    let client = client().await;
+    let alice = client.account_id();
+    let bob = AccountId::from(dev::bob().public_key().0);
+    let token_a = client
+        .deploy_token(SUPPLY, Some("TKA".into()), Some("TKA".into()), 18)
+        .await
+        .unwrap();
+    let token_b = client
+        .deploy_token(SUPPLY, Some("TKB".into()), Some("TKB".into()), 18)
+        .await
+        .unwrap();
+    let factory = client.factory(client.deploy_factory(alice).await.unwrap());
+    let (token_0, token_1) = sort_tokens(token_a, token_b).unwrap();
+
+    let computed = factory
+        .compute_pair_address(token_a, token_b)
+        .await
+        .unwrap();
+    let receipt = factory.create_pair(token_a, token_b).await.unwrap();
+    let pair = client.pair(receipt.value);
+    assert_eq!(pair.address, computed);
+    assert_eq!(
+        factory.events(&receipt),
+        vec![FactoryEvent::PairCreated(PairCreated {
+            token_0: Some(token_0),
+            token_1: Some(token_1),
+            pair: Some(pair.address),
+            pair_index: 1,
+            kind: PairKind::Classic,
+        })]
+    );
+    assert_eq!(
+        factory.get_pair(token_b, token_a).await.unwrap(),
+        Some(pair.address)
+    );
+
+    client
+        .token(token_0)
+        .transfer(pair.address, 1_000_000)
+        .await
+        .unwrap();
+    client
+        .token(token_1)
+        .transfer(pair.address, 4_000_000)
+        .await
+        .unwrap();
+    let liquidity = pair.mint(alice).await.unwrap().value;
+    let lp_token = client.token(pair.lp_token().await.unwrap());
+    assert_eq!(liquidity, 2_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(lp_token.balance_of(alice).await.unwrap(), liquidity);
+
+    let amount_out = get_amount_out(10_000, 1_000_000, 4_000_000).unwrap();
+    client
+        .token(token_0)
+        .transfer(pair.address, 10_000)
+        .await
+        .unwrap();
+    // Asking for more than K allows fails in the dry-run, before anything is submitted.
+    assert!(pair.swap(0, amount_out + 1, bob).await.is_err());
+    let receipt = pair.swap(0, amount_out, bob).await.unwrap();
+
+    assert_eq!(
+        pair.events(&receipt),
+        vec![
+            PairEvent::Sync(Sync {
+                reserve_0: 1_010_000,
+                reserve_1: 4_000_000 - amount_out,
+                pair: pair.address,
+            }),
+            PairEvent::Swap(Swap {
+                sender: Some(alice),
+                amount_0_in: 10_000,
+                amount_1_in: 0,
+                amount_0_out: 0,
+                amount_1_out: amount_out,
+                to: bob,
+                reserve_0: 1_010_000,
+                reserve_1: 4_000_000 - amount_out,
+            }),
+        ]
+    );
+    let (reserve_0, reserve_1, timestamp) = pair.get_reserves().await.unwrap();
+    assert_eq!((reserve_0, reserve_1), (1_010_000, 4_000_000 - amount_out));
+    assert_eq!(
+        factory.pools(0, 10).await.unwrap(),
+        vec![(
+            pair.address,
+            Some(PoolState {
+                token_0,
+                token_1,
+                lp_token: lp_token.address,
+                reserve_0,
+                reserve_1,
+                block_timestamp_last: timestamp,
+                total_supply: 2_000_000,
+                fee: 3_000,
+                stable: false,
+                weight_0: 50,
+                concentrated: false,
+            })
+        )]
+    );
+    assert_eq!(
+        client.token(token_1).balance_of(bob).await.unwrap(),
+        amount_out
+    );
+}
@@ -0,0 +1,266 @@
+//! Address derivation for contracts instantiated by the factory, the ink! counterpart of
+//! `UniswapV2Library.pairFor`.
+
+use ink::env::hash::Blake2x256;
+use ink::primitives::{AccountId, Hash};
+
+/// Selector of `Pair::create`, the constructor `UniswapCore::create_pair` instantiates
+/// every pair with.
+pub const PAIR_CONSTRUCTOR: [u8; 4] = ink::selector_bytes!("create");
+
+/// Address pallet-contracts assigns to a contract instantiated by `deployer` from
+/// `code_hash`, with the SCALE encoded constructor call `input_data` and `salt`.
+pub fn contract_address(
+    deployer: &AccountId,
+    code_hash: &Hash,
+    input_data: &[u8],
+    salt: &[u8],
+) -> AccountId {
+    let mut address = [0x0; 32];
+    ink::env::hash_encoded::<Blake2x256, _>(
+        &(b"contract_addr_v1", deployer, code_hash, input_data, salt),
+        &mut address,
+    );
+    AccountId::from(address)
+}
+
+/// Salt the factory instantiates the `token_0`/`token_1` pair with. Tokens must be sorted.
+pub fn pair_salt(token_0: &AccountId, token_1: &AccountId) -> [u8; 32] {
+    let mut salt = [0x0; 32];
+    ink::env::hash_encoded::<Blake2x256, _>(&(token_0, token_1), &mut salt);
+    salt
+}
+
+/// Salt of the stable `token_0`/`token_1` pair, distinct from the classic pair's.
+pub fn stable_pair_salt(token_0: &AccountId, token_1: &AccountId) -> [u8; 32] {
+    let mut salt = [0x0; 32];
+    ink::env::hash_encoded::<Blake2x256, _>(&(token_0, token_1, true), &mut salt);
+    salt
+}
+
+/// Salt of the weighted `token_0`/`token_1` pair in which token 0 weighs `weight_0`, so
+/// pairs of the same tokens with other weights can coexist.
+pub fn weighted_pair_salt(token_0: &AccountId, token_1: &AccountId, weight_0: u32) -> [u8; 32] {
+    let mut salt = [0x0; 32];
+    ink::env::hash_encoded::<Blake2x256, _>(&(token_0, token_1, weight_0), &mut salt);
+    salt
+}
+
+/// Salt of the concentrated liquidity `token_0`/`token_1` pool of the `fee` tier.
+pub fn cl_pool_salt(token_0: &AccountId, token_1: &AccountId, fee: u32) -> [u8; 32] {
+    let mut salt = [0x0; 32];
+    ink::env::hash_encoded::<Blake2x256, _>(&(token_0, token_1, fee, true), &mut salt);
+    salt
+}
+
+/// Address of the `token_a`/`token_b` pair created by `factory` from `pair_code_hash`,
+/// computed without any storage read. Tokens may be given in any order.
+pub fn pair_address(
+    factory: &AccountId,
+    pair_code_hash: &Hash,
+    token_a: AccountId,
+    token_b: AccountId,
+) -> crate::Result<AccountId> {
+    let (token_0, token_1) = crate::sort_tokens(token_a, token_b)?;
+    let salt = pair_salt(&token_0, &token_1);
+    Ok(contract_address(
+        factory,
+        pair_code_hash,
+        &PAIR_CONSTRUCTOR,
+        &salt,
+    ))
+}
+
+/// Address of the stable `token_a`/`token_b` pair created by `factory` from
+/// `stable_pair_code_hash`.
+pub fn stable_pair_address(
+    factory: &AccountId,
+    stable_pair_code_hash: &Hash,
+    token_a: AccountId,
+    token_b: AccountId,
+) -> crate::Result<AccountId> {
+    let (token_0, token_1) = crate::sort_tokens(token_a, token_b)?;
+    let salt = stable_pair_salt(&token_0, &token_1);
+    Ok(contract_address(
+        factory,
+        stable_pair_code_hash,
+        &PAIR_CONSTRUCTOR,
+        &salt,
+    ))
+}
+
+/// Address of the weighted `token_a`/`token_b` pair in which `token_a` weighs `weight_a`,
+/// created by `factory` from `weighted_pair_code_hash`.
+pub fn weighted_pair_address(
+    factory: &AccountId,
+    weighted_pair_code_hash: &Hash,
+    token_a: AccountId,
+    token_b: AccountId,
+    weight_a: u32,
+) -> crate::Result<AccountId> {
+    let (token_0, token_1) = crate::sort_tokens(token_a, token_b)?;
+    if !crate::weighted::is_valid_weight(weight_a) {
+        return Err(crate::MathError::InvalidWeight);
+    }
+    let weight_0 = if token_0 == token_a {
+        weight_a
+    } else {
+        crate::weighted::WEIGHT_DENOMINATOR - weight_a
+    };
+    let salt = weighted_pair_salt(&token_0, &token_1, weight_0);
+    Ok(contract_address(
+        factory,
+        weighted_pair_code_hash,
+        &PAIR_CONSTRUCTOR,
+        &salt,
+    ))
+}
+
+/// Address of the concentrated liquidity `token_a`/`token_b` pool of the `fee` tier,
+/// created by `factory` from `cl_pool_code_hash`.
+pub fn cl_pool_address(
+    factory: &AccountId,
+    cl_pool_code_hash: &Hash,
+    token_a: AccountId,
+    token_b: AccountId,
+    fee: u32,
+) -> crate::Result<AccountId> {
+    let (token_0, token_1) = crate::sort_tokens(token_a, token_b)?;
+    if crate::concentrated::tick_spacing(fee).is_none() {
+        return Err(crate::MathError::InvalidFee);
+    }
+    let salt = cl_pool_salt(&token_0, &token_1, fee);
+    Ok(contract_address(
+        factory,
+        cl_pool_code_hash,
+        &PAIR_CONSTRUCTOR,
+        &salt,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPECTED_ADDRESS: [u8; 32] = [
+        0xdf, 0x30, 0xd7, 0xe7, 0xd6, 0x76, 0x19, 0x2a, 0xcd, 0x0c, 0x3a, 0x89, 0x26, 0x9f, 0xa9,
+        0xf5, 0x43, 0x83, 0x7e, 0xc2, 0x25, 0x10, 0x6e, 0xb9, 0xf6, 0xe8, 0xcb, 0x17, 0xfb, 0xec,
+        0xb8, 0x51,
+    ];
+
+    #[test]
+    fn contract_address_matches_pallet_contracts() {
+        // blake2_256(b"contract_addr_v1" ++ deployer ++ code_hash ++ compact(4) ++ input
+        //            ++ compact(32) ++ salt), as in `DefaultAddressGenerator`.
+        let address = contract_address(
+            &AccountId::from([0x01; 32]),
+            &Hash::from([0x02; 32]),
+            &[0xde, 0xad, 0xbe, 0xef],
+            &[0x03; 32],
+        );
+        assert_eq!(address, AccountId::from(EXPECTED_ADDRESS));
+    }
+
+    #[test]
+    fn pair_address_ignores_token_order() {
+        let factory = AccountId::from([0x01; 32]);
+        let code_hash = Hash::from([0x02; 32]);
+        let token_a = AccountId::from([0x0a; 32]);
+        let token_b = AccountId::from([0x0b; 32]);
+
+        assert_eq!(
+            pair_address(&factory, &code_hash, token_a, token_b),
+            pair_address(&factory, &code_hash, token_b, token_a),
+        );
+        assert_eq!(
+            pair_address(&factory, &code_hash, token_a, token_b).unwrap(),
+            contract_address(
+                &factory,
+                &code_hash,
+                &PAIR_CONSTRUCTOR,
+                &pair_salt(&token_a, &token_b),
+            ),
+        );
+    }
+
+    #[test]
+    fn pair_address_depends_on_factory_and_code() {
+        let token_a = AccountId::from([0x0a; 32]);
+        let token_b = AccountId::from([0x0b; 32]);
+        let address = |factory: u8, code_hash: u8| {
+            pair_address(
+                &AccountId::from([factory; 32]),
+                &Hash::from([code_hash; 32]),
+                token_a,
+                token_b,
+            )
+            .unwrap()
+        };
+
+        assert_ne!(address(1, 2), address(3, 2));
+        assert_ne!(address(1, 2), address(1, 3));
+    }
+
+    #[test]
+    fn stable_pairs_get_their_own_address() {
+        let factory = AccountId::from([0x01; 32]);
+        let code_hash = Hash::from([0x02; 32]);
+        let token_a = AccountId::from([0x0a; 32]);
+        let token_b = AccountId::from([0x0b; 32]);
+
+        assert_eq!(
+            stable_pair_address(&factory, &code_hash, token_a, token_b),
+            stable_pair_address(&factory, &code_hash, token_b, token_a),
+        );
+        assert_ne!(
+            stable_pair_address(&factory, &code_hash, token_a, token_b),
+            pair_address(&factory, &code_hash, token_a, token_b),
+        );
+    }
+
+    #[test]
+    fn weighted_pairs_get_an_address_per_weight() {
+        let factory = AccountId::from([0x01; 32]);
+        let code_hash = Hash::from([0x02; 32]);
+        let token_a = AccountId::from([0x0a; 32]);
+        let token_b = AccountId::from([0x0b; 32]);
+        let address = |token_a, token_b, weight_a| {
+            weighted_pair_address(&factory, &code_hash, token_a, token_b, weight_a).unwrap()
+        };
+
+        assert_eq!(address(token_a, token_b, 80), address(token_b, token_a, 20));
+        assert_ne!(address(token_a, token_b, 80), address(token_a, token_b, 20));
+        assert_ne!(
+            address(token_a, token_b, 50),
+            pair_address(&factory, &code_hash, token_a, token_b).unwrap()
+        );
+        assert_eq!(
+            weighted_pair_address(&factory, &code_hash, token_a, token_b, 100),
+            Err(crate::MathError::InvalidWeight)
+        );
+    }
+
+    #[test]
+    fn cl_pools_get_an_address_per_fee_tier() {
+        let factory = AccountId::from([0x01; 32]);
+        let code_hash = Hash::from([0x02; 32]);
+        let token_a = AccountId::from([0x0a; 32]);
+        let token_b = AccountId::from([0x0b; 32]);
+        let address = |token_a, token_b, fee| {
+            cl_pool_address(&factory, &code_hash, token_a, token_b, fee).unwrap()
+        };
+
+        assert_eq!(
+            address(token_a, token_b, 500),
+            address(token_b, token_a, 500)
+        );
+        assert_ne!(
+            address(token_a, token_b, 500),
+            address(token_a, token_b, 3_000)
+        );
+        assert_eq!(
+            cl_pool_address(&factory, &code_hash, token_a, token_b, 2_500),
+            Err(crate::MathError::InvalidFee)
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Math behind `ClPool`, Uniswap V3's concentrated liquidity: liquidity sits between two
+//! ticks, prices `1.0001^tick`, and only counts while the price is in its range.
+//!
+//! Square roots of prices are kept in Q64.64 in a `u128` rather than V3's Q64.96, which
+//! narrows ticks to `MIN_TICK..=MAX_TICK`, prices from about `2^-64` to `2^64`. Fee growth
+//! per unit of liquidity is Q128.128, and wraps around like V3's.
+
+use primitive_types::{U256, U512};
+
+use crate::{Balance, MathError, Result};
+
+/// Lowest tick, of price `1.0001^MIN_TICK`, about `2^-64`.
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// `sqrt_price_at_tick(MIN_TICK)` and `sqrt_price_at_tick(MAX_TICK)`.
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_017;
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_062;
+
+/// Fees are in millionths of the amount in.
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// Ticks per bitmap word.
+const WORD_BITS: i32 = 128;
+
+/// `2^128 / 1.0001^(2^i / 2)`, rounded up, from V3's `TickMath`.
+const SQRT_POWERS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+];
+
+/// Tick spacing of each fee tier, as in V3; `None` for a fee without a tier.
+pub fn tick_spacing(fee: u32) -> Option<i32> {
+    match fee {
+        500 => Some(10),
+        3_000 => Some(60),
+        10_000 => Some(200),
+        _ => None,
+    }
+}
+
+/// `sqrt(1.0001^tick)` in Q64.64, rounded up.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(MathError::InvalidTick);
+    }
+    let abs = tick.unsigned_abs();
+    // Q128.128 product of `1 / sqrt(1.0001)^(2^i)` over the bits of `abs`.
+    let mut ratio = U256::one() << 128;
+    for (bit, power) in SQRT_POWERS.iter().enumerate() {
+        if abs & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*power)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    let rounding = u128::from(!(ratio & U256::from(u64::MAX)).is_zero());
+    Ok((ratio >> 64).as_u128() + rounding)
+}
+
+/// The greatest tick whose sqrt price is at most `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price) {
+        return Err(MathError::InvalidTick);
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(middle)? <= sqrt_price {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Ok(low)
+}
+
+fn to_balance(amount: U512) -> Result<Balance> {
+    if amount > U512::from(u128::MAX) {
+        return Err(MathError::Overflow);
+    }
+    Ok(amount.low_u128())
+}
+
+fn div_rounding(numerator: U512, denominator: U512, round_up: bool) -> U512 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+/// Token 0 worth `liquidity` between two sqrt prices.
+pub fn amount_0_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<Balance> {
+    let (lower, upper) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    if lower == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    let numerator = (U512::from(liquidity) << 64) * U512::from(upper - lower);
+    let denominator = U512::from(upper) * U512::from(lower);
+    to_balance(div_rounding(numerator, denominator, round_up))
+}
+
+/// Token 1 worth `liquidity` between two sqrt prices.
+pub fn amount_1_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<Balance> {
+    let (lower, upper) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    let numerator = U512::from(liquidity) * U512::from(upper - lower);
+    to_balance(div_rounding(numerator, U512::one() << 64, round_up))
+}
+
+fn to_sqrt_price(value: U512) -> Result<u128> {
+    if value.is_zero() || value > U512::from(u128::MAX) {
+        return Err(MathError::Overflow);
+    }
+    Ok(value.low_u128())
+}
+
+/// Sqrt price after adding or removing `amount` of token 0, rounded up.
+fn next_sqrt_price_from_amount_0(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: Balance,
+    add: bool,
+) -> Result<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+    let numerator = U512::from(liquidity) << 64;
+    let product = U512::from(amount) * U512::from(sqrt_price);
+    let denominator = if add {
+        numerator + product
+    } else if numerator > product {
+        numerator - product
+    } else {
+        return Err(MathError::InsufficientLiquidity);
+    };
+    to_sqrt_price(div_rounding(
+        numerator * U512::from(sqrt_price),
+        denominator,
+        true,
+    ))
+}
+
+/// Sqrt price after adding or removing `amount` of token 1, rounded down.
+fn next_sqrt_price_from_amount_1(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: Balance,
+    add: bool,
+) -> Result<u128> {
+    if liquidity == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let shifted = U512::from(amount) << 64;
+    if add {
+        to_sqrt_price(U512::from(sqrt_price) + shifted / U512::from(liquidity))
+    } else {
+        let quotient = div_rounding(shifted, U512::from(liquidity), true);
+        if quotient >= U512::from(sqrt_price) {
+            return Err(MathError::InsufficientLiquidity);
+        }
+        to_sqrt_price(U512::from(sqrt_price) - quotient)
+    }
+}
+
+/// Sqrt price after `amount_in` comes in, of token 0 when `zero_for_one`.
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: Balance,
+    zero_for_one: bool,
+) -> Result<u128> {
+    if zero_for_one {
+        next_sqrt_price_from_amount_0(sqrt_price, liquidity, amount_in, true)
+    } else {
+        next_sqrt_price_from_amount_1(sqrt_price, liquidity, amount_in, true)
+    }
+}
+
+/// One step of a swap, within a range of constant liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: Balance,
+    pub amount_out: Balance,
+    /// Taken from the amount in on top of `amount_in`.
+    pub fee_amount: Balance,
+}
+
+/// Swaps as much of `amount_remaining` as takes the price from `sqrt_price_current`
+/// towards `sqrt_price_target`, charging `fee` millionths of the amount in.
+pub fn compute_swap_step(
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: Balance,
+    fee: u32,
+) -> Result<SwapStep> {
+    let zero_for_one = sqrt_price_current >= sqrt_price_target;
+    let amount_less_fee = to_balance(
+        U512::from(amount_remaining) * U512::from(FEE_DENOMINATOR - fee)
+            / U512::from(FEE_DENOMINATOR),
+    )?;
+    let amount_in_to_target = if zero_for_one {
+        amount_0_delta(sqrt_price_target, sqrt_price_current, liquidity, true)
+    } else {
+        amount_1_delta(sqrt_price_current, sqrt_price_target, liquidity, true)
+    };
+    // Too much to fit a balance is more than any amount remaining.
+    let amount_in_to_target = match amount_in_to_target {
+        Err(MathError::Overflow) => Balance::MAX,
+        amount => amount?,
+    };
+    let sqrt_price_next = if amount_less_fee >= amount_in_to_target {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price_current, liquidity, amount_less_fee, zero_for_one)?
+    };
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        (
+            if reached_target {
+                amount_in_to_target
+            } else {
+                amount_0_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?
+            },
+            amount_1_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?,
+        )
+    } else {
+        (
+            if reached_target {
+                amount_in_to_target
+            } else {
+                amount_1_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?
+            },
+            amount_0_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?,
+        )
+    };
+    let fee_amount = if reached_target {
+        to_balance(div_rounding(
+            U512::from(amount_in) * U512::from(fee),
+            U512::from(FEE_DENOMINATOR - fee),
+            true,
+        ))?
+    } else {
+        // Whatever is left of the amount, so no dust is stranded.
+        amount_remaining - amount_in
+    };
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Most liquidity `amount_0` and `amount_1` buy between `sqrt_price_lower` and
+/// `sqrt_price_upper` at `sqrt_price`.
+pub fn liquidity_for_amounts(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    amount_0: Balance,
+    amount_1: Balance,
+) -> Result<u128> {
+    if sqrt_price_lower >= sqrt_price_upper {
+        return Err(MathError::InvalidTick);
+    }
+    let for_amount_0 = |lower: u128, upper: u128| {
+        let numerator = U512::from(amount_0) * U512::from(lower) * U512::from(upper);
+        to_balance(numerator / (U512::from(upper - lower) << 64))
+    };
+    let for_amount_1 = |lower: u128, upper: u128| {
+        to_balance((U512::from(amount_1) << 64) / U512::from(upper - lower))
+    };
+    if sqrt_price <= sqrt_price_lower {
+        for_amount_0(sqrt_price_lower, sqrt_price_upper)
+    } else if sqrt_price < sqrt_price_upper {
+        Ok(for_amount_0(sqrt_price, sqrt_price_upper)?
+            .min(for_amount_1(sqrt_price_lower, sqrt_price)?))
+    } else {
+        for_amount_1(sqrt_price_lower, sqrt_price_upper)
+    }
+}
+
+/// `liquidity` plus a signed `delta`.
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    if delta < 0 {
+        liquidity
+            .checked_sub(delta.unsigned_abs())
+            .ok_or(MathError::InsufficientLiquidity)
+    } else {
+        liquidity
+            .checked_add(delta.unsigned_abs())
+            .ok_or(MathError::Overflow)
+    }
+}
+
+/// `tick / tick_spacing`, rounded towards negative infinity.
+fn compress(tick: i32, tick_spacing: i32) -> i32 {
+    tick.div_euclid(tick_spacing)
+}
+
+/// The bitmap word and bit recording whether `tick` is initialized. `tick` must be a
+/// multiple of `tick_spacing`.
+pub fn bitmap_position(tick: i32, tick_spacing: i32) -> (i32, u32) {
+    let compressed = compress(tick, tick_spacing);
+    (
+        compressed.div_euclid(WORD_BITS),
+        compressed.rem_euclid(WORD_BITS) as u32,
+    )
+}
+
+/// The next initialized tick at or below `tick` when `lte`, else above it, looking no
+/// further than the bitmap word `word(position)` holding the start. Returns the word's last
+/// tick in that direction, uninitialized, when there is none.
+pub fn next_initialized_tick(
+    word: impl Fn(i32) -> u128,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> (i32, bool) {
+    let compressed = compress(tick, tick_spacing);
+    if lte {
+        let (position, bit) = (
+            compressed.div_euclid(WORD_BITS),
+            compressed.rem_euclid(WORD_BITS) as u32,
+        );
+        let masked = word(position) & (u128::MAX >> (127 - bit));
+        let next = if masked == 0 {
+            compressed - bit as i32
+        } else {
+            compressed - (bit - (127 - masked.leading_zeros())) as i32
+        };
+        (next * tick_spacing, masked != 0)
+    } else {
+        let compressed = compressed + 1;
+        let (position, bit) = (
+            compressed.div_euclid(WORD_BITS),
+            compressed.rem_euclid(WORD_BITS) as u32,
+        );
+        let masked = word(position) & (u128::MAX << bit);
+        let next = if masked == 0 {
+            compressed + (127 - bit) as i32
+        } else {
+            compressed + (masked.trailing_zeros() - bit) as i32
+        };
+        (next * tick_spacing, masked != 0)
+    }
+}
+
+/// `fee_amount` per unit of `liquidity`, in Q128.128.
+pub fn fee_growth(fee_amount: Balance, liquidity: u128) -> U256 {
+    if liquidity == 0 {
+        return U256::zero();
+    }
+    (U256::from(fee_amount) << 128) / U256::from(liquidity)
+}
+
+/// Fee growth per unit of liquidity between two ticks, from the growth on the far side of
+/// each, as V3's `Tick.getFeeGrowthInside`.
+pub fn fee_growth_inside(
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    global: U256,
+    outside_lower: U256,
+    outside_upper: U256,
+) -> U256 {
+    let below = if tick_current >= tick_lower {
+        outside_lower
+    } else {
+        global.overflowing_sub(outside_lower).0
+    };
+    let above = if tick_current < tick_upper {
+        outside_upper
+    } else {
+        global.overflowing_sub(outside_upper).0
+    };
+    global.overflowing_sub(below).0.overflowing_sub(above).0
+}
+
+/// Fees earned by `liquidity` since fee growth inside its range went from `last` to
+/// `inside`.
+pub fn fees_owed(inside: U256, last: U256, liquidity: u128) -> Balance {
+    let growth = inside.overflowing_sub(last).0;
+    (growth.full_mul(U256::from(liquidity)) >> 128).low_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+    fn to_float(sqrt_price: u128) -> f64 {
+        sqrt_price as f64 / Q64
+    }
+
+    #[test]
+    fn tick_prices_match_floats() {
+        for tick in [MIN_TICK, -200_000, -60, -1, 0, 1, 60, 123_456, MAX_TICK] {
+            let expected = 1.0001f64.powf(tick as f64 / 2.0);
+            let actual = to_float(sqrt_price_at_tick(tick).unwrap());
+            assert!(
+                (actual - expected).abs() <= expected * 1e-10 + 1.0 / Q64,
+                "tick {tick}: {actual} vs {expected}"
+            );
+        }
+        assert_eq!(sqrt_price_at_tick(0), Ok(1 << 64));
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), Ok(MIN_SQRT_PRICE));
+        assert_eq!(sqrt_price_at_tick(MAX_TICK), Ok(MAX_SQRT_PRICE));
+        assert_eq!(
+            sqrt_price_at_tick(MAX_TICK + 1),
+            Err(MathError::InvalidTick)
+        );
+    }
+
+    #[test]
+    fn ticks_round_trip_through_prices() {
+        for tick in [MIN_TICK, -443_635, -887, -1, 0, 1, 887, 443_635, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price), Ok(tick));
+            if tick < MAX_TICK {
+                assert_eq!(tick_at_sqrt_price(sqrt_price + 1), Ok(tick));
+            }
+            if tick > MIN_TICK {
+                assert_eq!(tick_at_sqrt_price(sqrt_price - 1), Ok(tick - 1));
+            }
+        }
+        assert_eq!(
+            tick_at_sqrt_price(MIN_SQRT_PRICE - 1),
+            Err(MathError::InvalidTick)
+        );
+    }
+
+    #[test]
+    fn amounts_of_liquidity_match_floats() {
+        let (lower, upper) = (sqrt_price_at_tick(-600).unwrap(), 1 << 64);
+        let liquidity = 1_000_000_000_000_000_000;
+
+        let amount_0 = amount_0_delta(lower, upper, liquidity, false).unwrap();
+        let amount_1 = amount_1_delta(lower, upper, liquidity, false).unwrap();
+        let expected_0 = liquidity as f64 * (1.0 / to_float(lower) - 1.0);
+        let expected_1 = liquidity as f64 * (1.0 - to_float(lower));
+        assert!((amount_0 as f64 - expected_0).abs() <= expected_0 * 1e-12);
+        assert!((amount_1 as f64 - expected_1).abs() <= expected_1 * 1e-12);
+
+        assert_eq!(
+            amount_0_delta(lower, upper, liquidity, true),
+            Ok(amount_0 + 1)
+        );
+        let bought = liquidity_for_amounts(lower, lower, upper, amount_0 + 1, 0).unwrap();
+        assert!(bought.abs_diff(liquidity) <= liquidity / amount_0 + 1);
+    }
+
+    #[test]
+    fn swap_steps_stop_at_the_target_or_the_amount() {
+        let liquidity = 1_000_000_000_000_000_000;
+        let (price, target) = (1 << 64, sqrt_price_at_tick(-60).unwrap());
+
+        // Not enough to reach the target: all of it is used.
+        let step = compute_swap_step(price, target, liquidity, 1_000_000, 3_000).unwrap();
+        assert!(step.sqrt_price_next > target && step.sqrt_price_next < price);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000_000);
+        assert_eq!(step.fee_amount, 3_000);
+        assert!(step.amount_out < step.amount_in);
+
+        // More than enough: the step ends at the target with some left over.
+        let amount = 10 * liquidity;
+        let step = compute_swap_step(price, target, liquidity, amount, 3_000).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(
+            step.amount_in,
+            amount_0_delta(target, price, liquidity, true).unwrap()
+        );
+        assert!(step.amount_in + step.fee_amount < amount);
+    }
+
+    #[test]
+    fn next_initialized_ticks_are_found_within_a_word() {
+        // Ticks 120 and -60 initialized, with a spacing of 60: compressed 2 and -1.
+        let word = |position: i32| match position {
+            0 => 1 << 2,
+            -1 => 1 << 127,
+            _ => 0,
+        };
+
+        assert_eq!(next_initialized_tick(word, 150, 60, true), (120, true));
+        assert_eq!(next_initialized_tick(word, 120, 60, true), (120, true));
+        assert_eq!(next_initialized_tick(word, 119, 60, true), (0, false));
+        assert_eq!(next_initialized_tick(word, 0, 60, false), (120, true));
+        assert_eq!(
+            next_initialized_tick(word, 120, 60, false),
+            (127 * 60, false)
+        );
+        assert_eq!(next_initialized_tick(word, -1, 60, true), (-60, true));
+        assert_eq!(next_initialized_tick(word, -120, 60, false), (-60, true));
+        assert_eq!(
+            next_initialized_tick(word, -61, 60, true),
+            (-128 * 60, false)
+        );
+        assert_eq!(bitmap_position(-60, 60), (-1, 127));
+    }
+
+    #[test]
+    fn fees_accrue_inside_the_range_only() {
+        let global = fee_growth(1_000, 1_000);
+        assert_eq!(global, U256::one() << 128);
+
+        // Range [-60, 60) with the price inside, all growth inside.
+        let inside = fee_growth_inside(-60, 60, 0, global, U256::zero(), U256::zero());
+        assert_eq!(inside, global);
+        assert_eq!(fees_owed(inside, U256::zero(), 500), 500);
+
+        // Price above the range since it was created: both ticks count all the growth as
+        // below them, none of it inside.
+        let inside = fee_growth_inside(-60, 60, 100, global, global, global);
+        assert_eq!(inside, U256::zero());
+
+        // Growth wraps around like V3's.
+        let wrapped = U256::MAX - U256::from(5);
+        assert_eq!(fees_owed(wrapped.overflowing_add(global).0, wrapped, 7), 7);
+    }
+}
@@ -0,0 +1,204 @@
+//! Math behind the optional dynamic fee of classic pairs, which follows the volatility of
+//! the pair's own oracle within bounds set by the factory.
+//!
+//! Volatility is the relative change between the average prices of consecutive oracle
+//! windows, smoothed so each window halves the weight of the previous estimate. Fees and
+//! volatility are in millionths.
+
+use primitive_types::U512;
+
+use crate::{Balance, MathError, Result};
+
+/// Fees and volatility are in millionths, e.g. 3000 for 0.3%.
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// Highest fee the factory may bound a dynamic fee with (10%).
+pub const MAX_FEE: u32 = 100_000;
+
+/// Length, in milliseconds, of the windows whose average prices are compared (5 minutes).
+pub const WINDOW: u128 = 300_000;
+
+/// Whether `min_fee..=max_fee` are valid bounds for a dynamic fee.
+pub fn is_valid_bounds(min_fee: u32, max_fee: u32) -> bool {
+    min_fee <= max_fee && max_fee <= MAX_FEE
+}
+
+/// Relative change from `previous` to `current`, two Q64.64 prices, saturating at 100%.
+pub fn price_change(previous: u128, current: u128) -> u32 {
+    if previous == 0 {
+        return 0;
+    }
+    let change =
+        U512::from(previous.abs_diff(current)) * U512::from(FEE_DENOMINATOR) / U512::from(previous);
+    change.min(U512::from(FEE_DENOMINATOR)).as_u32()
+}
+
+/// Volatility once a window with a price `change` closes, `windows` windows after the one
+/// `volatility` was measured in.
+pub fn next_volatility(volatility: u32, change: u32, windows: u128) -> u32 {
+    let decayed = volatility.checked_shr(windows.min(32) as u32).unwrap_or(0);
+    decayed + change / 2
+}
+
+/// Fee at `volatility`: `min_fee` plus half the volatility, capped at `max_fee`.
+pub fn fee(volatility: u32, min_fee: u32, max_fee: u32) -> u32 {
+    min_fee.saturating_add(volatility / 2).min(max_fee)
+}
+
+/// Whether the balances left after a swap, net of a `fee` on the amounts sent in, still
+/// cover `reserve_0 * reserve_1`.
+pub fn k_holds(
+    balance_0: Balance,
+    balance_1: Balance,
+    amount_0_in: Balance,
+    amount_1_in: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    fee: u32,
+) -> bool {
+    let adjusted = |balance: Balance, amount_in: Balance| {
+        U512::from(balance) * U512::from(FEE_DENOMINATOR) - U512::from(amount_in) * U512::from(fee)
+    };
+    if amount_0_in > balance_0 || amount_1_in > balance_1 || fee >= FEE_DENOMINATOR {
+        return false;
+    }
+    adjusted(balance_0, amount_0_in) * adjusted(balance_1, amount_1_in)
+        >= U512::from(reserve_0)
+            * U512::from(reserve_1)
+            * U512::from(FEE_DENOMINATOR)
+            * U512::from(FEE_DENOMINATOR)
+}
+
+/// `crate::get_amount_out` for a pair charging `fee`.
+pub fn get_amount_out(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee: u32,
+) -> Result<Balance> {
+    if amount_in == 0 {
+        return Err(MathError::InsufficientInputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    if fee >= FEE_DENOMINATOR {
+        return Err(MathError::InvalidFee);
+    }
+    let amount_in_with_fee = U512::from(amount_in) * U512::from(FEE_DENOMINATOR - fee);
+    let numerator = amount_in_with_fee * U512::from(reserve_out);
+    let denominator = U512::from(reserve_in) * U512::from(FEE_DENOMINATOR) + amount_in_with_fee;
+    Ok((numerator / denominator).as_u128())
+}
+
+/// `crate::get_amount_in` for a pair charging `fee`.
+pub fn get_amount_in(
+    amount_out: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee: u32,
+) -> Result<Balance> {
+    if amount_out == 0 {
+        return Err(MathError::InsufficientOutputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 || amount_out >= reserve_out {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    if fee >= FEE_DENOMINATOR {
+        return Err(MathError::InvalidFee);
+    }
+    let numerator = U512::from(reserve_in) * U512::from(amount_out) * U512::from(FEE_DENOMINATOR);
+    let denominator = U512::from(reserve_out - amount_out) * U512::from(FEE_DENOMINATOR - fee);
+    let amount_in = numerator / denominator + 1;
+    if amount_in > U512::from(u128::MAX) {
+        return Err(MathError::Overflow);
+    }
+    Ok(amount_in.as_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_change_is_relative() {
+        assert_eq!(price_change(1 << 64, 1 << 64), 0);
+        assert_eq!(price_change(100 << 64, 101 << 64), 10_000);
+        assert_eq!(price_change(100 << 64, 99 << 64), 10_000);
+        assert_eq!(price_change(1 << 64, 3 << 64), FEE_DENOMINATOR);
+        assert_eq!(price_change(0, 1 << 64), 0);
+    }
+
+    #[test]
+    fn volatility_decays_per_window() {
+        assert_eq!(next_volatility(0, 10_000, 1), 5_000);
+        assert_eq!(next_volatility(5_000, 10_000, 1), 7_500);
+        assert_eq!(next_volatility(8_000, 0, 3), 1_000);
+        assert_eq!(next_volatility(u32::MAX, 0, 1_000), 0);
+    }
+
+    #[test]
+    fn fee_stays_within_bounds() {
+        assert_eq!(fee(0, 1_000, 10_000), 1_000);
+        assert_eq!(fee(4_000, 1_000, 10_000), 3_000);
+        assert_eq!(fee(u32::MAX, 1_000, 10_000), 10_000);
+        assert!(is_valid_bounds(1_000, 10_000));
+        assert!(!is_valid_bounds(10_000, 1_000));
+        assert!(!is_valid_bounds(0, MAX_FEE + 1));
+    }
+
+    #[test]
+    fn matches_the_classic_fee_at_0_3_percent() {
+        for (amount, reserve_in, reserve_out) in [
+            (1_000, 1_000_000, 2_000_000),
+            (7, 10_000, 9_999),
+            (1 << 40, 1 << 60, 1 << 50),
+        ] {
+            assert_eq!(
+                get_amount_out(amount, reserve_in, reserve_out, 3_000),
+                crate::get_amount_out(amount, reserve_in, reserve_out)
+            );
+            assert_eq!(
+                get_amount_in(amount, reserve_in, reserve_out, 3_000),
+                crate::get_amount_in(amount, reserve_in, reserve_out)
+            );
+        }
+    }
+
+    #[test]
+    fn k_holds_charges_the_fee() {
+        let (reserve_0, reserve_1) = (1_000_000, 1_000_000);
+        let amount_in = 10_000;
+        for fee in [0, 3_000, 50_000] {
+            let amount_out = get_amount_out(amount_in, reserve_0, reserve_1, fee).unwrap();
+            let (balance_0, balance_1) = (reserve_0 + amount_in, reserve_1 - amount_out);
+            assert!(k_holds(
+                balance_0, balance_1, amount_in, 0, reserve_0, reserve_1, fee
+            ));
+            assert!(!k_holds(
+                balance_0,
+                balance_1 - 1,
+                amount_in,
+                0,
+                reserve_0,
+                reserve_1,
+                fee
+            ));
+        }
+    }
+
+    #[test]
+    fn k_holds_for_large_reserves() {
+        let reserve = u128::MAX / 2;
+        assert!(k_holds(reserve, reserve, 0, 0, reserve, reserve, 3_000));
+        assert!(!k_holds(
+            reserve,
+            reserve - 1,
+            0,
+            0,
+            reserve,
+            reserve,
+            3_000
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Pure AMM helpers shared by the contracts, mirroring `UniswapV2Library`.
+
+pub mod address;
+pub mod concentrated;
+pub mod dynamic_fee;
+pub mod pair;
+pub mod stable;
+pub mod weighted;
+
+pub type Balance = u128;
+
+/// Swap fee numerator, charged out of `FEE_DENOMINATOR` (0.3%).
+pub const FEE_NUMERATOR: u128 = 3;
+pub const FEE_DENOMINATOR: u128 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum MathError {
+    IdenticalAddresses,
+    InsufficientAmount,
+    InsufficientInputAmount,
+    InsufficientOutputAmount,
+    InsufficientLiquidity,
+    Overflow,
+    DivisionByZero,
+    InsufficientLiquidityMinted,
+    InsufficientLiquidityBurned,
+    /// A token with more decimals than a stable pair supports.
+    UnsupportedDecimals,
+    /// A token weight a weighted pair does not support.
+    InvalidWeight,
+    /// A tick, or a sqrt price, outside what a concentrated liquidity pool supports.
+    InvalidTick,
+    /// A fee without a concentrated liquidity tier, or at or above 100%.
+    InvalidFee,
+    /// A token the pool does not trade.
+    InvalidToken,
+}
+
+pub type Result<T> = core::result::Result<T, MathError>;
+
+/// Returns the two tokens in the order a pair stores them.
+pub fn sort_tokens<T: Ord>(token_a: T, token_b: T) -> Result<(T, T)> {
+    if token_a == token_b {
+        return Err(MathError::IdenticalAddresses);
+    }
+    if token_a < token_b {
+        Ok((token_a, token_b))
+    } else {
+        Ok((token_b, token_a))
+    }
+}
+
+/// Given some amount of an asset and pair reserves, returns an equivalent amount of the other asset.
+pub fn quote(amount_a: Balance, reserve_a: Balance, reserve_b: Balance) -> Result<Balance> {
+    if amount_a == 0 {
+        return Err(MathError::InsufficientAmount);
+    }
+    if reserve_a == 0 || reserve_b == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    amount_a
+        .checked_mul(reserve_b)
+        .map(|product| product / reserve_a)
+        .ok_or(MathError::Overflow)
+}
+
+/// Given an input amount of an asset and pair reserves, returns the maximum output amount of the other asset.
+pub fn get_amount_out(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+) -> Result<Balance> {
+    if amount_in == 0 {
+        return Err(MathError::InsufficientInputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let amount_in_with_fee = amount_in
+        .checked_mul(FEE_DENOMINATOR - FEE_NUMERATOR)
+        .ok_or(MathError::Overflow)?;
+    let numerator = amount_in_with_fee
+        .checked_mul(reserve_out)
+        .ok_or(MathError::Overflow)?;
+    let denominator = reserve_in
+        .checked_mul(FEE_DENOMINATOR)
+        .and_then(|value| value.checked_add(amount_in_with_fee))
+        .ok_or(MathError::Overflow)?;
+    Ok(numerator / denominator)
+}
+
+/// Given an output amount of an asset and pair reserves, returns a required input amount of the other asset.
+pub fn get_amount_in(
+    amount_out: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+) -> Result<Balance> {
+    if amount_out == 0 {
+        return Err(MathError::InsufficientOutputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 || amount_out >= reserve_out {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let numerator = reserve_in
+        .checked_mul(amount_out)
+        .and_then(|value| value.checked_mul(FEE_DENOMINATOR))
+        .ok_or(MathError::Overflow)?;
+    let denominator = (reserve_out - amount_out)
+        .checked_mul(FEE_DENOMINATOR - FEE_NUMERATOR)
+        .ok_or(MathError::Overflow)?;
+    Ok(numerator / denominator + 1)
+}
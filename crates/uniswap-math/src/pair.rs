@@ -0,0 +1,136 @@
+//! Math behind `Pair`'s liquidity accounting, swap invariant and price oracle.
+//!
+//! Intermediate products are taken in 256 (or 512) bits so none of these overflow for
+//! reserves anywhere in the `u128` range.
+
+use primitive_types::U256;
+
+use crate::{Balance, MathError, Result};
+
+/// LP shares locked away by the first mint so the share price can never be pushed to a
+/// point where a single share is unaffordable.
+pub const MINIMUM_LIQUIDITY: Balance = 1_000;
+
+/// `a * b / c`, rounded down.
+pub fn mul_div(a: Balance, b: Balance, c: Balance) -> Result<Balance> {
+    if c == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    let result = U256::from(a) * U256::from(b) / U256::from(c);
+    if result > U256::from(u128::MAX) {
+        return Err(MathError::Overflow);
+    }
+    Ok(result.as_u128())
+}
+
+/// `sqrt(a * b)`, rounded down.
+pub fn sqrt_product(a: Balance, b: Balance) -> Balance {
+    (U256::from(a) * U256::from(b)).integer_sqrt().as_u128()
+}
+
+/// LP shares owed for depositing `amount_0`/`amount_1` on top of the given reserves.
+/// On the first deposit `MINIMUM_LIQUIDITY` is withheld from the geometric mean.
+pub fn liquidity_minted(
+    amount_0: Balance,
+    amount_1: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    total_supply: Balance,
+) -> Result<Balance> {
+    let liquidity = if total_supply == 0 {
+        sqrt_product(amount_0, amount_1).saturating_sub(MINIMUM_LIQUIDITY)
+    } else {
+        core::cmp::min(
+            mul_div(amount_0, total_supply, reserve_0)?,
+            mul_div(amount_1, total_supply, reserve_1)?,
+        )
+    };
+    if liquidity == 0 {
+        return Err(MathError::InsufficientLiquidityMinted);
+    }
+    Ok(liquidity)
+}
+
+/// Token amounts paid out for burning `liquidity` out of `total_supply` shares.
+pub fn amounts_burned(
+    liquidity: Balance,
+    balance_0: Balance,
+    balance_1: Balance,
+    total_supply: Balance,
+) -> Result<(Balance, Balance)> {
+    let amount_0 = mul_div(liquidity, balance_0, total_supply)?;
+    let amount_1 = mul_div(liquidity, balance_1, total_supply)?;
+    if amount_0 == 0 || amount_1 == 0 {
+        return Err(MathError::InsufficientLiquidityBurned);
+    }
+    Ok((amount_0, amount_1))
+}
+
+/// LP shares minted to `fee_to`: one sixth of the growth of `sqrt(k)` since `root_k_last`.
+pub fn protocol_fee_liquidity(
+    total_supply: Balance,
+    root_k: Balance,
+    root_k_last: Balance,
+) -> Result<Balance> {
+    if root_k <= root_k_last {
+        return Ok(0);
+    }
+    let numerator = U256::from(total_supply) * U256::from(root_k - root_k_last);
+    let denominator = U256::from(root_k) * U256::from(5) + U256::from(root_k_last);
+    let liquidity = numerator / denominator;
+    if liquidity > U256::from(u128::MAX) {
+        return Err(MathError::Overflow);
+    }
+    Ok(liquidity.as_u128())
+}
+
+/// Price of token 0 in token 1 as Q64.64 fixed point, the unit the pair's cumulative
+/// prices are kept in. Saturates at `u128::MAX`.
+pub fn encode_price(reserve_0: Balance, reserve_1: Balance) -> u128 {
+    if reserve_0 == 0 {
+        return 0;
+    }
+    let price = (U256::from(reserve_1) << 64) / U256::from(reserve_0);
+    if price > U256::from(u128::MAX) {
+        return u128::MAX;
+    }
+    price.as_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_does_not_overflow_in_between() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Ok(u128::MAX));
+        assert_eq!(mul_div(u128::MAX, 2, 1), Err(MathError::Overflow));
+        assert_eq!(mul_div(1, 1, 0), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn first_mint_withholds_minimum_liquidity() {
+        assert_eq!(
+            liquidity_minted(1_000_000, 4_000_000, 0, 0, 0),
+            Ok(2_000_000 - MINIMUM_LIQUIDITY)
+        );
+        assert_eq!(
+            liquidity_minted(1_000, 1_000, 0, 0, 0),
+            Err(MathError::InsufficientLiquidityMinted)
+        );
+    }
+
+    #[test]
+    fn protocol_fee_is_a_sixth_of_growth() {
+        // sqrt(k) doubling mints total_supply / 11 shares, i.e. 1/6 of the growth.
+        assert_eq!(protocol_fee_liquidity(1_100, 200, 100), Ok(100));
+        assert_eq!(protocol_fee_liquidity(1_100, 100, 100), Ok(0));
+    }
+
+    #[test]
+    fn encode_price_is_q64() {
+        assert_eq!(encode_price(1, 1), 1 << 64);
+        assert_eq!(encode_price(4, 1), 1 << 62);
+        assert_eq!(encode_price(1, u128::MAX), u128::MAX);
+    }
+}
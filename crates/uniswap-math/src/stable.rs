@@ -0,0 +1,271 @@
+//! Math behind `StablePair`: Solidly's `x³y + y³x >= k` invariant, taken on reserves
+//! scaled to 18 decimals so that tokens of different precision trade close to one to one.
+//!
+//! `k` is taken exactly, in 1024 bits, which hold it for any pair of `u128` reserves.
+//! Rounding it down at each product instead, as Solidly does, lets a pool with a few units
+//! of reserves be drained while its `k` rounds to zero.
+
+use primitive_types::U512;
+
+use crate::{Balance, MathError, Result};
+use wide::U1024;
+
+/// `construct_uint!` names `Result` unqualified, so it expands out of reach of ours.
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod wide {
+    uint::construct_uint! {
+        /// Wide enough for `x³y + y³x` of reserves scaled to 18 decimals.
+        pub struct U1024(16);
+    }
+}
+
+/// Swap fee of stable pairs, charged out of `FEE_DENOMINATOR` (0.04%).
+pub const FEE_NUMERATOR: u128 = 4;
+pub const FEE_DENOMINATOR: u128 = 10_000;
+
+/// Most decimals a token of a stable pair may have.
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Least `k` of the first deposit into a stable pair, in Velodrome's units: `x³y + y³x`
+/// of reserves in 18 decimals, divided by `10^54`.
+pub const MINIMUM_K: u128 = 10_000_000_000;
+
+/// 1.0 in the 18 decimal fixed point reserves are scaled to.
+const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// Newton iterations `get_y` gives up after.
+const MAX_ITERATIONS: usize = 255;
+
+/// `10^decimals`, the amount of a token with `decimals` worth one whole token.
+pub fn precision(decimals: u8) -> Result<Balance> {
+    if decimals > MAX_DECIMALS {
+        return Err(MathError::UnsupportedDecimals);
+    }
+    Ok(10u128.pow(decimals.into()))
+}
+
+/// The part of `amount_in` kept by the pair, rounded up.
+pub fn fee(amount_in: Balance) -> Balance {
+    let fee = (U512::from(amount_in) * U512::from(FEE_NUMERATOR) + U512::from(FEE_DENOMINATOR - 1))
+        / U512::from(FEE_DENOMINATOR);
+    fee.as_u128()
+}
+
+/// `amount` of a token worth `precision` per whole token, in 18 decimals. Exact, as
+/// `precision` divides `ONE`.
+fn scaled(amount: Balance, precision: Balance) -> U1024 {
+    U1024::from(amount) * U1024::from(ONE / precision)
+}
+
+/// The invariant, `x³y + y³x`, on scaled reserves.
+fn k(x: U1024, y: U1024) -> U1024 {
+    x * y * (x * x + y * y)
+}
+
+/// Derivative of `k` in `y`.
+fn d(x0: U1024, y: U1024) -> U1024 {
+    U1024::from(3) * x0 * y * y + x0 * x0 * x0
+}
+
+/// The smallest `y` with `k(x0, y) >= xy`, found by Newton's method from `y`.
+fn get_y(x0: U1024, xy: U1024, mut y: U1024) -> Result<U1024> {
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let k = k(x0, y);
+        let slope = d(x0, y);
+        if slope.is_zero() {
+            return Err(MathError::DivisionByZero);
+        }
+        if k < xy {
+            y += (xy - k) / slope;
+        } else {
+            y -= (k - xy) / slope;
+        }
+        let step = if y > previous {
+            y - previous
+        } else {
+            previous - y
+        };
+        if step <= U1024::one() {
+            break;
+        }
+    }
+    // Newton's method lands within a unit of the root, on either side of it. Settle on
+    // the smallest `y` meeting the invariant.
+    for _ in 0..MAX_ITERATIONS {
+        if k(x0, y) < xy {
+            y += U1024::one();
+        } else if !y.is_zero() && k(x0, y - 1) >= xy {
+            y -= U1024::one();
+        } else {
+            return Ok(y);
+        }
+    }
+    Err(MathError::InsufficientLiquidity)
+}
+
+/// Output of swapping `amount_in` into a stable pair with these reserves, after the fee.
+/// `precision_in` and `precision_out` come from `precision`.
+pub fn get_amount_out(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    precision_in: Balance,
+    precision_out: Balance,
+) -> Result<Balance> {
+    if amount_in == 0 {
+        return Err(MathError::InsufficientInputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let x = scaled(reserve_in, precision_in);
+    let y = scaled(reserve_out, precision_out);
+    let x0 = x + scaled(amount_in - fee(amount_in), precision_in);
+    let y0 = get_y(x0, k(x, y), y)?;
+    if y0 >= y {
+        return Err(MathError::InsufficientOutputAmount);
+    }
+    Ok(((y - y0) / U1024::from(ONE / precision_out)).as_u128())
+}
+
+/// Whether a first deposit of `amount_0` and `amount_1` reaches `MINIMUM_K`, so that a
+/// new pool cannot start out with dust reserves.
+pub fn exceeds_minimum_k(
+    amount_0: Balance,
+    amount_1: Balance,
+    precision_0: Balance,
+    precision_1: Balance,
+) -> bool {
+    let one = U1024::from(ONE);
+    k(scaled(amount_0, precision_0), scaled(amount_1, precision_1))
+        > U1024::from(MINIMUM_K) * one * one * one
+}
+
+/// Whether the balances left after a swap, net of the fee on the amounts sent in, keep
+/// the invariant of the reserves.
+#[allow(clippy::too_many_arguments)]
+pub fn k_holds(
+    balance_0: Balance,
+    balance_1: Balance,
+    amount_0_in: Balance,
+    amount_1_in: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    precision_0: Balance,
+    precision_1: Balance,
+) -> bool {
+    if amount_0_in > balance_0 || amount_1_in > balance_1 {
+        return false;
+    }
+    let adjusted_0 = scaled(balance_0 - fee(amount_0_in), precision_0);
+    let adjusted_1 = scaled(balance_1 - fee(amount_1_in), precision_1);
+    k(adjusted_0, adjusted_1)
+        >= k(
+            scaled(reserve_0, precision_0),
+            scaled(reserve_1, precision_1),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: Balance = 1_000_000_000_000_000_000;
+    const E6: Balance = 1_000_000;
+
+    /// Quotes a swap of token 0 in and checks the pair would accept the quote, but not a
+    /// unit more.
+    fn quote_and_check(amount_in: Balance, reserves: (Balance, Balance), decimals: (u8, u8)) {
+        let (precision_0, precision_1) = (
+            precision(decimals.0).unwrap(),
+            precision(decimals.1).unwrap(),
+        );
+        let (reserve_0, reserve_1) = reserves;
+        let amount_out =
+            get_amount_out(amount_in, reserve_0, reserve_1, precision_0, precision_1).unwrap();
+        let holds = |amount_out: Balance| {
+            k_holds(
+                reserve_0 + amount_in,
+                reserve_1 - amount_out,
+                amount_in,
+                0,
+                reserve_0,
+                reserve_1,
+                precision_0,
+                precision_1,
+            )
+        };
+        assert!(holds(amount_out), "{amount_in} in on {reserves:?}");
+        assert!(!holds(amount_out + 1), "{amount_in} in on {reserves:?}");
+    }
+
+    #[test]
+    fn balanced_pools_trade_near_one_to_one() {
+        let amount_in = 1_000 * E6;
+        let reserves = 1_000_000 * E6;
+
+        let stable = get_amount_out(amount_in, reserves, reserves, E6, E6).unwrap();
+        let constant_product = crate::get_amount_out(amount_in, reserves, reserves).unwrap();
+
+        assert!(stable < amount_in - fee(amount_in));
+        assert!(stable > amount_in - fee(amount_in) - amount_in / 100_000);
+        assert!(stable > constant_product);
+    }
+
+    #[test]
+    fn decimals_are_scaled_away() {
+        // 1M USDC (6 decimals) against 1M DAI (18 decimals).
+        let usdc_for_dai =
+            get_amount_out(1_000 * E18, 1_000_000 * E18, 1_000_000 * E6, E18, E6).unwrap();
+        let dai_for_usdc =
+            get_amount_out(1_000 * E6, 1_000_000 * E6, 1_000_000 * E18, E6, E18).unwrap();
+
+        assert_eq!(usdc_for_dai, dai_for_usdc / 1_000_000_000_000);
+        assert!(usdc_for_dai > 999 * E6 && usdc_for_dai < 1_000 * E6);
+    }
+
+    #[test]
+    fn quotes_keep_the_invariant() {
+        for (amount_in, reserves, decimals) in [
+            (1_000 * E18, (1_000_000 * E18, 1_000_000 * E18), (18, 18)),
+            (1_000_000, (1_000_000 * E18, 1_000_000 * E18), (18, 18)),
+            (500_000 * E18, (1_000_000 * E18, 2_000_000 * E18), (18, 18)),
+            (10_000_000, (3_000_000_000, 2_500_000_000), (6, 6)),
+            (1_000 * E6, (1_000_000 * E6, 1_000_000 * E18), (6, 18)),
+            (7_000 * E18, (90_000 * E18, 5_000 * E6), (18, 6)),
+            (u64::MAX.into(), (u64::MAX.into(), u64::MAX.into()), (0, 0)),
+        ] {
+            quote_and_check(amount_in, reserves, decimals);
+        }
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_priced() {
+        assert_eq!(precision(19), Err(MathError::UnsupportedDecimals));
+        assert_eq!(
+            get_amount_out(0, E18, E18, E18, E18),
+            Err(MathError::InsufficientInputAmount)
+        );
+        assert_eq!(
+            get_amount_out(E18, 0, E18, E18, E18),
+            Err(MathError::InsufficientLiquidity)
+        );
+        assert!(!k_holds(E18, E18, E18 + 1, 0, E18, E18, E18, E18));
+    }
+
+    #[test]
+    fn tiny_reserves_cannot_be_drained() {
+        // 1 in for 1_000 out of a 1_001/1_001 pool, which a `k` rounded at each product
+        // lets through as zero against zero. Even 1 out takes more than 1 in.
+        assert!(!k_holds(1_002, 1, 1, 0, 1_001, 1_001, E18, E18));
+        assert!(!k_holds(1_002, 1_000, 1, 0, 1_001, 1_001, E18, E18));
+        assert!(k_holds(1_004, 1_000, 3, 0, 1_001, 1_001, E18, E18));
+
+        // Nor can such a pool be opened: 2x⁴ / 10^54 > 10^10 from x ≈ 8.4 * 10^15.
+        assert!(!exceeds_minimum_k(1_001, 1_001, E18, E18));
+        assert!(!exceeds_minimum_k(E18 / 1_000, E18 / 1_000, E18, E18));
+        assert!(exceeds_minimum_k(E18 / 100, E18 / 100, E18, E18));
+        assert!(exceeds_minimum_k(E6 / 100, E18 / 100, E6, E18));
+    }
+}
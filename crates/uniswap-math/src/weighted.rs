@@ -0,0 +1,281 @@
+//! Math behind `WeightedPair`: Balancer's weighted product invariant `x^wx * y^wy >= k`,
+//! where the weights of the two tokens add up to `WEIGHT_DENOMINATOR`.
+//!
+//! Powers with fractional exponents are taken as `exp(w * ln(x))` in 18 decimal fixed
+//! point. Both are approximations, so the pair only accepts a swap when the invariant grows
+//! by `MARGIN`, well above their error, and quotes leave room for it.
+
+use primitive_types::U256;
+
+use crate::{Balance, MathError, Result};
+
+/// Weights are in percent of the pool's value.
+pub const WEIGHT_DENOMINATOR: u32 = 100;
+
+/// Smallest weight a token may have.
+pub const MIN_WEIGHT: u32 = 1;
+
+/// 1.0 in the fixed point of `ln` and `exp`.
+const ONE: i128 = 1_000_000_000_000_000_000;
+
+/// `ln(2)`, rounded down.
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// Least growth of `ln` of the invariant, in weight percent times `ONE`, that `k_holds`
+/// accepts. About a `1e-12` relative rise of the invariant.
+const MARGIN: i128 = 100_000_000;
+
+/// `exp` of anything below this is under `1 / ONE`.
+const MIN_EXPONENT: i128 = -42 * ONE;
+
+/// Whether `weight` leaves both tokens of a pair at least `MIN_WEIGHT`.
+pub fn is_valid_weight(weight: u32) -> bool {
+    (MIN_WEIGHT..=WEIGHT_DENOMINATOR - MIN_WEIGHT).contains(&weight)
+}
+
+/// The part of `amount_in` kept by the pair, rounded up. Weighted pairs charge the fee of
+/// classic pairs.
+pub fn fee(amount_in: Balance) -> Balance {
+    let fee = (U256::from(amount_in) * U256::from(crate::FEE_NUMERATOR)
+        + U256::from(crate::FEE_DENOMINATOR - 1))
+        / U256::from(crate::FEE_DENOMINATOR);
+    fee.as_u128()
+}
+
+/// `ln(x)` for `x >= 1`, in fixed point, within a few units of `1 / ONE`.
+fn ln(x: Balance) -> i128 {
+    debug_assert!(x > 0);
+    // x = m * 2^k, with m in [1, 2).
+    let k = 127 - x.leading_zeros();
+    let m = ((U256::from(x) * U256::from(ONE)) >> k).as_u128() as i128;
+    // ln(m) = 2 * atanh(z), with z = (m - 1) / (m + 1) at most 1/3.
+    let z = (m - ONE) * ONE / (m + ONE);
+    let z_squared = z * z / ONE;
+    let (mut sum, mut power, mut n) = (z, z, 1);
+    loop {
+        power = power * z_squared / ONE;
+        n += 2;
+        if power == 0 {
+            break;
+        }
+        sum += power / n;
+    }
+    k as i128 * LN_2 + 2 * sum
+}
+
+/// `exp(x)` in fixed point, for `x` up to `ONE`, rounded down.
+fn exp(x: i128) -> u128 {
+    debug_assert!(x <= ONE);
+    if x < MIN_EXPONENT {
+        return 0;
+    }
+    // x = k * ln(2) + r, with r in [0, ln(2)).
+    let k = x.div_euclid(LN_2);
+    let r = x.rem_euclid(LN_2);
+    let (mut sum, mut term, mut n) = (ONE, ONE, 1);
+    while term > 0 {
+        term = term * r / ONE / n;
+        sum += term;
+        n += 1;
+    }
+    if k >= 0 {
+        (sum << k) as u128
+    } else {
+        (sum >> -k) as u128
+    }
+}
+
+/// `a / b` rounded towards positive infinity.
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -(-a).div_euclid(b)
+}
+
+/// Output of swapping `amount_in` into a weighted pair with these reserves, after the fee.
+/// `weight_in` and `weight_out` are the tokens' weights, adding up to `WEIGHT_DENOMINATOR`.
+pub fn get_amount_out(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    weight_in: u32,
+    weight_out: u32,
+) -> Result<Balance> {
+    if amount_in == 0 {
+        return Err(MathError::InsufficientInputAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let balance_in = (amount_in - fee(amount_in))
+        .checked_add(reserve_in)
+        .ok_or(MathError::Overflow)?;
+    // The least balance out with wi * ln(x' / x) + wo * ln(y' / y) >= 2 * MARGIN, twice what
+    // `k_holds` asks to absorb the error of `ln` and `exp`.
+    let growth_in = i128::from(weight_in) * (ln(balance_in) - ln(reserve_in));
+    let exponent = div_ceil(2 * MARGIN - growth_in, i128::from(weight_out));
+    let ratio = exp(exponent.min(ONE));
+    let balance_out = (U256::from(reserve_out) * U256::from(ratio) + U256::from(ONE - 1))
+        / U256::from(ONE)
+        + U256::one();
+    if balance_out >= U256::from(reserve_out) {
+        return Err(MathError::InsufficientOutputAmount);
+    }
+    Ok(reserve_out - balance_out.as_u128())
+}
+
+/// Whether the balances left after a swap, net of the fee on the amounts sent in, grow
+/// the weighted product of the reserves by at least `MARGIN`.
+#[allow(clippy::too_many_arguments)]
+pub fn k_holds(
+    balance_0: Balance,
+    balance_1: Balance,
+    amount_0_in: Balance,
+    amount_1_in: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    weight_0: u32,
+    weight_1: u32,
+) -> bool {
+    if amount_0_in > balance_0 || amount_1_in > balance_1 || reserve_0 == 0 || reserve_1 == 0 {
+        return false;
+    }
+    let adjusted_0 = balance_0 - fee(amount_0_in);
+    let adjusted_1 = balance_1 - fee(amount_1_in);
+    if adjusted_0 == 0 || adjusted_1 == 0 {
+        return false;
+    }
+    let growth = i128::from(weight_0) * (ln(adjusted_0) - ln(reserve_0))
+        + i128::from(weight_1) * (ln(adjusted_1) - ln(reserve_1));
+    growth >= MARGIN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: Balance = 1_000_000_000_000_000_000;
+
+    fn to_float(fixed: i128) -> f64 {
+        fixed as f64 / ONE as f64
+    }
+
+    #[test]
+    fn ln_and_exp_match_floats() {
+        for x in [1, 2, 3, 10, 1_000_003, E18, u64::MAX.into(), u128::MAX] {
+            let expected = (x as f64).ln();
+            assert!(
+                (to_float(ln(x)) - expected).abs() <= expected.max(1.0) * 1e-15,
+                "ln({x})"
+            );
+        }
+        assert_eq!(ln(1), 0);
+        for x in [-41.5f64, -10.0, -1.0, -0.5, 0.0, 0.25, 1.0] {
+            let fixed = (x * ONE as f64) as i128;
+            let expected = x.exp();
+            assert!(
+                (exp(fixed) as f64 / ONE as f64 - expected).abs() <= expected * 1e-15 + 1e-18,
+                "exp({x})"
+            );
+        }
+        assert_eq!(exp(0), ONE as u128);
+        assert_eq!(exp(MIN_EXPONENT - 1), 0);
+    }
+
+    #[test]
+    fn even_weights_quote_like_the_constant_product() {
+        let reserve = 10 * E18;
+        let amount_in = E18 / 100;
+
+        let weighted = get_amount_out(amount_in, reserve, reserve, 50, 50).unwrap();
+        let constant_product = crate::get_amount_out(amount_in, reserve, reserve).unwrap();
+
+        assert!(weighted <= constant_product);
+        assert!(constant_product - weighted < constant_product / 100_000_000);
+    }
+
+    #[test]
+    fn heavier_tokens_move_the_price_less() {
+        // 80/20 at a spot price of 1: 4M of token 0 against 1M of token 1.
+        let amount_in = 10_000 * E18;
+        let quote = get_amount_out(amount_in, 4_000_000 * E18, 1_000_000 * E18, 80, 20).unwrap();
+
+        // y * (1 - (x / (x + a))^(wx / wy)), with the fee taken out first.
+        let x: f64 = 4_000_000.0;
+        let a = 10_000.0 * 0.997;
+        let expected = 1_000_000.0 * (1.0 - (x / (x + a)).powf(4.0));
+        let quote = quote as f64 / E18 as f64;
+        assert!(
+            (quote - expected).abs() < expected * 1e-8,
+            "{quote} vs {expected}"
+        );
+    }
+
+    #[test]
+    fn quotes_keep_the_invariant() {
+        for (amount_in, reserves, weights) in [
+            (1_000 * E18, (1_000_000 * E18, 1_000_000 * E18), (50, 50)),
+            (10_000 * E18, (4_000_000 * E18, 1_000_000 * E18), (80, 20)),
+            (10_000 * E18, (1_000_000 * E18, 4_000_000 * E18), (20, 80)),
+            (1_000_000, (3_000_000_000, 2_500_000_000), (98, 2)),
+            (5_000_000 * E18, (1_000_000 * E18, 1_000_000 * E18), (1, 99)),
+            (
+                u64::MAX.into(),
+                (u64::MAX.into(), u64::MAX.into()),
+                (60, 40),
+            ),
+        ] {
+            let (reserve_0, reserve_1) = reserves;
+            let amount_out =
+                get_amount_out(amount_in, reserve_0, reserve_1, weights.0, weights.1).unwrap();
+            assert!(
+                k_holds(
+                    reserve_0 + amount_in,
+                    reserve_1 - amount_out,
+                    amount_in,
+                    0,
+                    reserve_0,
+                    reserve_1,
+                    weights.0,
+                    weights.1,
+                ),
+                "{amount_in} in on {reserves:?} at {weights:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_what_breaks_the_invariant() {
+        let reserve = 1_000_000 * E18;
+        let amount_out = get_amount_out(1_000 * E18, reserve, reserve, 80, 20).unwrap();
+        // Taking a thousandth more than quoted shrinks the invariant.
+        assert!(!k_holds(
+            reserve + 1_000 * E18,
+            reserve - amount_out - amount_out / 1_000,
+            1_000 * E18,
+            0,
+            reserve,
+            reserve,
+            80,
+            20,
+        ));
+        // Nothing in, a unit out.
+        assert!(!k_holds(
+            reserve,
+            reserve - 1,
+            0,
+            0,
+            reserve,
+            reserve,
+            50,
+            50
+        ));
+        assert_eq!(
+            get_amount_out(0, reserve, reserve, 50, 50),
+            Err(MathError::InsufficientInputAmount)
+        );
+        assert_eq!(
+            get_amount_out(1, reserve, reserve, 50, 50),
+            Err(MathError::InsufficientOutputAmount)
+        );
+        assert!(is_valid_weight(80) && !is_valid_weight(0) && !is_valid_weight(100));
+    }
+}
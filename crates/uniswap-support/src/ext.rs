@@ -0,0 +1,139 @@
+//! Calls from a contract into PSP22 tokens and the factory. The off-chain environment of
+//! unit tests cannot call other contracts, so contracts swap this module for `mock` there:
+//!
+//! ```ignore
+//! #[cfg(not(test))]
+//! use uniswap_support::ext;
+//! #[cfg(test)]
+//! use uniswap_support::mock as ext;
+//! ```
+//!
+//! Queries return `None` and other calls `false` when the call fails.
+
+use ink::env::{
+    call::{build_call, ExecutionInput, Selector},
+    DefaultEnvironment,
+};
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+type Balance = u128;
+
+/// PSP22 results are only checked for success, so the error is not decoded.
+type TokenResult = core::result::Result<(), ()>;
+
+/// Calls a message of `callee` that takes no arguments.
+pub fn query<T: scale::Decode>(callee: AccountId, selector: [u8; 4]) -> Option<T> {
+    build_call::<DefaultEnvironment>()
+        .call(callee)
+        .gas_limit(0)
+        .exec_input(ExecutionInput::new(Selector::new(selector)))
+        .returns::<T>()
+        .try_invoke()
+        .ok()?
+        .ok()
+}
+
+pub fn balance_of(token: AccountId, owner: AccountId) -> Option<Balance> {
+    build_call::<DefaultEnvironment>()
+        .call(token)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::balance_of")))
+                .push_arg(owner),
+        )
+        .returns::<Balance>()
+        .try_invoke()
+        .ok()?
+        .ok()
+}
+
+pub fn total_supply(token: AccountId) -> Option<Balance> {
+    query(token, ink::selector_bytes!("PSP22::total_supply"))
+}
+
+/// `None` for a token without `PSP22Metadata`.
+pub fn decimals(token: AccountId) -> Option<u8> {
+    query(token, ink::selector_bytes!("PSP22Metadata::token_decimals"))
+}
+
+pub fn transfer(token: AccountId, to: AccountId, value: Balance) -> bool {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                .push_arg(to)
+                .push_arg(value)
+                .push_arg(Vec::<u8>::new()),
+        )
+        .returns::<TokenResult>()
+        .try_invoke();
+
+    matches!(result, Ok(Ok(Ok(()))))
+}
+
+pub fn transfer_from(token: AccountId, from: AccountId, to: AccountId, value: Balance) -> bool {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                .push_arg(from)
+                .push_arg(to)
+                .push_arg(value)
+                .push_arg(Vec::<u8>::new()),
+        )
+        .returns::<TokenResult>()
+        .try_invoke();
+
+    matches!(result, Ok(Ok(Ok(()))))
+}
+
+pub fn mint(token: AccountId, to: AccountId, value: Balance) -> bool {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Mintable::mint")))
+                .push_arg(to)
+                .push_arg(value),
+        )
+        .returns::<TokenResult>()
+        .try_invoke();
+
+    matches!(result, Ok(Ok(Ok(()))))
+}
+
+pub fn burn(token: AccountId, from: AccountId, value: Balance) -> bool {
+    let result = build_call::<DefaultEnvironment>()
+        .call(token)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Burnable::burn")))
+                .push_arg(from)
+                .push_arg(value),
+        )
+        .returns::<TokenResult>()
+        .try_invoke();
+
+    matches!(result, Ok(Ok(Ok(()))))
+}
+
+pub fn fee_to(factory: AccountId) -> Option<AccountId> {
+    query(factory, ink::selector_bytes!("get_fee_to"))
+}
+
+/// Whether the factory pauses `pair`. A factory that cannot be asked pauses nothing.
+pub fn is_paused(factory: AccountId, pair: AccountId) -> bool {
+    let result = build_call::<DefaultEnvironment>()
+        .call(factory)
+        .gas_limit(0)
+        .exec_input(
+            ExecutionInput::new(Selector::new(ink::selector_bytes!("is_paused"))).push_arg(pair),
+        )
+        .returns::<bool>()
+        .try_invoke();
+
+    matches!(result, Ok(Ok(true)))
+}
@@ -0,0 +1,38 @@
+//! Storage layout checks. `set_code` keeps a contract's storage, so the code it installs
+//! must read the packed fields in the same order and find every other field under the
+//! same key.
+
+use ink::metadata::layout::Layout;
+use ink::storage::traits::{StorageKey, StorageLayout};
+
+/// Asserts that `T` packs exactly `packed`, in order, and still stores each of `root` in
+/// its own cell under the given key. The packed fields of a deployed contract must never
+/// change: new fields need their own cell (`Lazy` or `Mapping`) and only get added to
+/// `root`.
+pub fn assert_upgrade_compatible<T: StorageLayout + StorageKey>(
+    packed: &[&str],
+    root: &[(&str, u32)],
+) {
+    let layout = T::layout(&T::KEY);
+    let storage = match &layout {
+        Layout::Root(root) => root.layout(),
+        layout => layout,
+    };
+    let Layout::Struct(storage) = storage else {
+        panic!("storage is not a struct")
+    };
+
+    let mut packed_fields = Vec::new();
+    let mut cells = Vec::new();
+    for field in storage.fields() {
+        match field.layout() {
+            Layout::Root(cell) => cells.push((*field.name(), *cell.root_key().key())),
+            _ => packed_fields.push(*field.name()),
+        }
+    }
+
+    assert_eq!(packed_fields, packed);
+    for field in root {
+        assert!(cells.contains(field), "{} moved or was removed", field.0);
+    }
+}
@@ -0,0 +1,12 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//! Code shared by the AMM contracts.
+//!
+//! `ext` calls PSP22 tokens and the factory from a contract. With `test-support`, `mock`
+//! stands in for it in unit tests and `layout` checks a contract's storage against what is
+//! already deployed.
+
+pub mod ext;
+#[cfg(feature = "test-support")]
+pub mod layout;
+#[cfg(feature = "test-support")]
+pub mod mock;
@@ -0,0 +1,145 @@
+//! In-memory PSP22 ledgers and factory state standing in for `ext` in unit tests, with
+//! the same functions plus setters for the state they read. State is per thread, as each
+//! `#[ink::test]` runs on its own.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use ink::primitives::AccountId;
+
+type Balance = u128;
+
+#[derive(Default, Clone)]
+struct State {
+    balances: BTreeMap<(AccountId, AccountId), Balance>,
+    supplies: BTreeMap<AccountId, Balance>,
+    decimals: BTreeMap<AccountId, u8>,
+    fee_to: Option<AccountId>,
+    paused: bool,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// `balance_of`, unwrapped for assertions.
+pub fn balance(token: impl Into<AccountId>, owner: AccountId) -> Balance {
+    let token = token.into();
+    STATE.with(|state| {
+        state
+            .borrow()
+            .balances
+            .get(&(token, owner))
+            .copied()
+            .unwrap_or(0)
+    })
+}
+
+pub fn balance_of(token: AccountId, owner: AccountId) -> Option<Balance> {
+    Some(balance(token, owner))
+}
+
+pub fn total_supply(token: AccountId) -> Option<Balance> {
+    Some(STATE.with(|state| state.borrow().supplies.get(&token).copied().unwrap_or(0)))
+}
+
+/// `None` until `set_decimals` gives `token` some.
+pub fn decimals(token: AccountId) -> Option<u8> {
+    STATE.with(|state| state.borrow().decimals.get(&token).copied())
+}
+
+/// Transfers from the contract being called, like `PSP22::transfer` would.
+pub fn transfer(token: AccountId, to: AccountId, value: Balance) -> bool {
+    let from = ink::env::account_id::<ink::env::DefaultEnvironment>();
+    send(token, from, to, value)
+}
+
+/// Allowances are not tracked: any account may be pulled from.
+pub fn transfer_from(token: AccountId, from: AccountId, to: AccountId, value: Balance) -> bool {
+    send(token, from, to, value)
+}
+
+pub fn mint(token: AccountId, to: AccountId, value: Balance) -> bool {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        *state.balances.entry((token, to)).or_default() += value;
+        *state.supplies.entry(token).or_default() += value;
+    });
+    true
+}
+
+pub fn burn(token: AccountId, from: AccountId, value: Balance) -> bool {
+    if balance(token, from) < value {
+        return false;
+    }
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        *state.balances.entry((token, from)).or_default() -= value;
+        *state.supplies.entry(token).or_default() -= value;
+    });
+    true
+}
+
+/// The zero address until `set_fee_to` sets one, as the factory reports it.
+pub fn fee_to(_factory: AccountId) -> Option<AccountId> {
+    Some(
+        STATE
+            .with(|state| state.borrow().fee_to)
+            .unwrap_or(AccountId::from([0x0; 32])),
+    )
+}
+
+pub fn is_paused(_factory: AccountId, _pair: AccountId) -> bool {
+    STATE.with(|state| state.borrow().paused)
+}
+
+/// Moves `value` of `token` between any two accounts, e.g. a user paying a pair.
+pub fn send(token: AccountId, from: AccountId, to: AccountId, value: Balance) -> bool {
+    if balance(token, from) < value {
+        return false;
+    }
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        *state.balances.entry((token, from)).or_default() -= value;
+        *state.balances.entry((token, to)).or_default() += value;
+    });
+    true
+}
+
+/// Has the caller send `value` of `token` to `to`, as a user pays a pair before calling
+/// it. Panics if the caller holds too little.
+pub fn pay(token: impl Into<AccountId>, to: impl Into<AccountId>, value: Balance) {
+    let from = ink::env::caller::<ink::env::DefaultEnvironment>();
+    assert!(
+        send(token.into(), from, to.into(), value),
+        "the caller cannot pay {value}"
+    );
+}
+
+pub fn set_decimals(token: AccountId, decimals: u8) {
+    STATE.with(|state| state.borrow_mut().decimals.insert(token, decimals));
+}
+
+pub fn set_fee_to(fee_to: Option<AccountId>) {
+    STATE.with(|state| state.borrow_mut().fee_to = fee_to);
+}
+
+pub fn set_paused(paused: bool) {
+    STATE.with(|state| state.borrow_mut().paused = paused);
+}
+
+/// Runs `call` like a contract call that reverts if it panics, putting balances and
+/// factory state back as they were.
+pub fn revert_on_panic<R>(call: impl FnOnce() -> R) -> std::thread::Result<R> {
+    let snapshot = STATE.with(|state| state.borrow().clone());
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(call));
+    if result.is_err() {
+        STATE.with(|state| *state.borrow_mut() = snapshot);
+    }
+    result
+}
+
+/// Forgets all balances and factory state, for tests running several cases per thread.
+pub fn reset() {
+    STATE.with(|state| *state.borrow_mut() = State::default());
+}
@@ -0,0 +1,79 @@
+import { expect, use } from "chai";
+import chaiAsPromised from "chai-as-promised";
+import CoreFactory from "../../typedContracts/core/constructors/core";
+import Core from "../../typedContracts/core/contracts/core";
+import PairFactory from "../../typedContracts/pair/constructors/pair";
+import Psp22tokenFactory from "../../typedContracts/psp22token/constructors/psp22token";
+import pairAbi from "../../artifacts/pair/pair.json";
+import psp22tokenAbi from "../../artifacts/psp22token/psp22token.json";
+import { ApiPromise, WsProvider, Keyring } from "@polkadot/api";
+import { KeyringPair } from "@polkadot/keyring/types";
+
+use(chaiAsPromised);
+
+// Create a new instance of contract
+const wsProvider = new WsProvider("ws://127.0.0.1:9944");
+// Create a keyring instance
+const keyring = new Keyring({ type: "sr25519" });
+
+describe("core test", () => {
+  let api: ApiPromise;
+  let deployer: KeyringPair;
+  let contract: Core;
+  let tokenA: string;
+  let tokenB: string;
+  const maxSupply = 10000000000;
+
+  before(async function setup(): Promise<void> {
+    api = await ApiPromise.create({ provider: wsProvider });
+    deployer = keyring.addFromUri("//Alice");
+
+    const psp22tokenFactory = new Psp22tokenFactory(api, deployer);
+    const pairFactory = new PairFactory(api, deployer);
+    const coreFactory = new CoreFactory(api, deployer);
+
+    tokenA = (await psp22tokenFactory.new(maxSupply, "Token A", "TKA", 18)).address;
+    tokenB = (await psp22tokenFactory.new(maxSupply, "Token B", "TKB", 18)).address;
+    // Instantiating once uploads the pair code the factory deploys from
+    await pairFactory.create();
+
+    contract = new Core(
+      (
+        await coreFactory.new(
+          deployer.address,
+          pairAbi.source.hash,
+          psp22tokenAbi.source.hash
+        )
+      ).address,
+      deployer,
+      api
+    );
+  });
+
+  after(async function tearDown() {
+    await api.disconnect();
+  });
+
+  it("Computes the pair address before it is created", async () => {
+    const computed = (await contract.query.computePairAddress(tokenA, tokenB)).value.ok;
+
+    const { gasRequired } = await contract
+      .withSigner(deployer)
+      .query.createPair(tokenA, tokenB);
+    await contract.tx.createPair(tokenA, tokenB, { gasLimit: gasRequired });
+
+    expect((await contract.query.getPair(tokenA, tokenB)).value.ok).to.equal(computed);
+  });
+
+  it("Computes the same address for either token order", async () => {
+    expect(
+      (await contract.query.computePairAddress(tokenB, tokenA)).value.ok
+    ).to.equal((await contract.query.computePairAddress(tokenA, tokenB)).value.ok);
+  });
+
+  it("Can not create the same pair twice", async () => {
+    await expect(
+      contract.tx.createPair(tokenB, tokenA)
+    ).to.eventually.be.rejected;
+  });
+});
@@ -0,0 +1,121 @@
+import { expect, use } from "chai";
+import chaiAsPromised from "chai-as-promised";
+import DeflationaryTokenFactory from "../../typedContracts/deflationary_token/constructors/deflationary_token";
+import DeflationaryToken from "../../typedContracts/deflationary_token/contracts/deflationary_token";
+import Psp22tokenFactory from "../../typedContracts/psp22token/constructors/psp22token";
+import Psp22token from "../../typedContracts/psp22token/contracts/psp22token";
+import PairFactory from "../../typedContracts/pair/constructors/pair";
+import Pair from "../../typedContracts/pair/contracts/pair";
+import { ApiPromise, WsProvider, Keyring } from "@polkadot/api";
+import { KeyringPair } from "@polkadot/keyring/types";
+
+use(chaiAsPromised);
+
+// Create a new instance of contract
+const wsProvider = new WsProvider("ws://127.0.0.1:9944");
+// Create a keyring instance
+const keyring = new Keyring({ type: "sr25519" });
+
+// Mirrors `uniswap_math::get_amount_out`
+const getAmountOut = (amountIn: number, reserveIn: number, reserveOut: number) =>
+  Math.floor((amountIn * 997 * reserveOut) / (reserveIn * 1000 + amountIn * 997));
+
+describe("deflationary_token test", () => {
+  let api: ApiPromise;
+  let deployer: KeyringPair;
+  let wallet1: KeyringPair;
+  let feeToken: DeflationaryToken;
+  let token: Psp22token;
+  let pair: Pair;
+  const maxSupply = 10000000000;
+  const burnBps = 100;
+  const reserve = 1000000;
+
+  const afterBurn = (amount: number) =>
+    amount - Math.floor((amount * burnBps) / 10000);
+
+  before(async function setup(): Promise<void> {
+    api = await ApiPromise.create({ provider: wsProvider });
+    deployer = keyring.addFromUri("//Alice");
+    wallet1 = keyring.addFromUri("//Bob");
+
+    const feeTokenFactory = new DeflationaryTokenFactory(api, deployer);
+    const psp22tokenFactory = new Psp22tokenFactory(api, deployer);
+    const pairFactory = new PairFactory(api, deployer);
+
+    feeToken = new DeflationaryToken(
+      (await feeTokenFactory.new(maxSupply, "Fee", "FEE", 18, burnBps)).address,
+      deployer,
+      api
+    );
+    token = new Psp22token(
+      (await psp22tokenFactory.new(maxSupply, "Token", "TKN", 18)).address,
+      deployer,
+      api
+    );
+    const lpToken = await psp22tokenFactory.new(0, "LP", "LP", 18);
+
+    pair = new Pair(
+      (
+        await pairFactory.new(
+          deployer.address,
+          lpToken.address,
+          feeToken.address,
+          token.address
+        )
+      ).address,
+      deployer,
+      api
+    );
+
+    await feeToken.tx.transfer(pair.address, reserve, []);
+    await token.tx.transfer(pair.address, reserve, []);
+    await pair.tx.sync();
+  });
+
+  after(async function tearDown() {
+    await api.disconnect();
+  });
+
+  it("Burns a share of every transfer", async () => {
+    const transferredAmount = 10000;
+    const supplyBefore = (await feeToken.query.totalSupply()).value.ok?.rawNumber.toNumber()!;
+
+    await feeToken.tx.transfer(wallet1.address, transferredAmount, []);
+
+    expect(
+      (await feeToken.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(afterBurn(transferredAmount));
+    expect(
+      (await feeToken.query.totalSupply()).value.ok?.rawNumber.toNumber()
+    ).to.equal(supplyBefore - (transferredAmount - afterBurn(transferredAmount)));
+  });
+
+  it("Pair reserves track the amount actually received", async () => {
+    const [reserve0] = (await pair.query.getReserves()).value.ok!;
+    expect(Number(reserve0)).to.equal(afterBurn(reserve));
+  });
+
+  it("Pair prices swaps from the balance delta", async () => {
+    const amountIn = 10000;
+    const [reserve0, reserve1] = (await pair.query.getReserves()).value.ok!;
+    const received = afterBurn(amountIn);
+
+    await feeToken.tx.transfer(pair.address, amountIn, []);
+
+    // Quoting on the nominal amount ignores the burn and breaks K
+    const nominalOut = getAmountOut(amountIn, Number(reserve0), Number(reserve1));
+    await expect(
+      pair.tx.swap(0, nominalOut, wallet1.address, [])
+    ).to.eventually.be.rejected;
+
+    const amountOut = getAmountOut(received, Number(reserve0), Number(reserve1));
+    await expect(
+      pair.tx.swap(0, amountOut, wallet1.address, [])
+    ).to.eventually.be.fulfilled;
+
+    expect(
+      (await token.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(amountOut);
+  });
+});
@@ -2,6 +2,8 @@ import { expect, use } from "chai";
 import chaiAsPromised from "chai-as-promised";
 import PairFactory from "../../typedContracts/pair/constructors/pair";
 import Pair from "../../typedContracts/pair/contracts/pair";
+import Psp22tokenFactory from "../../typedContracts/psp22token/constructors/psp22token";
+import Psp22token from "../../typedContracts/psp22token/contracts/psp22token";
 import { ApiPromise, WsProvider, Keyring } from "@polkadot/api";
 import { KeyringPair } from "@polkadot/keyring/types";
 
@@ -12,22 +14,55 @@ const wsProvider = new WsProvider("ws://127.0.0.1:9944");
 // Create a keyring instance
 const keyring = new Keyring({ type: "sr25519" });
 
+// Mirrors `uniswap_math::get_amount_out`
+const getAmountOut = (amountIn: number, reserveIn: number, reserveOut: number) =>
+  Math.floor((amountIn * 997 * reserveOut) / (reserveIn * 1000 + amountIn * 997));
+
 describe("pair test", () => {
-  let pairFactory: PairFactory;
   let api: ApiPromise;
   let deployer: KeyringPair;
-  
+  let wallet1: KeyringPair;
+  let token0: Psp22token;
+  let token1: Psp22token;
+  let lpToken: Psp22token;
   let contract: Pair;
-  const initialState = true;
+  const maxSupply = 10000000000;
+  const minimumLiquidity = 1000;
 
   before(async function setup(): Promise<void> {
     api = await ApiPromise.create({ provider: wsProvider });
     deployer = keyring.addFromUri("//Alice");
+    wallet1 = keyring.addFromUri("//Bob");
+
+    const psp22tokenFactory = new Psp22tokenFactory(api, deployer);
+    const pairFactory = new PairFactory(api, deployer);
 
-    pairFactory = new PairFactory(api, deployer);
+    token0 = new Psp22token(
+      (await psp22tokenFactory.new(maxSupply, "Token 0", "TK0", 18)).address,
+      deployer,
+      api
+    );
+    token1 = new Psp22token(
+      (await psp22tokenFactory.new(maxSupply, "Token 1", "TK1", 18)).address,
+      deployer,
+      api
+    );
+    lpToken = new Psp22token(
+      (await psp22tokenFactory.new(0, "LP", "LP", 18)).address,
+      deployer,
+      api
+    );
 
+    // Without a factory contract there is no protocol fee and the pair is never paused
     contract = new Pair(
-      (await pairFactory.new(initialState)).address,
+      (
+        await pairFactory.new(
+          deployer.address,
+          lpToken.address,
+          token0.address,
+          token1.address
+        )
+      ).address,
       deployer,
       api
     );
@@ -37,17 +72,46 @@ describe("pair test", () => {
     await api.disconnect();
   });
 
-  it("Sets the initial state", async () => {
-    expect((await contract.query.get()).value.ok).to.equal(initialState);
+  it("Mints liquidity for the first deposit", async () => {
+    await token0.tx.transfer(contract.address, 1000000, []);
+    await token1.tx.transfer(contract.address, 4000000, []);
+    await contract.tx.mint(deployer.address);
+
+    expect(
+      (await lpToken.query.balanceOf(deployer.address)).value.ok?.toNumber()
+    ).to.equal(2000000 - minimumLiquidity);
+    const [reserve0, reserve1] = (await contract.query.getReserves()).value.ok!;
+    expect(Number(reserve0)).to.equal(1000000);
+    expect(Number(reserve1)).to.equal(4000000);
+  });
+
+  it("Swaps token 0 for token 1", async () => {
+    const amountIn = 10000;
+    const amountOut = getAmountOut(amountIn, 1000000, 4000000);
+
+    await token0.tx.transfer(contract.address, amountIn, []);
+    await expect(
+      contract.tx.swap(0, amountOut + 1, wallet1.address, [])
+    ).to.eventually.be.rejected;
+    await contract.tx.swap(0, amountOut, wallet1.address, []);
+
+    expect(
+      (await token1.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(amountOut);
   });
 
-  it("Can flip the state", async () => {
-    const { gasRequired } = await contract.withSigner(deployer).query.flip();
+  it("Burns liquidity for a share of the reserves", async () => {
+    const liquidity = (await lpToken.query.balanceOf(deployer.address)).value.ok!.toNumber();
+    const [reserve0] = (await contract.query.getReserves()).value.ok!;
 
-    await contract.withSigner(deployer).tx.flip({
-      gasLimit: gasRequired,
-    });
+    await lpToken.tx.transfer(contract.address, liquidity, []);
+    await contract.tx.burn(wallet1.address);
 
-    await expect((await contract.query.get()).value.ok).to.be.equal(!initialState);
+    expect(
+      (await token0.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(Math.floor((liquidity * Number(reserve0)) / 2000000));
+    expect(
+      (await lpToken.query.totalSupply()).value.ok?.rawNumber.toNumber()
+    ).to.equal(minimumLiquidity);
   });
 });
@@ -0,0 +1,85 @@
+import { expect, use } from "chai";
+import chaiAsPromised from "chai-as-promised";
+import WnativeFactory from "../../typedContracts/wnative/constructors/wnative";
+import Wnative from "../../typedContracts/wnative/contracts/wnative";
+import { ApiPromise, WsProvider, Keyring } from "@polkadot/api";
+import { KeyringPair } from "@polkadot/keyring/types";
+
+use(chaiAsPromised);
+
+// Create a new instance of contract
+const wsProvider = new WsProvider("ws://127.0.0.1:9944");
+// Create a keyring instance
+const keyring = new Keyring({ type: "sr25519" });
+
+describe("wnative test", () => {
+  let wnativeFactory: WnativeFactory;
+  let api: ApiPromise;
+  let deployer: KeyringPair;
+  let wallet1: KeyringPair;
+  let contract: Wnative;
+  const depositAmount = 1000000;
+
+  before(async function setup(): Promise<void> {
+    api = await ApiPromise.create({ provider: wsProvider });
+    deployer = keyring.addFromUri("//Alice");
+    wallet1 = keyring.addFromUri("//Bob");
+
+    wnativeFactory = new WnativeFactory(api, deployer);
+
+    contract = new Wnative(
+      (await wnativeFactory.new("Wrapped Shibuya", "WSBY", 18)).address,
+      deployer,
+      api
+    );
+  });
+
+  after(async function tearDown() {
+    await api.disconnect();
+  });
+
+  it("Mints wrapped tokens for deposited value", async () => {
+    const { gasRequired } = await contract
+      .withSigner(wallet1)
+      .query.deposit({ value: depositAmount });
+
+    await contract.withSigner(wallet1).tx.deposit({
+      gasLimit: gasRequired,
+      value: depositAmount,
+    });
+
+    expect(
+      (await contract.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(depositAmount);
+    expect(
+      (await contract.query.totalSupply()).value.ok?.rawNumber.toNumber()
+    ).to.equal(depositAmount);
+  });
+
+  it("Burns wrapped tokens on withdraw", async () => {
+    const withdrawAmount = 400000;
+    const { gasRequired } = await contract
+      .withSigner(wallet1)
+      .query.withdraw(withdrawAmount);
+
+    await contract.withSigner(wallet1).tx.withdraw(withdrawAmount, {
+      gasLimit: gasRequired,
+    });
+
+    expect(
+      (await contract.query.balanceOf(wallet1.address)).value.ok?.toNumber()
+    ).to.equal(depositAmount - withdrawAmount);
+  });
+
+  it("Can not withdraw more than the balance", async () => {
+    const { gasRequired } = await contract
+      .withSigner(wallet1)
+      .query.withdraw(depositAmount);
+
+    await expect(
+      contract.withSigner(wallet1).tx.withdraw(depositAmount, {
+        gasLimit: gasRequired,
+      })
+    ).to.eventually.be.rejected;
+  });
+});